@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use super::ENV_PREFIX;
+///
+/// Command line usage
+pub const USAGE: &str = "Usage: office-notice [--config <config.yaml>] [import-json [<assets dir>]]";
+///
+/// Command line arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    /// Path to the config file, `--config`, `OFFICE_NOTICE_CONFIG` or `./config.yaml`
+    pub config: PathBuf,
+    pub command: CliCommand,
+}
+///
+/// Command to be executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// Run the bot
+    Run,
+    /// Import JSON files into the SQLite database, from the directory if specified, or from the configured data files
+    ImportJson(Option<PathBuf>),
+}
+//
+//
+impl CliArgs {
+    ///
+    /// Parses command line arguments (without program name)
+    /// - `env_config` - value of the `OFFICE_NOTICE_CONFIG` environment variable
+    pub fn parse(args: impl IntoIterator<Item = String>, env_config: Option<String>) -> Result<Self, String> {
+        let mut config = None;
+        let mut command = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" | "-c" => match args.next() {
+                    Some(path) => config = Some(PathBuf::from(path)),
+                    None => return Err("CliArgs.parse | Missing value of the '--config'".to_owned()),
+                },
+                _ if arg.starts_with("--config=") => config = Some(PathBuf::from(&arg["--config=".len()..])),
+                "import-json" if command.is_none() => command = Some(CliCommand::ImportJson(None)),
+                _ if !arg.starts_with('-') && command == Some(CliCommand::ImportJson(None)) => {
+                    command = Some(CliCommand::ImportJson(Some(PathBuf::from(arg))));
+                }
+                _ => return Err(format!("CliArgs.parse | Unknown argument '{}'", arg)),
+            }
+        }
+        Ok(Self {
+            config: config
+                .or(env_config.map(PathBuf::from))
+                .unwrap_or(PathBuf::from("./config.yaml")),
+            command: command.unwrap_or(CliCommand::Run),
+        })
+    }
+    ///
+    /// Parses arguments of the current process
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1), std::env::var(format!("{}CONFIG", ENV_PREFIX)).ok())
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::{CliArgs, CliCommand};
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+    #[test]
+    fn parse() {
+        let parsed = CliArgs::parse(args(&[]), None).unwrap();
+        assert_eq!(parsed, CliArgs { config: PathBuf::from("./config.yaml"), command: CliCommand::Run });
+        let parsed = CliArgs::parse(args(&[]), Some("env.yaml".to_owned())).unwrap();
+        assert_eq!(parsed.config, PathBuf::from("env.yaml"));
+        let parsed = CliArgs::parse(args(&["--config", "test.yaml"]), Some("env.yaml".to_owned())).unwrap();
+        assert_eq!(parsed.config, PathBuf::from("test.yaml"));
+        let parsed = CliArgs::parse(args(&["--config=test.yaml", "import-json", "./old"]), None).unwrap();
+        assert_eq!(parsed, CliArgs { config: PathBuf::from("test.yaml"), command: CliCommand::ImportJson(Some(PathBuf::from("./old"))) });
+        assert!(CliArgs::parse(args(&["--config"]), None).is_err());
+        assert!(CliArgs::parse(args(&["--unknown"]), None).is_err());
+    }
+}
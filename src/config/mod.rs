@@ -1,10 +1,21 @@
-use std::{fs, path::Path};
+pub mod cli_args;
+use std::{fs, path::{Path, PathBuf}};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 ///
+/// Prefix of the environment variables overriding the config values
+pub const ENV_PREFIX: &str = "OFFICE_NOTICE_";
+///
 /// App configuration
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AppConfig {
     pub bot: BotConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub data: DataConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
 }
 impl AppConfig {
     ///
@@ -29,6 +40,43 @@ impl AppConfig {
             }
         }
     }
+    ///
+    /// Overrides config values with `OFFICE_NOTICE_*` variables, returned by `var`:
+    /// - `OFFICE_NOTICE_BOT_NAME`, `OFFICE_NOTICE_BOT_TOKEN`
+    /// - `OFFICE_NOTICE_STORAGE` - `json` or `sqlite`
+    /// - `OFFICE_NOTICE_DATA_DIR`, `OFFICE_NOTICE_USERS`, `OFFICE_NOTICE_SUBSCRIPTIONS`, `OFFICE_NOTICE_LINKS`,
+    ///   `OFFICE_NOTICE_USER_ROLES`, `OFFICE_NOTICE_DIALOGUES`, `OFFICE_NOTICE_DATABASE`, `OFFICE_NOTICE_SCHEDULED`,
+    ///   `OFFICE_NOTICE_RECURRING`, `OFFICE_NOTICE_SENT`, `OFFICE_NOTICE_TEMPLATES`
+    /// - `OFFICE_NOTICE_TIME_ZONE` - like `Europe/Moscow`
+    pub fn override_with(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(&format!("{}{}", ENV_PREFIX, name));
+        if let Some(name) = var("BOT_NAME") { self.bot.connection.name = name; }
+        if let Some(token) = var("BOT_TOKEN") { self.bot.connection.token = token; }
+        if let Some(kind) = var("STORAGE") {
+            match serde_yaml::from_str(&kind) {
+                Ok(kind) => self.storage.kind = kind,
+                Err(err) => panic!("AppConfig.override_with | Unknown storage kind '{}', error: {:?}", kind, err),
+            }
+        }
+        if let Some(dir) = var("DATA_DIR") { self.data.dir = dir.into(); }
+        if let Some(path) = var("USERS") { self.data.users = path.into(); }
+        if let Some(path) = var("SUBSCRIPTIONS") { self.data.subscriptions = path.into(); }
+        if let Some(path) = var("LINKS") { self.data.links = path.into(); }
+        if let Some(path) = var("USER_ROLES") { self.data.user_roles = path.into(); }
+        if let Some(path) = var("DIALOGUES") { self.data.dialogues = path.into(); }
+        if let Some(path) = var("DATABASE") { self.data.database = path.into(); }
+        if let Some(path) = var("SCHEDULED") { self.data.scheduled = path.into(); }
+        if let Some(path) = var("RECURRING") { self.data.recurring = path.into(); }
+        if let Some(path) = var("SENT") { self.data.sent = path.into(); }
+        if let Some(path) = var("TEMPLATES") { self.data.templates = path.into(); }
+        if let Some(time_zone) = var("TIME_ZONE") {
+            match time_zone.parse() {
+                Ok(time_zone) => self.schedule.time_zone = time_zone,
+                Err(err) => panic!("AppConfig.override_with | Unknown time zone '{}', error: {:?}", time_zone, err),
+            }
+        }
+        self
+    }
 }
 ///
 /// Telegram bot configuration
@@ -43,4 +91,129 @@ pub struct BotConnectionConfig {
     pub name: String,
     pub token: String,
 }
-
+///
+/// Storage backend configuration
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub kind: StorageKind,
+}
+///
+/// Storage backend kind
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// JSON files in the data directory
+    #[default]
+    Json,
+    /// SQLite database, requires `sqlite` cargo feature
+    Sqlite,
+}
+///
+/// Data directory and files, relative file paths are resolved against the `dir`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DataConfig {
+    pub dir: PathBuf,
+    pub users: PathBuf,
+    pub subscriptions: PathBuf,
+    pub links: PathBuf,
+    pub user_roles: PathBuf,
+    /// Dialogue states of the users
+    pub dialogues: PathBuf,
+    /// SQLite database file, used by `sqlite` storage
+    pub database: PathBuf,
+    /// Notices scheduled to be sent later
+    pub scheduled: PathBuf,
+    /// Notices sent repeatedly by the rule
+    pub recurring: PathBuf,
+    /// Sent notices with the delivery reports
+    pub sent: PathBuf,
+    /// Notice templates with the placeholders
+    pub templates: PathBuf,
+}
+//
+//
+impl DataConfig {
+    ///
+    /// Returns DataConfig with default file names in the `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            users: "users.json".into(),
+            subscriptions: "subscription.json".into(),
+            links: "links.json".into(),
+            user_roles: "user-roles.json".into(),
+            dialogues: "dialogues.json".into(),
+            database: "office-notice.sqlite".into(),
+            scheduled: "scheduled.json".into(),
+            recurring: "recurring.json".into(),
+            sent: "sent.json".into(),
+            templates: "templates.json".into(),
+        }
+    }
+    ///
+    /// Returns `file` resolved against the data directory
+    pub fn path(&self, file: impl AsRef<Path>) -> PathBuf {
+        self.dir.join(file)
+    }
+}
+//
+//
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self::new("./assets")
+    }
+}
+///
+/// Scheduled and recurring notices configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Time zone the senders enter and read the times in
+    pub time_zone: Tz,
+}
+//
+//
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self { time_zone: Tz::UTC }
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+    use super::{AppConfig, StorageKind};
+    ///
+    /// Missing sections take defaults, env variables override file values
+    #[test]
+    fn override_with_env() {
+        let config: AppConfig = serde_yaml::from_str("
+            bot:
+              connection:
+                name: bot
+                token: from-file
+            data:
+              users: /abs/users.json
+        ").unwrap();
+        assert_eq!(config.storage.kind, StorageKind::Json);
+        assert_eq!(config.schedule.time_zone, chrono_tz::UTC);
+        assert_eq!(config.data.path(&config.data.users), PathBuf::from("/abs/users.json"));
+        assert_eq!(config.data.path(&config.data.links), PathBuf::from("./assets/links.json"));
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("OFFICE_NOTICE_BOT_TOKEN", "from-env"),
+            ("OFFICE_NOTICE_STORAGE", "sqlite"),
+            ("OFFICE_NOTICE_DATA_DIR", "/tmp/test-bot"),
+            ("OFFICE_NOTICE_TIME_ZONE", "Europe/Moscow"),
+        ]);
+        let config = config.override_with(|name| env.get(name).map(|value| value.to_string()));
+        assert_eq!(config.bot.connection.token, "from-env");
+        assert_eq!(config.bot.connection.name, "bot");
+        assert_eq!(config.storage.kind, StorageKind::Sqlite);
+        assert_eq!(config.schedule.time_zone, chrono_tz::Europe::Moscow);
+        assert_eq!(config.data.path(&config.data.subscriptions), PathBuf::from("/tmp/test-bot/subscription.json"));
+        assert_eq!(config.data.path(&config.data.users), PathBuf::from("/abs/users.json"));
+    }
+}
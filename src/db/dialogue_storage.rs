@@ -0,0 +1,124 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+use futures::future::BoxFuture;
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use teloxide::{dispatching::dialogue::Storage, types::ChatId};
+use tokio::sync::Mutex;
+use crate::kernel::error::Error;
+use super::json_storage::{load, store};
+///
+/// Dialogue storage persisting the dialogue of each chat in the JSON file,
+/// so the users keep their dialogue state over the bot restarts
+/// - Dialogue could not be restored (file format changed) is replaced with the `fallback`
+pub struct JsonDialogueStorage<D> {
+    path: PathBuf,
+    dialogues: Mutex<IndexMap<String, Value>>,
+    fallback: D,
+}
+//
+//
+impl<D> JsonDialogueStorage<D> {
+    ///
+    /// Returns JsonDialogueStorage new instance, loaded from the `path` if file exists
+    /// - `fallback` - dialogue returned for the chat, which dialogue could not be restored
+    pub fn open(path: impl AsRef<Path>, fallback: D) -> Arc<Self> {
+        let path = path.as_ref().to_owned();
+        let dialogues = match load(&path) {
+            Ok(dialogues) => dialogues,
+            Err(err) => {
+                log::info!("JsonDialogueStorage.open | Starting with empty dialogues, error: {}", err);
+                IndexMap::new()
+            }
+        };
+        Arc::new(Self { path, dialogues: Mutex::new(dialogues), fallback })
+    }
+}
+//
+//
+impl<D> Storage<D> for JsonDialogueStorage<D>
+where
+    D: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    type Error = Error;
+    //
+    //
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<'static, Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let mut dialogues = self.dialogues.lock().await;
+            match dialogues.shift_remove(&chat_id.to_string()) {
+                Some(_) => store(&self.path, &*dialogues).await,
+                None => Err(Error::NotFound(format!("JsonDialogueStorage.remove_dialogue | Dialogue for chat {}", chat_id))),
+            }
+        })
+    }
+    //
+    //
+    fn update_dialogue(self: Arc<Self>, chat_id: ChatId, dialogue: D) -> BoxFuture<'static, Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let dialogue = serde_json::to_value(dialogue)
+                .map_err(|err| Error::Parse(format!("JsonDialogueStorage.update_dialogue | Chat {} - Error: {:?}", chat_id, err)))?;
+            let mut dialogues = self.dialogues.lock().await;
+            if dialogues.get(&chat_id.to_string()) == Some(&dialogue) {
+                return Ok(());
+            }
+            dialogues.insert(chat_id.to_string(), dialogue);
+            store(&self.path, &*dialogues).await
+        })
+    }
+    //
+    //
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
+        Box::pin(async move {
+            let dialogues = self.dialogues.lock().await;
+            Ok(dialogues.get(&chat_id.to_string()).map(|dialogue| {
+                match serde_json::from_value(dialogue.clone()) {
+                    Ok(dialogue) => dialogue,
+                    Err(err) => {
+                        log::warn!("JsonDialogueStorage.get_dialogue | Chat {} - dialogue can't be restored, error: {:?}", chat_id, err);
+                        self.fallback.clone()
+                    }
+                }
+            }))
+        })
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::{SystemTime, UNIX_EPOCH}};
+    use teloxide::{dispatching::dialogue::Storage, types::ChatId};
+    use crate::{notice::notice::NoticeState, states::{MainState, State}};
+    use super::JsonDialogueStorage;
+    ///
+    /// Dialogue survives reopening of the storage, broken one is replaced with the fallback
+    #[tokio::test]
+    async fn dialogue_restored_after_reopen() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = std::env::temp_dir().join(format!("office-notice-dialogues-{}-{}.json", std::process::id(), nanos));
+        let state = State::Notice(NoticeState { prev_state: MainState::default(), groups: vec!["GROUP".to_owned()], chat_id: ChatId(7), ..Default::default() });
+        let storage = JsonDialogueStorage::open(&path, State::restarted());
+        storage.clone().update_dialogue(ChatId(7), state).await.unwrap();
+        storage.update_dialogue(ChatId(8), State::default()).await.unwrap();
+        let storage = JsonDialogueStorage::<State>::open(&path, State::restarted());
+        match storage.clone().get_dialogue(ChatId(7)).await.unwrap() {
+            Some(State::Notice(state)) => assert_eq!(state.groups, vec!["GROUP"]),
+            state => panic!("unexpected state: {:?}", state),
+        }
+        assert!(storage.get_dialogue(ChatId(9)).await.unwrap().is_none());
+        fs::write(&path, r#"{"7": {"Unknown": {}}}"#).unwrap();
+        let storage = JsonDialogueStorage::<State>::open(&path, State::restarted());
+        match storage.get_dialogue(ChatId(7)).await.unwrap() {
+            Some(State::Start(state)) => assert!(state.restarted),
+            state => panic!("unexpected state: {:?}", state),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
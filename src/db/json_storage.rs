@@ -0,0 +1,615 @@
+use std::{fs, io::Write, path::{Path, PathBuf}};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Serialize};
+use teloxide::types::ChatId;
+use tokio::sync::Mutex;
+use crate::{config::DataConfig, kernel::error::Error, links::Links, menu::MenuItem, notice::{recurring_notice::{RecurringNotice, RecurringNotices}, scheduled_notice::{ScheduledNotice, ScheduledNotices}, sent_notice::{SentNotice, SentNotices}, template::{NoticeTemplate, Templates}}, subscribe::subscription::{Senders, Subscription, Subscriptions}, user::{user::User, user_role::{UserRole, UserRoleDb, UserRoles}}};
+use super::storage::{default_menu, Storage};
+///
+/// Storage based on the JSON files in the assets directory
+/// - All mutations are serialized by the `writer` lock
+/// - Files are replaced atomically, so reads never observe a half-written file
+pub struct JsonStorage {
+    users: PathBuf,
+    subscriptions: PathBuf,
+    links: PathBuf,
+    user_roles: PathBuf,
+    scheduled: PathBuf,
+    recurring: PathBuf,
+    sent: PathBuf,
+    templates: PathBuf,
+    writer: Mutex<()>,
+}
+//
+//
+impl JsonStorage {
+    ///
+    /// Returns JsonStorage new instance, using files configured in the `data`
+    pub fn new(data: &DataConfig) -> Self {
+        Self {
+            users: data.path(&data.users),
+            subscriptions: data.path(&data.subscriptions),
+            links: data.path(&data.links),
+            user_roles: data.path(&data.user_roles),
+            scheduled: data.path(&data.scheduled),
+            recurring: data.path(&data.recurring),
+            sent: data.path(&data.sent),
+            templates: data.path(&data.templates),
+            writer: Mutex::new(()),
+        }
+    }
+    ///
+    /// Returns users, or empty map if users file can't be read
+    fn users_or_empty(&self) -> Result<IndexMap<String, User>, Error> {
+        load_or_empty(&self.users)
+    }
+}
+//
+//
+#[async_trait]
+impl Storage for JsonStorage {
+    //
+    //
+    async fn menu(&self) -> Result<IndexMap<String, MenuItem>, Error> {
+        Ok(default_menu())
+    }
+    //
+    //
+    async fn user_insert(&self, user_id: u64, name: String, contact: Option<String>, address: Option<String>, last_seen: Option<DateTime<Utc>>, role: &[UserRole]) -> Result<(), Error> {
+        let _writer = self.writer.lock().await;
+        let mut users = self.users_or_empty()?;
+        let last_seen = last_seen.map_or("".to_owned(), |t| t.to_rfc3339());
+        match users.get_mut(&user_id.to_string()) {
+            Some(user) => {
+                user.name = name.to_owned();
+                user.contact = contact.clone();
+                user.address = address.clone();
+                user.last_seen = last_seen;
+            }
+            None => {
+                users.insert(
+                    user_id.to_string(),
+                    User {
+                        id: ChatId(user_id as i64),
+                        name: name.clone(),
+                        contact,
+                        address,
+                        subscriptions: vec![],
+                        last_seen,
+                        role: role.into(),
+                    }
+                );
+            }
+        };
+        store(&self.users, &users).await
+    }
+    //
+    //
+    async fn user_update(&self, user: User) -> Result<(), Error> {
+        let _writer = self.writer.lock().await;
+        let mut users = self.users_or_empty()?;
+        let user_id = user.id.to_string();
+        match users.get_mut(&user_id) {
+            Some(db_usr) => {
+                db_usr.update(user);
+            }
+            None => {
+                users.insert(
+                    user.id.to_string(),
+                    user,
+                );
+            }
+        };
+        store(&self.users, &users).await
+    }
+    //
+    //
+    async fn user(&self, chat_id: &ChatId) -> Result<User, Error> {
+        log::info!("JsonStorage.user | path: {:?}", self.users);
+        match load(&self.users) {
+            Ok(users) => {
+                let users: IndexMap<String, User> = users;
+                match users.get(&format!("{}", chat_id.0)) {
+                    Some(user) => Ok(user.to_owned()),
+                    None => Err(Error::NotFound(format!("JsonStorage.user | User with id '{}'", chat_id))),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+    //
+    //
+    async fn users(&self) -> Result<IndexMap<String, User>, Error> {
+        log::info!("JsonStorage.users | load users from: {:?}", self.users);
+        load(&self.users)
+    }
+    //
+    //
+    async fn insert_subscription(&self, chat_id: &str, chat_title: &str) -> Result<(), Error> {
+        log::debug!("JsonStorage.insert_subscription | Trying register chat {} ({}) ...", chat_title, chat_id);
+        let _writer = self.writer.lock().await;
+        match self.subscriptions().await {
+            Ok(mut subscriptions) => {
+                let subscription = subscriptions
+                    .iter_mut()
+                    .find(|(group_id, group)| *group_id == chat_id || group.id.as_deref() == Some(chat_id))
+                    .map(|(_, group)| group);
+                match subscription {
+                    Some(subscription) => {
+                        log::debug!("JsonStorage.insert_subscription | Chat {} ({}) already registered", chat_title, chat_id);
+                        if subscription.archived.take().is_some() {
+                            log::info!("JsonStorage.insert_subscription | Chat {} ({}) re-activated with {} members", chat_title, chat_id, subscription.members.len());
+                        }
+                        subscription.title = chat_title.to_owned();
+                        store(&self.subscriptions, &subscriptions).await
+                    }
+                    None => {
+                        log::debug!("JsonStorage.insert_subscription | Regictering chat {} ({})...", chat_title, chat_id);
+                        let subscription = Subscription {
+                            id: Some(chat_id.to_owned()),
+                            title: chat_title.to_owned(),
+                            members: IndexMap::new(),
+                            archived: None,
+                            senders: Default::default(),
+                        };
+                        subscriptions.insert(chat_id.to_string(), subscription);
+                        match store(&self.subscriptions, &subscriptions).await {
+                            Ok(_) => Ok(()),
+                            Err(err) => {
+                                log::warn!("JsonStorage.insert_subscription | Error regictering chat {} ({}): {}", chat_title, chat_id, err);
+                                Err(err)
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                log::debug!("JsonStorage.insert_subscription | Error: {}", err);
+                Err(err)
+            }
+        }
+    }
+    //
+    //
+    async fn remove_subscription(&self, chat_id: ChatId) -> Result<Option<Subscription>, Error> {
+        let _writer = self.writer.lock().await;
+        let mut subscriptions = self.subscriptions().await?;
+        let chat_id = chat_id.to_string();
+        let subscription = subscriptions
+            .iter_mut()
+            .find(|(group_id, group)| **group_id == chat_id || group.id.as_ref() == Some(&chat_id))
+            .map(|(_, group)| group);
+        match subscription {
+            Some(subscription) => {
+                log::debug!("JsonStorage.remove_subscription | Archiving chat {} ({})...", subscription.title, chat_id);
+                subscription.archived = Some(Utc::now().to_rfc3339());
+                let subscription = subscription.clone();
+                store(&self.subscriptions, &subscriptions).await?;
+                Ok(Some(subscription))
+            }
+            None => {
+                log::debug!("JsonStorage.remove_subscription | Chat {} isn't registered", chat_id);
+                Ok(None)
+            }
+        }
+    }
+    //
+    //
+    async fn toggle_member(&self, group_id: &str, user: User) -> Result<Option<bool>, Error> {
+        let _writer = self.writer.lock().await;
+        let mut subscriptions = self.subscriptions().await?;
+        let Some(group) = subscriptions.get_mut(group_id).filter(|group| group.is_active()) else {
+            return Ok(None);
+        };
+        let user_id = user.id.to_string();
+        let added = match group.members.shift_remove(&user_id) {
+            Some(_) => false,
+            None => {
+                group.members.insert(user_id, user);
+                true
+            }
+        };
+        store(&self.subscriptions, &subscriptions).await?;
+        Ok(Some(added))
+    }
+    //
+    //
+    async fn update_senders(&self, group_id: &str, f: Box<dyn for<'a> FnOnce(&'a mut Senders) + Send>) -> Result<Senders, Error> {
+        let _writer = self.writer.lock().await;
+        let mut subscriptions = self.subscriptions().await?;
+        let group = subscriptions.get_mut(group_id)
+            .ok_or_else(|| Error::NotFound(format!("JsonStorage.update_senders | Group '{}'", group_id)))?;
+        f(&mut group.senders);
+        let senders = group.senders.clone();
+        store(&self.subscriptions, &subscriptions).await?;
+        Ok(senders)
+    }
+    //
+    //
+    async fn subscriptions(&self) -> Result<Subscriptions, Error> {
+        log::info!("JsonStorage.subscriptions | load subscriptions from: {:?}", self.subscriptions);
+        match load(&self.subscriptions) {
+            Ok(groups) => {
+                let groups: IndexMap<String, Subscription> = groups;
+                Ok(groups)
+            }
+            Err(err) => Err(err),
+        }
+    }
+    //
+    //
+    async fn links(&self, user_id: ChatId) -> Result<Links, Error> {
+        let _ = user_id;
+        log::info!("JsonStorage.links | load links from: {:?}", self.links);
+        match load(&self.links) {
+            Ok(links) => {
+                let links: Links = links;
+                Ok(links)
+            }
+            Err(err) => Err(err),
+        }
+    }
+    //
+    //
+    async fn user_roles(&self, user_id: ChatId) -> Result<UserRoles, Error> {
+        let _ = user_id;
+        log::info!("JsonStorage.user_roles | load roles from: {:?}", self.user_roles);
+        match load(&self.user_roles) {
+            Ok(roles) => {
+                let roles: IndexMap<String, UserRoleDb> = roles;
+                Ok(roles)
+            }
+            Err(err) => Err(err),
+        }
+    }
+    //
+    //
+    async fn scheduled_notices(&self) -> Result<ScheduledNotices, Error> {
+        load_or_empty(&self.scheduled)
+    }
+    //
+    //
+    async fn update_scheduled_notice(&self, mut notice: ScheduledNotice) -> Result<u64, Error> {
+        let _writer = self.writer.lock().await;
+        let mut notices: ScheduledNotices = load_or_empty(&self.scheduled)?;
+        if notice.id == 0 {
+            notice.id = notices.values().map(|notice| notice.id).max().unwrap_or(0) + 1;
+        }
+        let id = notice.id;
+        notices.insert(id.to_string(), notice);
+        store(&self.scheduled, &notices).await?;
+        Ok(id)
+    }
+    //
+    //
+    async fn remove_scheduled_notice(&self, id: u64) -> Result<Option<ScheduledNotice>, Error> {
+        let _writer = self.writer.lock().await;
+        let mut notices: ScheduledNotices = load_or_empty(&self.scheduled)?;
+        let notice = notices.shift_remove(&id.to_string());
+        if notice.is_some() {
+            store(&self.scheduled, &notices).await?;
+        }
+        Ok(notice)
+    }
+    //
+    //
+    async fn recurring_notices(&self) -> Result<RecurringNotices, Error> {
+        load_or_empty(&self.recurring)
+    }
+    //
+    //
+    async fn update_recurring_notice(&self, mut notice: RecurringNotice) -> Result<u64, Error> {
+        let _writer = self.writer.lock().await;
+        let mut notices: RecurringNotices = load_or_empty(&self.recurring)?;
+        if notice.id == 0 {
+            notice.id = notices.values().map(|notice| notice.id).max().unwrap_or(0) + 1;
+        }
+        let id = notice.id;
+        notices.insert(id.to_string(), notice);
+        store(&self.recurring, &notices).await?;
+        Ok(id)
+    }
+    //
+    //
+    async fn remove_recurring_notice(&self, id: u64) -> Result<Option<RecurringNotice>, Error> {
+        let _writer = self.writer.lock().await;
+        let mut notices: RecurringNotices = load_or_empty(&self.recurring)?;
+        let notice = notices.shift_remove(&id.to_string());
+        if notice.is_some() {
+            store(&self.recurring, &notices).await?;
+        }
+        Ok(notice)
+    }
+    //
+    //
+    async fn sent_notices(&self) -> Result<SentNotices, Error> {
+        load_or_empty(&self.sent)
+    }
+    //
+    //
+    async fn update_sent_notice(&self, mut notice: SentNotice) -> Result<u64, Error> {
+        let _writer = self.writer.lock().await;
+        let mut notices: SentNotices = load_or_empty(&self.sent)?;
+        if notice.id == 0 {
+            notice.id = notices.values().map(|notice| notice.id).max().unwrap_or(0) + 1;
+        }
+        let id = notice.id;
+        notices.insert(id.to_string(), notice);
+        store(&self.sent, &notices).await?;
+        Ok(id)
+    }
+    //
+    //
+    async fn templates(&self) -> Result<Templates, Error> {
+        load_or_empty(&self.templates)
+    }
+    //
+    //
+    async fn update_template(&self, mut template: NoticeTemplate) -> Result<u64, Error> {
+        let _writer = self.writer.lock().await;
+        let mut templates: Templates = load_or_empty(&self.templates)?;
+        if template.id == 0 {
+            template.id = templates.values().map(|template| template.id).max().unwrap_or(0) + 1;
+        }
+        let id = template.id;
+        templates.insert(id.to_string(), template);
+        store(&self.templates, &templates).await?;
+        Ok(id)
+    }
+    //
+    //
+    async fn remove_template(&self, id: u64) -> Result<Option<NoticeTemplate>, Error> {
+        let _writer = self.writer.lock().await;
+        let mut templates: Templates = load_or_empty(&self.templates)?;
+        let template = templates.shift_remove(&id.to_string());
+        if template.is_some() {
+            store(&self.templates, &templates).await?;
+        }
+        Ok(template)
+    }
+}
+///
+/// Returns parsed JSON file, or empty value if file can't be read,
+/// broken file is reported, so it isn't overwritten with the empty value
+fn load_or_empty<T: DeserializeOwned + Default>(path: impl AsRef<Path>) -> Result<T, Error> {
+    match load(&path) {
+        Ok(value) => Ok(value),
+        Err(Error::Parse(err)) => Err(Error::Parse(err)),
+        Err(err) => {
+            log::info!("JsonStorage.load_or_empty | error: {}", err);
+            Ok(T::default())
+        }
+    }
+}
+///
+/// Reads and parses JSON file
+pub(super) fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Error> {
+    match fs::read_to_string(&path) {
+        Ok(json_string) => {
+            log::info!("JsonStorage.load | Path: {:?}", path.as_ref());
+            match serde_json::from_str(&json_string) {
+                Ok(data) => {
+                    let data: T = data;
+                    Ok(data)
+                }
+                Err(err) => {
+                    Err(Error::Parse(format!("JsonStorage.load | Error in: {:?}\n\terror: {:?}", json_string, err)))
+                }
+            }
+        }
+        Err(err) => {
+            Err(Error::Storage(format!("JsonStorage.load | File '{:?}' reading error: {:?}", path.as_ref(), err)))
+        }
+    }
+}
+///
+/// Writes `value` as pretty JSON into the temporary file, syncs it to the disk
+/// and atomically renames over the original file, the file IO runs on the blocking pool
+pub(super) async fn store<T: Serialize + ?Sized>(path: impl AsRef<Path>, value: &T) -> Result<(), Error> {
+    let path = path.as_ref().to_owned();
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|err| Error::Parse(format!("JsonStorage.store | File '{:?}' serialize error: {:?}", path, err)))?;
+    tokio::task::spawn_blocking(move || write(&path, &json))
+        .await
+        .map_err(|err| Error::Storage(format!("JsonStorage.store | Task error: {:?}", err)))?
+}
+///
+/// Blocking part of the [store]
+fn write(path: &Path, json: &[u8]) -> Result<(), Error> {
+    let tmp = path.with_extension("json.tmp");
+    fs::File::create(&tmp)
+        .and_then(|mut f| {
+            f.write_all(json)?;
+            f.sync_all()
+        })
+        .map_err(|err| Error::Storage(format!("JsonStorage.store | File '{:?}' writing error: {:?}", tmp, err)))?;
+    fs::rename(&tmp, path)
+        .map_err(|err| Error::Storage(format!("JsonStorage.store | File '{:?}' renaming to '{:?}' error: {:?}", tmp, path, err)))?;
+    // Sync the directory entry, so the rename survives a crash
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Err(err) = fs::File::open(dir).and_then(|dir| dir.sync_all()) {
+            log::warn!("JsonStorage.store | Directory '{:?}' sync error: {:?}", dir, err);
+        }
+    }
+    Ok(())
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+    use chrono::Utc;
+    use teloxide::types::ChatId;
+    use crate::{config::DataConfig, db::storage::Storage, kernel::error::Error, user::user_role::UserRole};
+    use super::JsonStorage;
+    ///
+    /// Returns new empty directory in the system temp
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("office-notice-{}-{}-{}", name, std::process::id(), nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    ///
+    /// Hundreds of parallel inserts must not lose any user
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn user_insert_parallel() {
+        let dir = temp_dir("user_insert_parallel");
+        let storage = Arc::new(JsonStorage::new(&DataConfig::new(&dir)));
+        let count = 300;
+        let tasks: Vec<_> = (1..=count)
+            .map(|id| {
+                let storage = storage.clone();
+                tokio::spawn(async move {
+                    storage.user_insert(id, format!("User {}", id), None, None, Some(Utc::now()), &[UserRole::Guest]).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let users = storage.users().await.unwrap();
+        assert_eq!(users.len(), count as usize, "users lost: {}", count as usize - users.len());
+        for id in 1..=count {
+            assert!(users.contains_key(&id.to_string()), "user {} lost", id);
+        }
+        assert!(!dir.join("users.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    ///
+    /// Parallel toggles of the different users must not lose any membership
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn toggle_member_parallel() {
+        let dir = temp_dir("toggle_member_parallel");
+        fs::write(dir.join("subscription.json"), "{}").unwrap();
+        let storage = Arc::new(JsonStorage::new(&DataConfig::new(&dir)));
+        storage.insert_subscription("-100", "Group").await.unwrap();
+        let count = 100;
+        for id in 1..=count {
+            storage.user_insert(id, format!("User {}", id), None, None, None, &[UserRole::Member]).await.unwrap();
+        }
+        // Odd users are subscribed beforehand and unsubscribed in parallel with the even ones subscribing
+        for id in (1..=count).step_by(2) {
+            let user = storage.user(&ChatId(id as i64)).await.unwrap();
+            assert_eq!(storage.toggle_member("-100", user).await.unwrap(), Some(true));
+        }
+        let tasks: Vec<_> = (1..=count)
+            .map(|id| {
+                let storage = storage.clone();
+                tokio::spawn(async move {
+                    let user = storage.user(&ChatId(id as i64)).await.unwrap();
+                    storage.toggle_member("-100", user).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let members = &storage.subscriptions().await.unwrap()["-100"].members;
+        for id in 1..=count {
+            assert_eq!(members.contains_key(&id.to_string()), id % 2 == 0, "membership of the user {} lost", id);
+        }
+        storage.remove_subscription(ChatId(-100)).await.unwrap();
+        let user = storage.user(&ChatId(1)).await.unwrap();
+        assert_eq!(storage.toggle_member("-100", user.clone()).await.unwrap(), None, "archived group isn't changed");
+        assert_eq!(storage.toggle_member("-200", user).await.unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    ///
+    /// Senders edited by the Moder in parallel with the subscribe toggles keep both changes
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn update_senders_parallel() {
+        let dir = temp_dir("update_senders_parallel");
+        fs::write(dir.join("subscription.json"), "{}").unwrap();
+        let storage = Arc::new(JsonStorage::new(&DataConfig::new(&dir)));
+        storage.insert_subscription("-100", "Group").await.unwrap();
+        let count = 50;
+        for id in 1..=count {
+            storage.user_insert(id, format!("User {}", id), None, None, None, &[UserRole::Sender]).await.unwrap();
+        }
+        let tasks: Vec<_> = (1..=count)
+            .map(|id| {
+                let storage = storage.clone();
+                tokio::spawn(async move {
+                    let user = storage.user(&ChatId(id as i64)).await.unwrap();
+                    storage.toggle_member("-100", user).await.unwrap();
+                    storage.update_senders("-100", Box::new(move |senders| senders.toggle_user(&id.to_string()))).await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let group = &storage.subscriptions().await.unwrap()["-100"];
+        assert_eq!(group.members.len(), count as usize);
+        assert_eq!(group.senders.users.len(), count as usize);
+        assert!(matches!(storage.update_senders("-200", Box::new(|_| {})).await, Err(Error::NotFound(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    ///
+    /// Group, the bot was removed from, is archived with members and restored on re-adding
+    #[tokio::test]
+    async fn remove_subscription_archives_and_restores() {
+        let dir = temp_dir("remove_subscription");
+        fs::write(dir.join("subscription.json"), "{}").unwrap();
+        let storage = JsonStorage::new(&DataConfig::new(&dir));
+        storage.user_insert(7, "User 7".to_owned(), None, None, None, &[UserRole::Member]).await.unwrap();
+        storage.insert_subscription("-100", "Group").await.unwrap();
+        let user = storage.user(&ChatId(7)).await.unwrap();
+        storage.toggle_member("-100", user).await.unwrap();
+        let archived = storage.remove_subscription(ChatId(-100)).await.unwrap().unwrap();
+        assert!(!archived.is_active());
+        assert!(!storage.subscriptions().await.unwrap()["-100"].is_active());
+        assert!(storage.remove_subscription(ChatId(-200)).await.unwrap().is_none());
+        storage.insert_subscription("-100", "Group renamed").await.unwrap();
+        let group = &storage.subscriptions().await.unwrap()["-100"];
+        assert!(group.is_active());
+        assert_eq!(group.title, "Group renamed");
+        assert!(group.members.contains_key("7"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    ///
+    /// Group keyed by the name is found by its chat id, re-added with the members instead of the new empty group
+    #[tokio::test]
+    async fn remove_subscription_restores_name_keyed() {
+        let dir = temp_dir("remove_subscription_name_keyed");
+        fs::write(dir.join("subscription.json"), r#"{
+            "TKZ_OFFICE_GROUP": {
+                "id": "-4224543713",
+                "title": "Office-group",
+                "members": {
+                    "7": { "id": "7", "name": "Anton", "contact": null, "address": null, "subscriptions": [], "last_seen": "", "role": ["Member"] }
+                }
+            }
+        }"#).unwrap();
+        let storage = JsonStorage::new(&DataConfig::new(&dir));
+        assert!(storage.remove_subscription(ChatId(-4224543713)).await.unwrap().is_some());
+        assert!(!storage.subscriptions().await.unwrap()["TKZ_OFFICE_GROUP"].is_active());
+        storage.insert_subscription("-4224543713", "Office").await.unwrap();
+        let groups = storage.subscriptions().await.unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["TKZ_OFFICE_GROUP"]);
+        let group = &groups["TKZ_OFFICE_GROUP"];
+        assert!(group.is_active());
+        assert_eq!(group.title, "Office");
+        assert!(group.members.contains_key("7"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    ///
+    /// Missing file, broken file and unknown user are reported with the matching error kind
+    #[tokio::test]
+    async fn error_kinds() {
+        let dir = temp_dir("error_kinds");
+        let storage = JsonStorage::new(&DataConfig::new(&dir));
+        assert!(matches!(storage.users().await, Err(Error::Storage(_))));
+        storage.user_insert(7, "User 7".to_owned(), None, None, None, &[UserRole::Guest]).await.unwrap();
+        assert!(matches!(storage.user(&ChatId(8)).await, Err(Error::NotFound(_))));
+        fs::write(dir.join("subscription.json"), "{ broken").unwrap();
+        assert!(matches!(storage.subscriptions().await, Err(Error::Parse(_))));
+        fs::write(dir.join("users.json"), "{ broken").unwrap();
+        assert!(matches!(storage.user_insert(9, "User 9".to_owned(), None, None, None, &[UserRole::Guest]).await, Err(Error::Parse(_))));
+        assert_eq!(fs::read_to_string(dir.join("users.json")).unwrap(), "{ broken");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+use std::{fmt, fs, path::Path};
+use indexmap::IndexMap;
+use rusqlite::{params, Transaction};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::{config::DataConfig, kernel::error::Error, links::Links, notice::{recurring_notice::RecurringNotice, template::NoticeTemplate}, subscribe::subscription::Subscription, user::{user::User, user_role::UserRoleDb}};
+use super::sqlite_storage::{insert_user_if_absent, to_json, upsert_user, SqliteStorage};
+///
+/// Result of the one-shot import of the assets JSON files into the SQLite database
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub roles: usize,
+    pub users: usize,
+    pub groups: usize,
+    pub memberships: usize,
+    pub links: bool,
+    pub recurring: usize,
+    pub templates: usize,
+    /// Rows could not be mapped, with the reason
+    pub skipped: Vec<String>,
+}
+//
+//
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Imported roles: {}, users: {}, groups: {}, memberships: {}, links: {}, recurring notices: {}, templates: {}", self.roles, self.users, self.groups, self.memberships, self.links, self.recurring, self.templates)?;
+        writeln!(f, "Skipped rows: {}", self.skipped.len())?;
+        for skipped in &self.skipped {
+            writeln!(f, "\t{}", skipped)?;
+        }
+        Ok(())
+    }
+}
+//
+//
+impl SqliteStorage {
+    ///
+    /// Imports users, subscriptions, links, user roles, recurring notices and templates JSON files configured in the `data`,
+    /// existing rows with the same id are replaced, other existing rows are kept
+    pub async fn import_json(&self, data: &DataConfig) -> Result<ImportReport, Error> {
+        let data = data.clone();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.import_json | Error: {:?}", err)))?;
+            let mut report = ImportReport::default();
+            import_roles(&tx, &data.path(&data.user_roles), &mut report)?;
+            import_users(&tx, &data.path(&data.users), &mut report)?;
+            import_subscriptions(&tx, &data.path(&data.subscriptions), &mut report)?;
+            import_links(&tx, &data.path(&data.links), &mut report)?;
+            import_recurring(&tx, &data.path(&data.recurring), &mut report)?;
+            import_templates(&tx, &data.path(&data.templates), &mut report)?;
+            tx.commit()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.import_json | Commit error: {:?}", err)))?;
+            Ok(report)
+        }).await
+    }
+}
+///
+///
+fn import_roles(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let roles: IndexMap<String, UserRoleDb> = entries(path, report);
+    for (position, (id, role)) in roles.into_iter().enumerate() {
+        let role_json = serde_json::to_string(&role.role)
+            .map_err(|err| Error::Parse(format!("SqliteStorage.import_roles | Role '{}' error: {:?}", id, err)))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO roles (id, position, hidden, title, role) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, position as i64, role.hidden, role.title, role_json],
+        ).map_err(|err| Error::Storage(format!("SqliteStorage.import_roles | Role '{}' error: {:?}", id, err)))?;
+        report.roles += 1;
+    }
+    Ok(())
+}
+///
+///
+fn import_users(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let users: IndexMap<String, User> = entries(path, report);
+    for (key, user) in users {
+        if key != user.id.to_string() {
+            report.skipped.push(format!("{:?} | User '{}': key doesn't match id '{}'", path, key, user.id));
+            continue;
+        }
+        upsert_user(tx, &user)?;
+        report.users += 1;
+    }
+    Ok(())
+}
+///
+/// Groups are parsed member by member, so a broken member skips only itself, not the whole group.
+/// Existing groups and memberships are kept, the group with the same id gets the title, archive state and senders of the imported one
+fn import_subscriptions(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let groups: IndexMap<String, Value> = entries(path, report);
+    for (group_id, mut group) in groups {
+        let members = group.get_mut("members")
+            .map(|members| std::mem::replace(members, Value::Object(Default::default())))
+            .unwrap_or(Value::Object(Default::default()));
+        let mut subscription: Subscription = match serde_json::from_value(group) {
+            Ok(subscription) => subscription,
+            Err(err) => {
+                report.skipped.push(format!("{:?} | Group '{}': {}", path, group_id, err));
+                continue;
+            }
+        };
+        let members: IndexMap<String, Value> = match serde_json::from_value(members) {
+            Ok(members) => members,
+            Err(err) => {
+                report.skipped.push(format!("{:?} | Group '{}' members: {}", path, group_id, err));
+                IndexMap::new()
+            }
+        };
+        for (member_id, member) in members {
+            match serde_json::from_value::<User>(member) {
+                Ok(member) => {
+                    subscription.members.insert(member_id, member);
+                    report.memberships += 1;
+                }
+                Err(err) => report.skipped.push(format!("{:?} | Group '{}' member '{}': {}", path, group_id, member_id, err)),
+            }
+        }
+        upsert_subscription(tx, &group_id, &subscription)?;
+        report.groups += 1;
+    }
+    Ok(())
+}
+///
+/// Inserts or updates the group, its members are added to the existing ones,
+/// members not known as users are inserted into the `users`
+fn upsert_subscription(tx: &Transaction, group_id: &str, group: &Subscription) -> Result<(), Error> {
+    let senders = match group.senders.is_empty() {
+        true => None,
+        false => Some(to_json(&group.senders)?),
+    };
+    tx.execute(
+        "INSERT INTO groups (id, position, chat_id, title, archived, senders) VALUES (?1, (SELECT COALESCE(MAX(position), -1) + 1 FROM groups), ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET chat_id = excluded.chat_id, title = excluded.title, archived = excluded.archived, senders = excluded.senders",
+        params![group_id, group.id, group.title, group.archived, senders],
+    ).map_err(|err| Error::Storage(format!("SqliteStorage.import_subscriptions | Group '{}' error: {:?}", group_id, err)))?;
+    for member in group.members.values() {
+        insert_user_if_absent(tx, member)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO memberships (group_id, user_id, position)
+                VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM memberships WHERE group_id = ?1))",
+            params![group_id, member.id.0],
+        ).map_err(|err| Error::Storage(format!("SqliteStorage.import_subscriptions | Group '{}' member '{}' error: {:?}", group_id, member.id, err)))?;
+    }
+    Ok(())
+}
+///
+///
+fn import_links(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let links = fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|json| serde_json::from_str::<Links>(&json).map(|_| json).map_err(|err| err.to_string()));
+    match links {
+        Ok(links) => {
+            tx.execute("INSERT OR REPLACE INTO links (id, data) VALUES (0, ?1)", params![links])
+                .map_err(|err| Error::Storage(format!("SqliteStorage.import_links | Error: {:?}", err)))?;
+            report.links = true;
+        }
+        Err(err) => report.skipped.push(format!("{:?} | Links: {}", path, err)),
+    }
+    Ok(())
+}
+///
+///
+fn import_recurring(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let notices: IndexMap<String, RecurringNotice> = entries(path, report);
+    for (key, notice) in notices {
+        if key != notice.id.to_string() {
+            report.skipped.push(format!("{:?} | Recurring notice '{}': key doesn't match id '{}'", path, key, notice.id));
+            continue;
+        }
+        let data = serde_json::to_string(&notice)
+            .map_err(|err| Error::Parse(format!("SqliteStorage.import_recurring | Notice '{}' error: {:?}", key, err)))?;
+        tx.execute("INSERT OR REPLACE INTO recurring_notices (id, data) VALUES (?1, ?2)", params![notice.id as i64, data])
+            .map_err(|err| Error::Storage(format!("SqliteStorage.import_recurring | Notice '{}' error: {:?}", key, err)))?;
+        report.recurring += 1;
+    }
+    Ok(())
+}
+///
+///
+fn import_templates(tx: &Transaction, path: &Path, report: &mut ImportReport) -> Result<(), Error> {
+    let templates: IndexMap<String, NoticeTemplate> = entries(path, report);
+    for (key, template) in templates {
+        if key != template.id.to_string() {
+            report.skipped.push(format!("{:?} | Template '{}': key doesn't match id '{}'", path, key, template.id));
+            continue;
+        }
+        let data = serde_json::to_string(&template)
+            .map_err(|err| Error::Parse(format!("SqliteStorage.import_templates | Template '{}' error: {:?}", key, err)))?;
+        tx.execute("INSERT OR REPLACE INTO templates (id, data) VALUES (?1, ?2)", params![template.id as i64, data])
+            .map_err(|err| Error::Storage(format!("SqliteStorage.import_templates | Template '{}' error: {:?}", key, err)))?;
+        report.templates += 1;
+    }
+    Ok(())
+}
+///
+/// Returns entries of the JSON object file, entries could not be parsed are reported as skipped
+fn entries<T: DeserializeOwned>(path: &Path, report: &mut ImportReport) -> IndexMap<String, T> {
+    let json: IndexMap<String, Value> = match fs::read_to_string(path).map_err(|err| err.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|err| err.to_string())) {
+        Ok(json) => json,
+        Err(err) => {
+            report.skipped.push(format!("{:?} | {}", path, err));
+            return IndexMap::new();
+        }
+    };
+    json.into_iter()
+        .filter_map(|(key, value)| match serde_json::from_value(value) {
+            Ok(value) => Some((key, value)),
+            Err(err) => {
+                report.skipped.push(format!("{:?} | '{}': {}", path, key, err));
+                None
+            }
+        })
+        .collect()
+}
+//
+//
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::{fs, time::{SystemTime, UNIX_EPOCH}};
+    use teloxide::types::ChatId;
+    use crate::{config::DataConfig, db::{sqlite_storage::SqliteStorage, storage::Storage}, user::user_role::UserRole};
+    ///
+    /// Valid rows are imported, unmappable ones are reported, rows already in the database are kept
+    #[tokio::test]
+    async fn import_json() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("office-notice-import-{}-{}", std::process::id(), nanos));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("user-roles.json"), r#"{
+            "sender": { "title": "Sender", "role": "GrantRole/Sender" },
+            "owner": { "title": "Owner", "role": "GrantRole/Owner" }
+        }"#).unwrap();
+        fs::write(dir.join("users.json"), r#"{
+            "7": { "id": "7", "name": "Anton", "contact": null, "address": null, "last_seen": "", "role": ["Sender"] },
+            "8": { "id": "9", "name": "Wrong key", "contact": null, "address": null, "last_seen": "", "role": [] }
+        }"#).unwrap();
+        fs::write(dir.join("subscription.json"), r#"{
+            "TKZ_OFFICE_GROUP": {
+                "title": "Office",
+                "members": {
+                    "7": { "id": "7", "name": "Anton", "contact": null, "address": null, "last_seen": "", "role": ["Sender"] },
+                    "10": { "id": "10", "name": "Broken", "role": "Sender" }
+                },
+                "senders": { "roles": ["Moder"] }
+            },
+            "NO_TITLE_GROUP": { "members": {} }
+        }"#).unwrap();
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        storage.user_insert(5, "Existing".to_owned(), None, None, None, &[UserRole::Member]).await.unwrap();
+        storage.insert_subscription("-100", "Existing group").await.unwrap();
+        let user = storage.user(&ChatId(5)).await.unwrap();
+        storage.toggle_member("-100", user).await.unwrap();
+        let report = storage.import_json(&DataConfig::new(&dir)).await.unwrap();
+        assert_eq!((report.roles, report.users, report.groups, report.memberships), (1, 1, 1, 1), "{}", report);
+        let skipped = |text: &str| report.skipped.iter().any(|skipped| skipped.contains(text));
+        assert!(skipped("'owner'"), "{}", report);
+        assert!(skipped("User '8'"), "{}", report);
+        assert!(skipped("member '10'"), "{}", report);
+        assert!(skipped("Group 'NO_TITLE_GROUP'"), "{}", report);
+        assert!(skipped("Links"), "{}", report);
+        let groups = storage.subscriptions().await.unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["-100", "TKZ_OFFICE_GROUP"]);
+        assert!(groups["-100"].members.contains_key("5"), "existing membership is kept");
+        let office = &groups["TKZ_OFFICE_GROUP"];
+        assert_eq!(office.members.keys().collect::<Vec<_>>(), ["7"]);
+        assert_eq!(office.senders.roles, vec![UserRole::Moder]);
+        assert_eq!(storage.user(&ChatId(7)).await.unwrap().role, vec![UserRole::Sender]);
+        assert_eq!(storage.user_roles(ChatId(7)).await.unwrap().keys().collect::<Vec<_>>(), ["sender"]);
+        // The import run again doesn't duplicate anything
+        let report = storage.import_json(&DataConfig::new(&dir)).await.unwrap();
+        assert_eq!(report.memberships, 1);
+        assert_eq!(storage.subscriptions().await.unwrap()["TKZ_OFFICE_GROUP"].members.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,600 @@
+use std::{path::Path, sync::{Arc, Mutex}};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use rusqlite::{params, Connection, OptionalExtension};
+use teloxide::types::ChatId;
+use crate::{kernel::error::Error, links::Links, menu::MenuItem, notice::{recurring_notice::{RecurringNotice, RecurringNotices}, scheduled_notice::{ScheduledNotice, ScheduledNotices}, sent_notice::{SentNotice, SentNotices}, template::{NoticeTemplate, Templates}}, subscribe::subscription::{Senders, Subscription, Subscriptions}, user::{user::User, user_role::{UserRole, UserRoleDb, UserRoles}}};
+use super::storage::{default_menu, Storage};
+///
+/// Database schema, applied on every open
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS roles (
+        id          TEXT PRIMARY KEY,
+        position    INTEGER NOT NULL,
+        hidden      INTEGER NOT NULL,
+        title       TEXT NOT NULL,
+        role        TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS users (
+        id          INTEGER PRIMARY KEY,
+        name        TEXT NOT NULL,
+        contact     TEXT,
+        address     TEXT,
+        last_seen   TEXT NOT NULL,
+        role        TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS groups (
+        id          TEXT PRIMARY KEY,
+        position    INTEGER NOT NULL,
+        chat_id     TEXT,
+        title       TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS memberships (
+        group_id    TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+        user_id     INTEGER NOT NULL REFERENCES users(id),
+        position    INTEGER NOT NULL,
+        PRIMARY KEY (group_id, user_id)
+    );
+    CREATE TABLE IF NOT EXISTS links (
+        id          INTEGER PRIMARY KEY CHECK (id = 0),
+        data        TEXT NOT NULL
+    );
+";
+///
+/// Schema changes, applied in order on top of the `SCHEMA`,
+/// index of the last applied one is stored in the `user_version`
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE groups ADD COLUMN archived TEXT;",
+    "CREATE TABLE scheduled_notices (id INTEGER PRIMARY KEY AUTOINCREMENT, data TEXT NOT NULL);",
+    "CREATE TABLE recurring_notices (id INTEGER PRIMARY KEY AUTOINCREMENT, data TEXT NOT NULL);",
+    "CREATE TABLE sent_notices (id INTEGER PRIMARY KEY AUTOINCREMENT, data TEXT NOT NULL);",
+    "CREATE TABLE templates (id INTEGER PRIMARY KEY AUTOINCREMENT, data TEXT NOT NULL);",
+    "ALTER TABLE groups ADD COLUMN senders TEXT;",
+];
+///
+/// Storage based on the SQLite database
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+//
+//
+impl SqliteStorage {
+    ///
+    /// Opens (creates if not exists) the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let conn = Connection::open(&path)
+            .map_err(|err| Error::Storage(format!("SqliteStorage.open | Database '{:?}' opening error: {:?}", path.as_ref(), err)))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")
+            .and_then(|_| conn.execute_batch(SCHEMA))
+            .map_err(|err| Error::Storage(format!("SqliteStorage.open | Database '{:?}' schema error: {:?}", path.as_ref(), err)))?;
+        Self::migrate(&conn)
+            .map_err(|err| Error::Storage(format!("SqliteStorage.open | Database '{:?}' migration error: {:?}", path.as_ref(), err)))?;
+        Ok(Self { conn: Arc::new(Mutex::new(conn)) })
+    }
+    ///
+    /// Applies `MIGRATIONS` not applied yet
+    fn migrate(conn: &Connection) -> Result<(), rusqlite::Error> {
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            log::info!("SqliteStorage.migrate | Applying migration {}: {}", index + 1, migration);
+            conn.execute_batch(&format!("BEGIN; {} PRAGMA user_version = {}; COMMIT;", migration, index + 1))?;
+        }
+        Ok(())
+    }
+    ///
+    /// Executes `f` with the connection on the blocking thread pool
+    pub(super) async fn with_conn<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, Error> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.with_conn | Connection lock error: {:?}", err)))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|err| Error::Storage(format!("SqliteStorage.with_conn | Task error: {:?}", err)))?
+    }
+}
+//
+//
+#[async_trait]
+impl Storage for SqliteStorage {
+    //
+    //
+    async fn menu(&self) -> Result<IndexMap<String, MenuItem>, Error> {
+        Ok(default_menu())
+    }
+    //
+    //
+    async fn user_insert(&self, user_id: u64, name: String, contact: Option<String>, address: Option<String>, last_seen: Option<DateTime<Utc>>, role: &[UserRole]) -> Result<(), Error> {
+        let last_seen = last_seen.map_or("".to_owned(), |t| t.to_rfc3339());
+        let role = to_json(role)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO users (id, name, contact, address, last_seen, role) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    ON CONFLICT(id) DO UPDATE SET name = excluded.name, contact = excluded.contact, address = excluded.address, last_seen = excluded.last_seen",
+                params![user_id as i64, name, contact, address, last_seen, role],
+            )
+            .map(|_| ())
+            .map_err(|err| Error::Storage(format!("SqliteStorage.user_insert | User '{}' ({}) - Error {:#?}", name, user_id, err)))
+        }).await
+    }
+    //
+    //
+    async fn user_update(&self, user: User) -> Result<(), Error> {
+        self.with_conn(move |conn| {
+            upsert_user(conn, &user)
+        }).await
+    }
+    //
+    //
+    async fn user(&self, chat_id: &ChatId) -> Result<User, Error> {
+        let chat_id = *chat_id;
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT id, name, contact, address, last_seen, role FROM users WHERE id = ?1",
+                params![chat_id.0],
+                read_user,
+            )
+            .optional()
+            .map_err(|err| Error::Storage(format!("SqliteStorage.user | Error: {:#?}", err)))?
+            .ok_or_else(|| Error::NotFound(format!("SqliteStorage.user | User with id '{}'", chat_id)))
+        }).await
+    }
+    //
+    //
+    async fn users(&self) -> Result<IndexMap<String, User>, Error> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, name, contact, address, last_seen, role FROM users ORDER BY rowid")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.users | Error: {:#?}", err)))?;
+            let users = stmt.query_map([], read_user)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.users | Error: {:#?}", err)))?;
+            Ok(users.into_iter().map(|user| (user.id.to_string(), user)).collect())
+        }).await
+    }
+    //
+    //
+    async fn insert_subscription(&self, chat_id: &str, chat_title: &str) -> Result<(), Error> {
+        log::debug!("SqliteStorage.insert_subscription | Trying register chat {} ({}) ...", chat_title, chat_id);
+        let (chat_id, chat_title) = (chat_id.to_owned(), chat_title.to_owned());
+        self.with_conn(move |conn| {
+            let err = |err| Error::Storage(format!("SqliteStorage.insert_subscription | Error regictering chat {} ({}): {:#?}", chat_title, chat_id, err));
+            let tx = conn.transaction().map_err(err)?;
+            // The group registered before, by its chat id or under the name key, is re-activated with its members
+            let updated = tx.execute("UPDATE groups SET title = ?2, archived = NULL WHERE id = ?1 OR chat_id = ?1", params![chat_id, chat_title])
+                .map_err(err)?;
+            if updated == 0 {
+                tx.execute(
+                    "INSERT INTO groups (id, position, chat_id, title) VALUES (?1, (SELECT COALESCE(MAX(position), -1) + 1 FROM groups), ?1, ?2)",
+                    params![chat_id, chat_title],
+                )
+                .map_err(err)?;
+            }
+            tx.commit().map_err(err)
+        }).await
+    }
+    //
+    //
+    async fn remove_subscription(&self, chat_id: ChatId) -> Result<Option<Subscription>, Error> {
+        let chat_id = chat_id.to_string();
+        self.with_conn(move |conn| {
+            let archived = Utc::now().to_rfc3339();
+            conn.execute("UPDATE groups SET archived = ?2 WHERE id = ?1 OR chat_id = ?1", params![chat_id, archived])
+                .map_err(|err| Error::Storage(format!("SqliteStorage.remove_subscription | Error archiving chat {}: {:#?}", chat_id, err)))?;
+            let subscriptions = read_subscriptions(conn)
+                .map_err(|err| Error::Storage(format!("SqliteStorage.remove_subscription | Error: {:#?}", err)))?;
+            Ok(subscriptions
+                .into_iter()
+                .find(|(group_id, group)| *group_id == chat_id || group.id.as_ref() == Some(&chat_id))
+                .map(|(_, group)| group))
+        }).await
+    }
+    //
+    //
+    async fn toggle_member(&self, group_id: &str, user: User) -> Result<Option<bool>, Error> {
+        let group_id = group_id.to_owned();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.toggle_member | Error {:#?}", err)))?;
+            let active: Option<bool> = tx.query_row("SELECT archived IS NULL FROM groups WHERE id = ?1", params![group_id], |row| row.get(0))
+                .optional()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.toggle_member | Group '{}' error: {:#?}", group_id, err)))?;
+            if active != Some(true) {
+                return Ok(None);
+            }
+            let removed = tx.execute("DELETE FROM memberships WHERE group_id = ?1 AND user_id = ?2", params![group_id, user.id.0])
+                .map_err(|err| Error::Storage(format!("SqliteStorage.toggle_member | Group '{}' member '{}' error: {:#?}", group_id, user.id, err)))?;
+            if removed == 0 {
+                insert_user_if_absent(&tx, &user)?;
+                tx.execute(
+                    "INSERT INTO memberships (group_id, user_id, position)
+                        VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM memberships WHERE group_id = ?1))",
+                    params![group_id, user.id.0],
+                ).map_err(|err| Error::Storage(format!("SqliteStorage.toggle_member | Group '{}' member '{}' error: {:#?}", group_id, user.id, err)))?;
+            }
+            tx.commit()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.toggle_member | Commit error {:#?}", err)))?;
+            Ok(Some(removed == 0))
+        }).await
+    }
+    //
+    //
+    async fn update_senders(&self, group_id: &str, f: Box<dyn for<'a> FnOnce(&'a mut Senders) + Send>) -> Result<Senders, Error> {
+        let group_id = group_id.to_owned();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.update_senders | Error {:#?}", err)))?;
+            let senders: Option<String> = tx.query_row("SELECT senders FROM groups WHERE id = ?1", params![group_id], |row| row.get(0))
+                .optional()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.update_senders | Group '{}' error: {:#?}", group_id, err)))?
+                .ok_or_else(|| Error::NotFound(format!("SqliteStorage.update_senders | Group '{}'", group_id)))?;
+            let mut senders = match senders {
+                Some(senders) => from_json(&senders)?,
+                None => Senders::default(),
+            };
+            f(&mut senders);
+            let json = match senders.is_empty() {
+                true => None,
+                false => Some(to_json(&senders)?),
+            };
+            tx.execute("UPDATE groups SET senders = ?2 WHERE id = ?1", params![group_id, json])
+                .map_err(|err| Error::Storage(format!("SqliteStorage.update_senders | Group '{}' error: {:#?}", group_id, err)))?;
+            tx.commit()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.update_senders | Commit error {:#?}", err)))?;
+            Ok(senders)
+        }).await
+    }
+    //
+    //
+    async fn subscriptions(&self) -> Result<Subscriptions, Error> {
+        self.with_conn(|conn| {
+            read_subscriptions(conn)
+                .map_err(|err| Error::Storage(format!("SqliteStorage.subscriptions | Error: {:#?}", err)))
+        }).await
+    }
+    //
+    //
+    async fn links(&self, user_id: ChatId) -> Result<Links, Error> {
+        let _ = user_id;
+        self.with_conn(|conn| {
+            let data: String = conn.query_row("SELECT data FROM links WHERE id = 0", [], |row| row.get(0))
+                .map_err(|err| Error::Storage(format!("SqliteStorage.links | Error: {:#?}", err)))?;
+            serde_json::from_str(&data)
+                .map_err(|err| Error::Parse(format!("SqliteStorage.links | Error in: {:?}\n\terror: {:?}", data, err)))
+        }).await
+    }
+    //
+    //
+    async fn user_roles(&self, user_id: ChatId) -> Result<UserRoles, Error> {
+        let _ = user_id;
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, hidden, title, role FROM roles ORDER BY position")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.user_roles | Error: {:#?}", err)))?;
+            let roles = stmt.query_map([], |row| {
+                    let id: String = row.get(0)?;
+                    let role: String = row.get(3)?;
+                    Ok((id, row.get::<_, bool>(1)?, row.get::<_, String>(2)?, role))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.user_roles | Error: {:#?}", err)))?;
+            roles.into_iter()
+                .map(|(id, hidden, title, role)| {
+                    let role = from_json(&role)?;
+                    Ok((id.clone(), UserRoleDb { hidden, id: Some(id), title, role }))
+                })
+                .collect()
+        }).await
+    }
+    //
+    //
+    async fn scheduled_notices(&self) -> Result<ScheduledNotices, Error> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, data FROM scheduled_notices ORDER BY id")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.scheduled_notices | Error: {:#?}", err)))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.scheduled_notices | Error: {:#?}", err)))?;
+            rows.into_iter()
+                .map(|(id, data)| {
+                    let notice: ScheduledNotice = from_json(&data)?;
+                    let notice = ScheduledNotice { id: id as u64, ..notice };
+                    Ok((id.to_string(), notice))
+                })
+                .collect()
+        }).await
+    }
+    //
+    //
+    async fn update_scheduled_notice(&self, notice: ScheduledNotice) -> Result<u64, Error> {
+        self.with_conn(move |conn| {
+            let data = to_json(&notice)?;
+            match notice.id {
+                0 => conn.execute("INSERT INTO scheduled_notices (data) VALUES (?1)", params![data])
+                    .map(|_| conn.last_insert_rowid() as u64),
+                id => conn.execute("INSERT OR REPLACE INTO scheduled_notices (id, data) VALUES (?1, ?2)", params![id as i64, data])
+                    .map(|_| id),
+            }
+            .map_err(|err| Error::Storage(format!("SqliteStorage.update_scheduled_notice | Error: {:#?}", err)))
+        }).await
+    }
+    //
+    //
+    async fn remove_scheduled_notice(&self, id: u64) -> Result<Option<ScheduledNotice>, Error> {
+        self.with_conn(move |conn| {
+            let data: Option<String> = conn.query_row("DELETE FROM scheduled_notices WHERE id = ?1 RETURNING data", params![id as i64], |row| row.get(0))
+                .optional()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.remove_scheduled_notice | Error: {:#?}", err)))?;
+            data.map(|data| from_json::<ScheduledNotice>(&data).map(|notice| ScheduledNotice { id, ..notice }))
+                .transpose()
+        }).await
+    }
+    //
+    //
+    async fn recurring_notices(&self) -> Result<RecurringNotices, Error> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, data FROM recurring_notices ORDER BY id")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.recurring_notices | Error: {:#?}", err)))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.recurring_notices | Error: {:#?}", err)))?;
+            rows.into_iter()
+                .map(|(id, data)| {
+                    let notice: RecurringNotice = from_json(&data)?;
+                    let notice = RecurringNotice { id: id as u64, ..notice };
+                    Ok((id.to_string(), notice))
+                })
+                .collect()
+        }).await
+    }
+    //
+    //
+    async fn update_recurring_notice(&self, notice: RecurringNotice) -> Result<u64, Error> {
+        self.with_conn(move |conn| {
+            let data = to_json(&notice)?;
+            match notice.id {
+                0 => conn.execute("INSERT INTO recurring_notices (data) VALUES (?1)", params![data])
+                    .map(|_| conn.last_insert_rowid() as u64),
+                id => conn.execute("INSERT OR REPLACE INTO recurring_notices (id, data) VALUES (?1, ?2)", params![id as i64, data])
+                    .map(|_| id),
+            }
+            .map_err(|err| Error::Storage(format!("SqliteStorage.update_recurring_notice | Error: {:#?}", err)))
+        }).await
+    }
+    //
+    //
+    async fn remove_recurring_notice(&self, id: u64) -> Result<Option<RecurringNotice>, Error> {
+        self.with_conn(move |conn| {
+            let data: Option<String> = conn.query_row("DELETE FROM recurring_notices WHERE id = ?1 RETURNING data", params![id as i64], |row| row.get(0))
+                .optional()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.remove_recurring_notice | Error: {:#?}", err)))?;
+            data.map(|data| from_json::<RecurringNotice>(&data).map(|notice| RecurringNotice { id, ..notice }))
+                .transpose()
+        }).await
+    }
+    //
+    //
+    async fn sent_notices(&self) -> Result<SentNotices, Error> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, data FROM sent_notices ORDER BY id")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.sent_notices | Error: {:#?}", err)))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.sent_notices | Error: {:#?}", err)))?;
+            rows.into_iter()
+                .map(|(id, data)| {
+                    let notice: SentNotice = from_json(&data)?;
+                    let notice = SentNotice { id: id as u64, ..notice };
+                    Ok((id.to_string(), notice))
+                })
+                .collect()
+        }).await
+    }
+    //
+    //
+    async fn update_sent_notice(&self, notice: SentNotice) -> Result<u64, Error> {
+        self.with_conn(move |conn| {
+            let data = to_json(&notice)?;
+            match notice.id {
+                0 => conn.execute("INSERT INTO sent_notices (data) VALUES (?1)", params![data])
+                    .map(|_| conn.last_insert_rowid() as u64),
+                id => conn.execute("INSERT OR REPLACE INTO sent_notices (id, data) VALUES (?1, ?2)", params![id as i64, data])
+                    .map(|_| id),
+            }
+            .map_err(|err| Error::Storage(format!("SqliteStorage.update_sent_notice | Error: {:#?}", err)))
+        }).await
+    }
+    //
+    //
+    async fn templates(&self) -> Result<Templates, Error> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, data FROM templates ORDER BY id")
+                .map_err(|err| Error::Storage(format!("SqliteStorage.templates | Error: {:#?}", err)))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| Error::Storage(format!("SqliteStorage.templates | Error: {:#?}", err)))?;
+            rows.into_iter()
+                .map(|(id, data)| {
+                    let template: NoticeTemplate = from_json(&data)?;
+                    let template = NoticeTemplate { id: id as u64, ..template };
+                    Ok((id.to_string(), template))
+                })
+                .collect()
+        }).await
+    }
+    //
+    //
+    async fn update_template(&self, template: NoticeTemplate) -> Result<u64, Error> {
+        self.with_conn(move |conn| {
+            let data = to_json(&template)?;
+            match template.id {
+                0 => conn.execute("INSERT INTO templates (data) VALUES (?1)", params![data])
+                    .map(|_| conn.last_insert_rowid() as u64),
+                id => conn.execute("INSERT OR REPLACE INTO templates (id, data) VALUES (?1, ?2)", params![id as i64, data])
+                    .map(|_| id),
+            }
+            .map_err(|err| Error::Storage(format!("SqliteStorage.update_template | Error: {:#?}", err)))
+        }).await
+    }
+    //
+    //
+    async fn remove_template(&self, id: u64) -> Result<Option<NoticeTemplate>, Error> {
+        self.with_conn(move |conn| {
+            let data: Option<String> = conn.query_row("DELETE FROM templates WHERE id = ?1 RETURNING data", params![id as i64], |row| row.get(0))
+                .optional()
+                .map_err(|err| Error::Storage(format!("SqliteStorage.remove_template | Error: {:#?}", err)))?;
+            data.map(|data| from_json::<NoticeTemplate>(&data).map(|template| NoticeTemplate { id, ..template }))
+                .transpose()
+        }).await
+    }
+}
+///
+/// Inserts or updates all fields of the user
+pub(super) fn upsert_user(conn: &Connection, user: &User) -> Result<(), Error> {
+    conn.execute(
+        "INSERT INTO users (id, name, contact, address, last_seen, role) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(id) DO UPDATE SET name = excluded.name, contact = excluded.contact, address = excluded.address, last_seen = excluded.last_seen, role = excluded.role",
+        params![user.id.0, user.name, user.contact, user.address, user.last_seen, to_json(&user.role)?],
+    )
+    .map(|_| ())
+    .map_err(|err| Error::Storage(format!("{:?}", err)))
+}
+///
+/// Inserts user if not exists, existing user is kept as is
+pub(super) fn insert_user_if_absent(conn: &Connection, user: &User) -> Result<(), Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO users (id, name, contact, address, last_seen, role) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![user.id.0, user.name, user.contact, user.address, user.last_seen, to_json(&user.role)?],
+    )
+    .map(|_| ())
+    .map_err(|err| Error::Storage(format!("{:?}", err)))
+}
+///
+/// Reads all groups with their members
+fn read_subscriptions(conn: &Connection) -> Result<Subscriptions, rusqlite::Error> {
+    let mut groups_stmt = conn.prepare("SELECT id, chat_id, title, archived, senders FROM groups ORDER BY position")?;
+    let mut members_stmt = conn.prepare(
+        "SELECT u.id, u.name, u.contact, u.address, u.last_seen, u.role FROM memberships m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.group_id = ?1 ORDER BY m.position",
+    )?;
+    let groups = groups_stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?, row.get::<_, Option<String>>(4)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut subscriptions = Subscriptions::new();
+    for (group_id, chat_id, title, archived, senders) in groups {
+        let senders = match senders {
+            Some(senders) => from_json(&senders).map_err(|err| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, err.into()))?,
+            None => Senders::default(),
+        };
+        let members = members_stmt
+            .query_map(params![group_id], read_user)?
+            .map(|user| user.map(|user| (user.id.to_string(), user)))
+            .collect::<Result<IndexMap<_, _>, _>>()?;
+        subscriptions.insert(group_id, Subscription { id: chat_id, title, members, archived, senders });
+    }
+    Ok(subscriptions)
+}
+///
+/// Maps `users` row into the User
+fn read_user(row: &rusqlite::Row) -> Result<User, rusqlite::Error> {
+    let role: String = row.get(5)?;
+    Ok(User {
+        id: ChatId(row.get(0)?),
+        name: row.get(1)?,
+        contact: row.get(2)?,
+        address: row.get(3)?,
+        subscriptions: vec![],
+        last_seen: row.get(4)?,
+        role: from_json(&role).map_err(|err| rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, err.into()))?,
+    })
+}
+///
+///
+pub(super) fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    serde_json::to_string(value).map_err(|err| Error::Parse(format!("SqliteStorage.to_json | Error: {:?}", err)))
+}
+///
+///
+fn from_json<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, Error> {
+    serde_json::from_str(value).map_err(|err| Error::Parse(format!("SqliteStorage.from_json | Error in: {:?}\n\terror: {:?}", value, err)))
+}
+//
+//
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use teloxide::types::ChatId;
+    use crate::{db::storage::Storage, user::user_role::UserRole};
+    use super::{SqliteStorage, MIGRATIONS};
+    ///
+    /// Users, roles, groups, memberships, senders and archive state are read back as written
+    #[tokio::test]
+    async fn round_trip() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        let version: usize = storage.with_conn(|conn| Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap())).await.unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        storage.user_insert(7, "Anton".to_owned(), Some("@anton".to_owned()), None, None, &[UserRole::Guest]).await.unwrap();
+        storage.user_insert(8, "Maria".to_owned(), None, None, None, &[UserRole::Guest]).await.unwrap();
+        let mut user = storage.user(&ChatId(7)).await.unwrap();
+        user.add_role(UserRole::Sender);
+        user.add_role(UserRole::Moder);
+        storage.user_update(user).await.unwrap();
+        let user = storage.user(&ChatId(7)).await.unwrap();
+        assert_eq!(user.role, vec![UserRole::Sender, UserRole::Moder]);
+        assert_eq!(user.contact.as_deref(), Some("@anton"));
+        assert_eq!(storage.users().await.unwrap().keys().collect::<Vec<_>>(), ["7", "8"]);
+        storage.insert_subscription("-100", "Office").await.unwrap();
+        storage.insert_subscription("-200", "Lab").await.unwrap();
+        for id in [8, 7] {
+            let user = storage.user(&ChatId(id)).await.unwrap();
+            assert_eq!(storage.toggle_member("-100", user).await.unwrap(), Some(true));
+        }
+        let maria = storage.user(&ChatId(8)).await.unwrap();
+        assert_eq!(storage.toggle_member("-200", maria.clone()).await.unwrap(), Some(true));
+        assert_eq!(storage.toggle_member("-200", maria).await.unwrap(), Some(false));
+        storage.update_senders("-100", Box::new(|senders| {
+            senders.toggle_user("7");
+            senders.toggle_role(UserRole::Moder);
+        })).await.unwrap();
+        assert!(storage.remove_subscription(ChatId(-200)).await.unwrap().is_some_and(|group| !group.is_active()));
+        let groups = storage.subscriptions().await.unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["-100", "-200"]);
+        let office = &groups["-100"];
+        assert_eq!(office.members.keys().collect::<Vec<_>>(), ["8", "7"], "members are kept in the subscribe order");
+        assert_eq!(office.members["7"].role, vec![UserRole::Sender, UserRole::Moder]);
+        assert_eq!(office.senders.users, vec!["7".to_owned()]);
+        assert_eq!(office.senders.roles, vec![UserRole::Moder]);
+        assert!(office.is_active());
+        assert!(groups["-200"].members.is_empty());
+        assert!(!groups["-200"].is_active());
+        let user = storage.user(&ChatId(7)).await.unwrap();
+        assert_eq!(storage.toggle_member("-200", user).await.unwrap(), None, "archived group isn't changed");
+        storage.insert_subscription("-200", "Lab").await.unwrap();
+        assert!(storage.subscriptions().await.unwrap()["-200"].is_active());
+    }
+    ///
+    /// Group keyed by the name is found by its chat id, re-added with the members instead of the new empty group
+    #[tokio::test]
+    async fn remove_subscription_restores_name_keyed() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        storage.user_insert(7, "Anton".to_owned(), None, None, None, &[UserRole::Member]).await.unwrap();
+        storage.with_conn(|conn| {
+            conn.execute("INSERT INTO groups (id, position, chat_id, title) VALUES ('TKZ_OFFICE_GROUP', 0, '-4224543713', 'Office-group')", []).unwrap();
+            Ok(())
+        }).await.unwrap();
+        let user = storage.user(&ChatId(7)).await.unwrap();
+        assert_eq!(storage.toggle_member("TKZ_OFFICE_GROUP", user).await.unwrap(), Some(true));
+        assert!(storage.remove_subscription(ChatId(-4224543713)).await.unwrap().is_some_and(|group| !group.is_active()));
+        storage.insert_subscription("-4224543713", "Office").await.unwrap();
+        let groups = storage.subscriptions().await.unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["TKZ_OFFICE_GROUP"]);
+        let group = &groups["TKZ_OFFICE_GROUP"];
+        assert!(group.is_active());
+        assert_eq!(group.title, "Office");
+        assert!(group.members.contains_key("7"));
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use teloxide::types::ChatId;
+use crate::{kernel::error::Error, links::Links, menu::MenuItem, notice::{recurring_notice::{RecurringNotice, RecurringNotices}, scheduled_notice::{ScheduledNotice, ScheduledNotices}, sent_notice::{SentNotice, SentNotices}, template::{NoticeTemplate, Templates}}, subscribe::subscription::{Senders, Subscription, Subscriptions}, user::{user::User, user_role::{UserRole, UserRoles}}};
+///
+/// Shared storage handle, passed to the handlers via `dptree::deps!`
+pub type Db = Arc<dyn Storage>;
+///
+/// Storage of the users, subscriptions, links, roles and menu
+#[async_trait]
+pub trait Storage: Send + Sync {
+    ///
+    /// Returns main menu items
+    async fn menu(&self) -> Result<IndexMap<String, MenuItem>, Error>;
+    ///
+    /// Inserts a user, or updates name, contact, address and last_seen if user already exists
+    async fn user_insert(&self, user_id: u64, name: String, contact: Option<String>, address: Option<String>, last_seen: Option<DateTime<Utc>>, role: &[UserRole]) -> Result<(), Error>;
+    ///
+    /// Updates or Inserts a user
+    async fn user_update(&self, user: User) -> Result<(), Error>;
+    ///
+    /// Returns user from storage
+    async fn user(&self, chat_id: &ChatId) -> Result<User, Error>;
+    ///
+    /// Returns all users from storage
+    async fn users(&self) -> Result<IndexMap<String, User>, Error>;
+    ///
+    /// Registers a chat, the bot was added to, as subscription group,
+    /// archived group is re-activated with its members intact
+    async fn insert_subscription(&self, chat_id: &str, chat_title: &str) -> Result<(), Error>;
+    ///
+    /// Archives the group of the chat, the bot was removed from, members are kept.
+    /// Returns archived group, or None if chat isn't registered as group
+    async fn remove_subscription(&self, chat_id: ChatId) -> Result<Option<Subscription>, Error>;
+    ///
+    /// Adds the user to the members of the active group, or removes if the user is already its member.
+    /// Returns true if the user is added, false if removed, None if the group isn't found or archived
+    async fn toggle_member(&self, group_id: &str, user: User) -> Result<Option<bool>, Error>;
+    ///
+    /// Changes the senders allowed to notice the group by `f` in one step, returns the changed senders
+    async fn update_senders(&self, group_id: &str, f: Box<dyn for<'a> FnOnce(&'a mut Senders) + Send>) -> Result<Senders, Error>;
+    ///
+    /// Returns subscriptions from storage
+    async fn subscriptions(&self) -> Result<Subscriptions, Error>;
+    ///
+    /// Returns Links
+    async fn links(&self, user_id: ChatId) -> Result<Links, Error>;
+    ///
+    /// Returns UserRoles
+    async fn user_roles(&self, user_id: ChatId) -> Result<UserRoles, Error>;
+    ///
+    /// Returns pending scheduled notices
+    async fn scheduled_notices(&self) -> Result<ScheduledNotices, Error>;
+    ///
+    /// Inserts new scheduled notice (with id 0) or replaces the existing one, returns id of the notice
+    async fn update_scheduled_notice(&self, notice: ScheduledNotice) -> Result<u64, Error>;
+    ///
+    /// Removes scheduled notice, returns removed one, or None if it isn't found
+    async fn remove_scheduled_notice(&self, id: u64) -> Result<Option<ScheduledNotice>, Error>;
+    ///
+    /// Returns recurring notices
+    async fn recurring_notices(&self) -> Result<RecurringNotices, Error>;
+    ///
+    /// Inserts new recurring notice (with id 0) or replaces the existing one, returns id of the notice
+    async fn update_recurring_notice(&self, notice: RecurringNotice) -> Result<u64, Error>;
+    ///
+    /// Removes recurring notice, returns removed one, or None if it isn't found
+    async fn remove_recurring_notice(&self, id: u64) -> Result<Option<RecurringNotice>, Error>;
+    ///
+    /// Returns sent notices with the delivery reports
+    async fn sent_notices(&self) -> Result<SentNotices, Error>;
+    ///
+    /// Inserts new sent notice (with id 0) or replaces the existing one, returns id of the notice
+    async fn update_sent_notice(&self, notice: SentNotice) -> Result<u64, Error>;
+    ///
+    /// Returns notice templates
+    async fn templates(&self) -> Result<Templates, Error>;
+    ///
+    /// Inserts new notice template (with id 0) or replaces the existing one, returns id of the template
+    async fn update_template(&self, template: NoticeTemplate) -> Result<u64, Error>;
+    ///
+    /// Removes notice template, returns removed one, or None if it isn't found
+    async fn remove_template(&self, id: u64) -> Result<Option<NoticeTemplate>, Error>;
+}
+///
+/// Returns main menu items, common for all storages
+pub fn default_menu() -> IndexMap<String, MenuItem> {
+    IndexMap::from([
+        ("Links", "/Links"),
+        ("Notice", "/Notice"),
+        ("RequestAccess", "/RequestAccess"),
+        ("Subscribe", "/Subscribe"),
+        ("Scheduled", "/Scheduled"),
+        ("Recurring", "/Recurring"),
+        ("Help", "/Help"),
+    ])
+        .into_iter()
+        .map(|(title, command)| (title.to_owned(), MenuItem { title: title.to_owned(), command: command.to_owned()}))
+        .collect()
+}
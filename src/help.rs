@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
 use crate::{kernel::error::HandlerResult, loc::loc, states::{MainState, MyDialogue}, user::user::User};
 ///
 /// 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HelpState {
     pub prev_state: MainState,  // Where to go on Back btn
     pub user: User,        // User id doing notice
@@ -1,35 +1,79 @@
-//! 
-//! fn foo() -> Result<String, Box<dyn std::error::Error>> {
-//!     Err(Box::new(StrError("Error...")))
+//!
+//! fn foo() -> Result<User, Error> {
+//!     Err(Error::NotFound(format!("User '{}'", user_id)))
 //! }
+use std::fmt;
+use teloxide::RequestError;
+use crate::loc::loc;
 pub type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 ///
-/// Error container
+/// Errors of the storage and handlers
 #[derive(Debug)]
-pub struct StrError(pub String);
-// Error doesn't require you to implement any methods, but
-// your type must also implement Debug and Display.
-impl std::error::Error for StrError {}
+pub enum Error {
+    /// Requested item not found, like user who never sent '/start'
+    NotFound(String),
+    /// Storage file / database can't be read or written
+    Storage(String),
+    /// Stored or received data can't be parsed / serialized
+    Parse(String),
+    /// User has no role required for the action, contains localized reason
+    PermissionDenied(String),
+    /// Telegram Bot API request failed
+    Telegram(RequestError),
+}
 //
 //
-impl std::fmt::Display for StrError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Delegate to the Display impl for `&str`:
-        self.0.fmt(f)
+impl Error {
+    ///
+    /// Returns localized text to be replied to the user
+    pub fn user_message(&self) -> String {
+        match self {
+            Error::NotFound(_) => loc("Not found, please type '/Start' to begin"),
+            Error::Storage(_) => loc("Storage is temporarily unavailable, please try again later"),
+            Error::Parse(_) => loc("Stored data is damaged, please contact the administrator"),
+            Error::PermissionDenied(reason) => format!("{}\n{}", loc("Access denied"), reason),
+            Error::Telegram(_) => loc("Telegram is temporarily unavailable, please try again later"),
+        }
+    }
+    ///
+    /// Returns the typed error from the handler error, if it contains one
+    pub fn from_handler(err: Box<dyn std::error::Error + Send + Sync>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        match err.downcast::<Error>() {
+            Ok(err) => Ok(*err),
+            Err(err) => match err.downcast::<RequestError>() {
+                Ok(err) => Ok(Error::Telegram(*err)),
+                Err(err) => Err(err),
+            },
+        }
     }
 }
-impl From<String> for StrError {
-    fn from(value: String) -> Self {
-        StrError(value)
+//
+//
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(what) => write!(f, "Not found: {}", what),
+            Error::Storage(err) => write!(f, "Storage error: {}", err),
+            Error::Parse(err) => write!(f, "Parse error: {}", err),
+            Error::PermissionDenied(reason) => write!(f, "Permission denied: {}", reason),
+            Error::Telegram(err) => write!(f, "Telegram error: {}", err),
+        }
     }
 }
-impl From<&str> for StrError {
-    fn from(value: &str) -> Self {
-        StrError(value.to_owned())
+//
+//
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Telegram(err) => Some(err),
+            _ => None,
+        }
     }
 }
-impl Into<Box<StrError>> for &str {
-    fn into(self) -> Box<StrError> {
-        Box::new(StrError(self.to_owned()))
+//
+//
+impl From<RequestError> for Error {
+    fn from(value: RequestError) -> Self {
+        Error::Telegram(value)
     }
-}
\ No newline at end of file
+}
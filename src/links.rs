@@ -1,7 +1,7 @@
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
-use crate::{db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MainState, MyDialogue}};
+use crate::{db::storage::Db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MainState, MyDialogue}};
 ///
 /// Links menu
 #[derive(Debug, Clone, PartialEq)]
@@ -39,7 +39,7 @@ pub struct Links {
 }
 ///
 /// 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinksState {
     pub prev_state: MainState,
     // pub prev_level: Option<String>,
@@ -49,12 +49,12 @@ pub struct LinksState {
 }
 ///
 ///  
-pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, mut state: LinksState) -> HandlerResult {
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, mut state: LinksState, db: Db) -> HandlerResult {
     let user_id = state.chat_id;
     log::debug!("links.enter | state: {:#?}", state);
     let links =  match state.child.get(&state.level) {
         Some(links) => links.to_owned(),
-        None => db::links(user_id).await?,
+        None => db.links(user_id).await?,
     };
     log::debug!("links.enter | links: {:#?}", links);
     state.child = links.child.clone();
@@ -18,7 +18,143 @@ lazy_static!{
     static ref RU: HashMap<&'static str, &'static str> = vec![
         ("You are in the main menu", "Вы в главном меню"),
         ("Sorry, the bot has been restarted", "Извините, бот был перезапущен"),
+        ("Please type '/Start' to begin", "Наберите '/Start' чтобы начать"),
         ("Error, start again", "Ошибка, начните заново"),
-        ("Cancel", "Отмена")
+        ("Cancel", "Отмена"),
+        ("Not found, please type '/Start' to begin", "Не найдено, наберите '/Start' чтобы начать"),
+        ("Storage is temporarily unavailable, please try again later", "Хранилище временно недоступно, попробуйте позже"),
+        ("Stored data is damaged, please contact the administrator", "Сохраненные данные повреждены, обратитесь к администратору"),
+        ("Access denied", "Доступ запрещен"),
+        ("Telegram is temporarily unavailable, please try again later", "Telegram временно недоступен, попробуйте позже"),
+        ("Send the notice above to the group", "Отправить уведомление выше в группу"),
+        ("Recipients", "Получателей"),
+        ("Send", "Отправить"),
+        ("Edit", "Изменить"),
+        ("Notice sent", "Уведомление отправлено"),
+        ("Notice canceled", "Уведомление отменено"),
+        ("Send now", "Отправить сейчас"),
+        ("Schedule", "Запланировать"),
+        ("Type the date and time to send the notice at", "Введите дату и время отправки уведомления"),
+        ("Wrong or passed time", "Неверное или прошедшее время"),
+        ("Notice scheduled at", "Уведомление запланировано на"),
+        ("Scheduled", "Запланированные"),
+        ("Scheduled notices", "Запланированные уведомления"),
+        ("No scheduled notices", "Нет запланированных уведомлений"),
+        ("Notice above is scheduled at", "Уведомление выше запланировано на"),
+        ("to the group", "в группу"),
+        ("Reschedule", "Перенести"),
+        ("Cancel notice", "Отменить уведомление"),
+        ("Scheduled notice canceled", "Запланированное уведомление отменено"),
+        ("Repeat", "Повторять"),
+        ("Type the rule to repeat the notice by", "Введите правило повторения уведомления"),
+        ("cron: minute hour day month weekday", "cron: минута час день месяц день_недели"),
+        ("in the other time zone", "в другом часовом поясе"),
+        ("Notice is too long, shorten it and send again", "Уведомление слишком длинное, сократите его и отправьте снова"),
+        ("Notice isn't sent to the groups you aren't allowed to notice anymore", "Уведомление не отправлено в группы, в которые вам больше нельзя отправлять уведомления"),
+        ("Wrong rule or it never fires", "Неверное правило, или оно никогда не срабатывает"),
+        ("Notice is repeated", "Уведомление повторяется"),
+        ("Next at", "Следующее"),
+        ("Recurring", "Повторяющиеся"),
+        ("Recurring notices", "Повторяющиеся уведомления"),
+        ("No recurring notices", "Нет повторяющихся уведомлений"),
+        ("Notice above is repeated", "Уведомление выше повторяется"),
+        ("Change rule", "Изменить правило"),
+        ("Delete notice", "Удалить уведомление"),
+        ("Recurring notice deleted", "Повторяющееся уведомление удалено"),
+        ("Delivered", "Доставлено"),
+        ("failed", "ошибок"),
+        ("blocked", "заблокировано"),
+        ("pending", "в очереди"),
+        ("more", "еще"),
+        ("Notice delivered to the group", "Уведомление доставлено в группу"),
+        ("Reports", "Отчеты"),
+        ("Sent notices", "Отправленные уведомления"),
+        ("No sent notices", "Нет отправленных уведомлений"),
+        ("History", "История"),
+        ("Select group to browse the history", "Выберите группу для просмотра истории"),
+        ("No groups to browse the history, subscribe to the group first", "Нет групп для просмотра истории, сначала подпишитесь на группу"),
+        ("you aren't subscribed to the group", "вы не подписаны на группу"),
+        ("No notices sent to the group", "Нет уведомлений, отправленных в группу"),
+        ("Notices of the group", "Уведомления группы"),
+        ("page", "страница"),
+        ("Continue", "Продолжить"),
+        ("✅ Acknowledge", "✅ Ознакомлен"),
+        ("Acknowledged, thank you", "Ознакомление отмечено, спасибо"),
+        ("Already acknowledged", "Ознакомление уже отмечено"),
+        ("Requires acknowledgement", "Требует ознакомления"),
+        ("Acknowledgements", "Ознакомление"),
+        ("Acknowledgements of the notice to the group", "Ознакомление с уведомлением в группу"),
+        ("acknowledged", "ознакомлены"),
+        ("not acknowledged", "не ознакомлены"),
+        ("Refresh", "Обновить"),
+        ("Reminders", "Напоминания"),
+        ("off", "выкл"),
+        ("sent", "отправлено"),
+        ("no reminders", "без напоминаний"),
+        ("Wrong reminders policy", "Неверный порядок напоминаний"),
+        ("Type how often to remind the recipients not acknowledged the notice and how many times, then the Moders of the group get the list of them", "Введите, как часто и сколько раз напоминать получателям, не подтвердившим уведомление, после чего модераторы группы получат их список"),
+        ("⏰ Reminder: please read the notice above and acknowledge it", "⏰ Напоминание: прочитайте уведомление выше и подтвердите его"),
+        ("Notice to the group is not acknowledged", "Уведомление группе не подтверждено"),
+        ("reminders", "напоминаний"),
+        ("Reported to the Moders at", "Передано модераторам"),
+        ("Silent", "Без звука"),
+        ("Normal", "Обычное"),
+        ("Urgent", "Срочное"),
+        ("URGENT", "СРОЧНО"),
+        ("Priority", "Приоритет"),
+        ("Templates", "Шаблоны"),
+        ("Template", "Шаблон"),
+        ("No templates yet", "Шаблонов пока нет"),
+        ("Select template of the notice", "Выберите шаблон уведомления"),
+        ("Add template", "Добавить шаблон"),
+        ("Type the value of", "Введите значение"),
+        ("Type the title of the template on the first line and its text on the next lines, {name} in the text is asked when the template is used", "Введите название шаблона в первой строке и его текст в следующих строках, {name} в тексте запрашивается при использовании шаблона"),
+        ("Template saved", "Шаблон сохранен"),
+        ("Template deleted", "Шаблон удален"),
+        ("only Admin can edit the templates", "изменять шаблоны может только администратор"),
+        ("Yes", "Да"),
+        ("No", "Нет"),
+        ("Maybe", "Возможно"),
+        ("buttons", "кнопки"),
+        ("poll", "опрос"),
+        ("Answers", "Ответы"),
+        ("Deadline", "Срок"),
+        ("Your answer", "Ваш ответ"),
+        ("Your answer to the notice above", "Ваш ответ на уведомление выше"),
+        ("Answers are closed", "Прием ответов закрыт"),
+        ("Answers to the notice to the group", "Ответы на уведомление группе"),
+        ("no answer", "без ответа"),
+        ("Answers close at", "Прием ответов до"),
+        ("Answers closed at", "Прием ответов закрыт в"),
+        ("Type the date and time the answers close at", "Введите дату и время окончания приема ответов"),
+        ("no deadline", "без срока"),
+        ("Expires in", "Устареет через"),
+        ("Expires at", "Устареет"),
+        ("Expired at", "Устарело"),
+        ("expired", "устарело"),
+        ("deleted", "удалено"),
+        ("marked expired", "отмечено устаревшим"),
+        ("never expires", "не устаревает"),
+        ("Wrong time-to-live", "Неверное время актуальности"),
+        ("Type how long the notice is actual, then it's deleted from every chat", "Введите, сколько уведомление актуально, после чего оно будет удалено из всех чатов"),
+        ("Senders", "Отправители"),
+        ("Moders", "Модераторы"),
+        ("users", "пользователей"),
+        ("every Sender", "любой отправитель"),
+        ("Select group to edit the senders allowed to notice it", "Выберите группу, чтобы изменить отправителей, которым разрешено ее уведомлять"),
+        ("Senders allowed to notice the group", "Отправители, которым разрешено уведомлять группу"),
+        ("Every Sender can notice the group if nobody is checked, Admins can notice every group", "Если никто не отмечен, группу может уведомлять любой отправитель, администраторы могут уведомлять любую группу"),
+        ("only Moder can edit the senders of the groups", "изменять отправителей групп может только модератор"),
+        ("you aren't allowed to notice the group", "вам не разрешено уведомлять группу"),
+        ("Correct text", "Исправить текст"),
+        ("Recall", "Отозвать"),
+        ("Corrected at", "Исправлено"),
+        ("Recalled at", "Отозвано"),
+        ("Type the corrected text, it replaces the notice text in every chat", "Введите исправленный текст, он заменит текст уведомления во всех чатах"),
+        ("Notice corrected in the chats", "Уведомление исправлено в чатах"),
+        ("Delete the notice from every chat", "Удалить уведомление из всех чатов"),
+        ("Notice deleted from the chats", "Уведомление удалено из чатов"),
+        ("Notice is being delivered, already recalled or expired", "Уведомление еще доставляется, уже отозвано или устарело"),
+        ("Notice can't be empty, send a text, photo, document, video, voice or sticker", "Уведомление не может быть пустым, отправьте текст, фото, документ, видео, голосовое сообщение или стикер"),
     ].into_iter().collect();
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+#![allow(clippy::empty_docs, clippy::module_inception, clippy::useless_format, clippy::collapsible_match)]
 mod config;
 mod message;
 mod loc;
@@ -13,9 +14,10 @@ mod kernel;
 //
 use std::{env, fmt::Debug, process::Command, sync::Arc};
 use futures::future::BoxFuture;
-use config::AppConfig;
+use config::{cli_args::{CliArgs, CliCommand, USAGE}, AppConfig, DataConfig, StorageKind};
+use db::{dialogue_storage::JsonDialogueStorage, json_storage::JsonStorage, storage::Db};
 use states::State;
-use teloxide::{dispatching::dialogue::InMemStorage, error_handlers::ErrorHandler, prelude::*, types::UpdateKind};
+use teloxide::{dispatching::dialogue::Storage, error_handlers::ErrorHandler, prelude::*, types::UpdateKind};
 ///
 ///
 struct MyErrorHandler {}
@@ -36,19 +38,25 @@ where
 }
 ///
 /// 
-async fn default_handler(upd: Arc<Update>) {
+async fn default_handler(upd: Arc<Update>, bot: Bot, db: Db) {
     match &upd.kind {
         UpdateKind::MyChatMember(chat_member) => {
             if chat_member.new_chat_member.is_member() {    //m.old_chat_member.is_left() && 
-                if let Err(err) = crate::states::new_chat_member(chat_member).await {
+                if let Err(err) = crate::states::new_chat_member(chat_member, &db).await {
                     log::warn!("main | Error in states.new_chat_member: {:?}", err);
                 };
             } else if chat_member.new_chat_member.is_left() { // m.old_chat_member.is_member() && 
-                if let Err(err) = crate::states::left_chat_member(chat_member).await {
+                if let Err(err) = crate::states::left_chat_member(&bot, chat_member, &db).await {
                     log::warn!("main | Error in states.left_chat_member: {:?}", err);
                 };
             }
         }
+        // Answers to the notice polls have no chat, so they don't reach the dialogues
+        UpdateKind::PollAnswer(answer) => {
+            if let Err(err) = crate::notice::rsvp::poll_answer(&bot, answer, &db).await {
+                log::warn!("main | Error in rsvp.poll_answer: {:?}", err);
+            }
+        }
         _ => {
             log::warn!("main | Unhandled update: {:?}", upd);
         }
@@ -66,14 +74,38 @@ async fn main() {
     env::set_var("RUST_BACKTRACE", "1");
     pretty_env_logger::init();
     log::info!("Starting dialogue bot...");
-    let config = AppConfig::read("./config.yaml");
+    let args = match CliArgs::from_env() {
+        Ok(args) => args,
+        Err(err) => {
+            log::error!("main | {}\n{}", err, USAGE);
+            return;
+        }
+    };
+    let config = AppConfig::read(&args.config).override_with(|name| env::var(name).ok());
     log::info!("config: {:#?}", config);
+    match args.command {
+        CliCommand::Run => {}
+        // One-shot import of the JSON files into the SQLite database
+        CliCommand::ImportJson(assets) => {
+            let data = assets.map_or(config.data.clone(), |dir| DataConfig { dir, ..config.data.clone() });
+            import_json(config.storage.kind, &config.data, &data).await;
+            return;
+        }
+    }
     env::set_var("TELOXIDE_TOKEN", config.bot.connection.token);
     let bot = Bot::from_env();
+    let db = open_storage(config.storage.kind, &config.data);
+    let dialogues = JsonDialogueStorage::open(config.data.path(&config.data.dialogues), State::restarted()).erase();
+    let default_bot = bot.clone();
+    tokio::spawn(notice::scheduler::run(bot.clone(), db.clone(), config.schedule.clone()));
+    tokio::spawn(notice::outbox::run(bot.clone(), db.clone()));
+    tokio::spawn(notice::escalation::run(bot.clone(), db.clone()));
+    tokio::spawn(notice::rsvp::run(bot.clone(), db.clone()));
+    tokio::spawn(notice::expiry::run(bot.clone(), db.clone()));
     Dispatcher::builder(bot.clone(), states::schema())
-        .dependencies(dptree::deps![InMemStorage::<State>::new()])
+        .dependencies(dptree::deps![dialogues, db.clone(), config.schedule.clone()])
         // All unhandled updates redirects to the default_handler
-        .default_handler(default_handler)
+        .default_handler(move |upd| default_handler(upd, default_bot.clone(), db.clone()))
         // If the dispatcher fails for some reason, execute this handler.
         .error_handler(Arc::new(MyErrorHandler{}))
         .enable_ctrlc_handler()
@@ -82,6 +114,49 @@ async fn main() {
         .await;
 }
 ///
+/// Returns storage backend selected in the config
+fn open_storage(kind: StorageKind, data: &DataConfig) -> Db {
+    log::info!("main.open_storage | {:?} storage in: {:?}", kind, data.dir);
+    match kind {
+        StorageKind::Json => Arc::new(JsonStorage::new(data)),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => match db::sqlite_storage::SqliteStorage::open(data.path(&data.database)) {
+            Ok(storage) => Arc::new(storage),
+            Err(err) => panic!("main.open_storage | Error: {}", err),
+        },
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => panic!("main.open_storage | SQLite storage requires the 'sqlite' cargo feature"),
+    }
+}
+///
+/// Imports JSON files of the `from` into the SQLite database configured in the `data`
+#[cfg(feature = "sqlite")]
+async fn import_json(kind: StorageKind, data: &DataConfig, from: &DataConfig) {
+    if kind != StorageKind::Sqlite {
+        log::error!("main.import_json | Storage kind must be 'sqlite', but configured: {:?}", kind);
+        return;
+    }
+    let database = data.path(&data.database);
+    log::info!("main.import_json | Importing '{:?}' into '{:?}'...", from.dir, database);
+    let result = match db::sqlite_storage::SqliteStorage::open(database) {
+        Ok(storage) => storage.import_json(from).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(report) => {
+            log::info!("main.import_json | Done");
+            println!("{}", report);
+        }
+        Err(err) => log::error!("main.import_json | Error: {}", err),
+    }
+}
+///
+/// Imports are available with the `sqlite` cargo feature only
+#[cfg(not(feature = "sqlite"))]
+async fn import_json(_kind: StorageKind, _data: &DataConfig, _from: &DataConfig) {
+    log::error!("main.import_json | Import requires the 'sqlite' cargo feature");
+}
+///
 /// 
 fn clear_console() {
     let cmd = Command::new("/bin/bash").arg("-c").arg("clear").spawn();
@@ -9,8 +9,7 @@ use teloxide::{
     types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
 };
 use arraylib::iter::IteratorExt;
-use crate::{kernel::error::HandlerResult, user::{user::User, user_role::UserRole}};
-use crate::db as db;
+use crate::{db::storage::Db, kernel::error::HandlerResult, user::{user::User, user_role::UserRole}};
 use crate::loc::*;
 ///
 /// Main menu
@@ -19,6 +18,8 @@ pub enum MainMenu {
    Links(String),   // Links menu
    Notice,          // Notice menu
    Subscribe,       // subscribe to receive notice
+   Scheduled,       // Scheduled notices menu
+   Recurring,       // Recurring notices menu
    RequestAccess,   // User request access
    Help,
    Done,            // Exit menu
@@ -32,6 +33,8 @@ impl MainMenu {
             "/notice" | "/Notice" => Self::Notice,
             "/links" | "/Links" => Self::Links(s.to_owned()),
             "/subscribe" | "/Subscribe" => Self::Subscribe,
+            "/scheduled" | "/Scheduled" => Self::Scheduled,
+            "/recurring" | "/Recurring" => Self::Recurring,
             "/requestaccess" | "/RequestAccess" => Self::RequestAccess,
             "/help" | "/Help" => Self::Help,
             "/done" | "/Done" => Self::Done,
@@ -50,8 +53,8 @@ pub struct MenuItem {
 }
 ///
 /// Create a MainMenu
-pub async fn enter(bot: &Bot, msg: &Message, user: &User) -> HandlerResult {
-    let menu =  db::menu().await?;
+pub async fn enter(bot: &Bot, msg: &Message, user: &User, db: &Db) -> HandlerResult {
+    let menu =  db.menu().await?;
     let markup = markup(user, &menu).await?;
     let text = "Main menu";
     bot.send_message(msg.chat.id, text)
@@ -64,8 +67,8 @@ pub async fn enter(bot: &Bot, msg: &Message, user: &User) -> HandlerResult {
 }
 ///
 /// Reloads a MainMenu
-pub async fn reload(bot: &Bot, msg: &Message, user: &User) -> HandlerResult {
-    let menu =  db::menu().await?;
+pub async fn reload(bot: &Bot, msg: &Message, user: &User, db: &Db) -> HandlerResult {
+    let menu =  db.menu().await?;
     let markup = markup(user, &menu).await?;
     let text = "Main menu";
     crate::message::edit_markup_message_or_send(bot, msg, &markup, text).await
@@ -99,6 +102,9 @@ async fn markup(user: &User, menu: &IndexMap<String, MenuItem>) -> Result<Inline
                 "Subscribe" => {
                     user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender, UserRole::Member])
                 }
+                "Scheduled" | "Recurring" => {
+                    user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender])
+                }
                 "Help" => {
                     user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender, UserRole::Member])
                 }
@@ -118,9 +124,8 @@ async fn markup(user: &User, menu: &IndexMap<String, MenuItem>) -> Result<Inline
     // Put in vec last unpaired button, if any
     let mut last_row = vec![];
     if short.len() % 2 == 1 {
-        let unpaired = short.pop();
-        if unpaired.is_some() {
-            last_row.push(unpaired.unwrap());
+        if let Some(unpaired) = short.pop() {
+            last_row.push(unpaired);
         }
     }
     // Long buttons by one in row
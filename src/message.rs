@@ -1,5 +1,56 @@
-use teloxide::{payloads::{EditMessageTextSetters, SendMessageSetters}, prelude::Requester, types::{InlineKeyboardMarkup, Message, ParseMode, Recipient}, Bot};
-use crate::kernel::error::HandlerResult;
+use serde::{Deserialize, Serialize};
+use teloxide::{payloads::{EditMessageTextSetters, SendMessageSetters}, prelude::Requester, types::{ChatId, InlineKeyboardMarkup, Message, MessageEntity, MessageEntityKind, ParseMode, Recipient}, Bot};
+use crate::{kernel::error::{Error, HandlerResult}, loc::loc, BOT_NAME};
+///
+/// How hard the message draws the attention of the receiver
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Delivered without the sound, like the lunch menu
+    Silent,
+    #[default]
+    Normal,
+    /// Distinct header, pinned in the group chats, like the fire drill
+    Urgent,
+}
+//
+//
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Silent, Priority::Normal, Priority::Urgent];
+    ///
+    /// Parses the priority stored in the callback data
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "silent" => Some(Self::Silent),
+            "normal" => Some(Self::Normal),
+            "urgent" => Some(Self::Urgent),
+            _ => None,
+        }
+    }
+    ///
+    /// Returns the priority as stored in the callback data
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Silent => "silent",
+            Self::Normal => "normal",
+            Self::Urgent => "urgent",
+        }
+    }
+    ///
+    /// Returns the localized title of the priority
+    pub fn title(&self) -> String {
+        match self {
+            Self::Silent => format!("🔕 {}", loc("Silent")),
+            Self::Normal => loc("Normal"),
+            Self::Urgent => format!("🚨 {}", loc("Urgent")),
+        }
+    }
+    ///
+    /// Returns true if the message is delivered without the sound
+    pub fn is_silent(&self) -> bool {
+        *self == Self::Silent
+    }
+}
 ///
 /// Edit current markup message if possible or sending new one
 pub async fn edit_markup_message_or_send(bot: &Bot, msg: &Message, markup: &InlineKeyboardMarkup, text: &str) -> HandlerResult {
@@ -51,11 +102,198 @@ pub async fn edit_text_message_or_send(bot: &Bot, msg: &Message, text: &str) ->
     }
 }
 ///
-/// Sends message with header
-pub async fn send_message_with_header(bot: &Bot, chat_id: impl Into<Recipient>, header: &str, text: &str) -> HandlerResult {
+/// Returns the text with the HTML special characters replaced by the entities
+pub fn escape(text: &str) -> String {
+    text.chars().fold(String::with_capacity(text.len()), |mut escaped, c| {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+        escaped
+    })
+}
+///
+/// Returns the length of the Telegram HTML as Telegram counts it: tags aren't counted,
+/// the escaped char is counted as one, in UTF-16 code units
+pub fn html_len(html: &str) -> usize {
+    let (mut len, mut in_tag, mut in_entity) = (0, false, false);
+    for c in html.chars() {
+        match c {
+            '<' if !in_tag => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if in_tag => {}
+            '&' => {
+                in_entity = true;
+                len += 1;
+            }
+            ';' if in_entity => in_entity = false,
+            _ if in_entity => {}
+            c => len += c.len_utf16(),
+        }
+    }
+    len
+}
+///
+/// Returns the text with the formatting entities of the message as Telegram HTML, the rest of the text is escaped.
+/// Mentions, hashtags, urls and so on are left as the plain text, Telegram recognizes them again.
+/// Tags are balanced even if the entities overlap or exceed the text
+pub fn to_html(text: &str, entities: &[MessageEntity]) -> String {
+    let len = text.encode_utf16().count();
+    let mut entities: Vec<(&MessageEntity, (String, &str))> = entities.iter()
+        .filter(|entity| entity.length > 0 && entity.offset < len)
+        .filter_map(|entity| tags(&entity.kind).map(|tags| (entity, tags)))
+        .collect();
+    entities.sort_by_key(|(entity, _)| (entity.offset, std::cmp::Reverse(entity.length)));
+    let mut entities = entities.into_iter().peekable();
+    // Entities opened, with the end offset, the innermost last
+    let mut opened: Vec<(usize, (String, &str))> = vec![];
+    let mut html = String::with_capacity(text.len());
+    let mut pos = 0;
+    for c in text.chars().map(Some).chain([None]) {
+        if opened.iter().any(|(end, _)| *end <= pos) {
+            let mut reopened = vec![];
+            while opened.iter().any(|(end, _)| *end <= pos) {
+                let Some((end, (open, close))) = opened.pop() else { break };
+                html.push_str(close);
+                if end > pos {
+                    reopened.push((end, (open, close)));
+                }
+            }
+            for (end, (open, close)) in reopened.into_iter().rev() {
+                html.push_str(&open);
+                opened.push((end, (open, close)));
+            }
+        }
+        let Some(c) = c else { break };
+        while let Some((entity, (open, close))) = entities.next_if(|(entity, _)| entity.offset <= pos) {
+            html.push_str(&open);
+            opened.push(((entity.offset + entity.length).min(len), (open, close)));
+        }
+        html.push_str(&escape(c.encode_utf8(&mut [0; 4])));
+        pos += c.len_utf16();
+    }
+    html
+}
+///
+/// Returns opening and closing HTML tags of the formatting entity, None for the entity Telegram recognizes itself
+fn tags(kind: &MessageEntityKind) -> Option<(String, &'static str)> {
+    let tags = match kind {
+        MessageEntityKind::Bold => ("<b>".to_owned(), "</b>"),
+        MessageEntityKind::Italic => ("<i>".to_owned(), "</i>"),
+        MessageEntityKind::Underline => ("<u>".to_owned(), "</u>"),
+        MessageEntityKind::Strikethrough => ("<s>".to_owned(), "</s>"),
+        MessageEntityKind::Spoiler => ("<tg-spoiler>".to_owned(), "</tg-spoiler>"),
+        MessageEntityKind::Blockquote => ("<blockquote>".to_owned(), "</blockquote>"),
+        MessageEntityKind::Code => ("<code>".to_owned(), "</code>"),
+        MessageEntityKind::Pre { language: Some(language) } => (format!("<pre><code class=\"language-{}\">", escape(language)), "</code></pre>"),
+        MessageEntityKind::Pre { language: None } => ("<pre>".to_owned(), "</pre>"),
+        MessageEntityKind::TextLink { url } => (format!("<a href=\"{}\">", escape(url.as_str())), "</a>"),
+        MessageEntityKind::TextMention { user } => (format!("<a href=\"tg://user?id={}\">", user.id), "</a>"),
+        MessageEntityKind::CustomEmoji { custom_emoji_id } => (format!("<tg-emoji emoji-id=\"{}\">", escape(custom_emoji_id)), "</tg-emoji>"),
+        MessageEntityKind::Mention | MessageEntityKind::Hashtag | MessageEntityKind::Cashtag | MessageEntityKind::BotCommand
+            | MessageEntityKind::Url | MessageEntityKind::Email | MessageEntityKind::PhoneNumber => return None,
+    };
+    Some(tags)
+}
+///
+/// Returns HTML text with bold header on the first line, the urgent header is marked.
+/// The `header` is escaped, the `html` is put as is
+pub fn with_header(header: &str, html: &str, priority: Priority) -> String {
+    match priority {
+        Priority::Urgent => format!("🚨 <b>{} | {}:</b>\n{}", escape(&loc("URGENT")), escape(header), html),
+        Priority::Silent | Priority::Normal => format!("<b>{}:</b>\n{}", escape(header), html),
+    }
+}
+///
+/// Sends the plain `text` with header, the silent one is delivered without the sound
+pub async fn send_message_with_header(bot: &Bot, chat_id: impl Into<Recipient>, header: &str, text: &str, priority: Priority) -> HandlerResult {
     bot
-        .send_message(chat_id, format!("<b>{}:</b>\n{}", header, text))
+        .send_message(chat_id, with_header(header, &escape(text), priority))
         .parse_mode(ParseMode::Html)
+        .disable_notification(priority.is_silent())
         .await?;
     Ok(())
 }
+
+///
+/// Replies to the user with the localized text of the typed handler error,
+/// other errors are returned to the dispatcher error handler
+pub async fn reply_on_error(bot: &Bot, chat_id: ChatId, result: HandlerResult) -> HandlerResult {
+    let err = match result {
+        Ok(_) => return Ok(()),
+        Err(err) => Error::from_handler(err)?,
+    };
+    log::warn!("message.reply_on_error | Chat {}, error: {}", chat_id, err);
+    if let Err(reply_err) = send_message_with_header(bot, chat_id, BOT_NAME, &err.user_message(), Priority::Normal).await {
+        log::warn!("message.reply_on_error | Chat {}, error replying: {:?}", chat_id, reply_err);
+    }
+    Ok(())
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use teloxide::types::MessageEntity;
+    use super::{escape, html_len, to_html, with_header, Priority};
+    ///
+    ///
+    fn entity(json: &str) -> MessageEntity {
+        serde_json::from_str(json).unwrap()
+    }
+    #[test]
+    fn header() {
+        assert_eq!(escape(r#"A&B <i>"x"</i>"#), "A&amp;B &lt;i&gt;&quot;x&quot;&lt;/i&gt;");
+        assert_eq!(with_header("A&B <script>", "1 &lt; 2", Priority::Normal), "<b>A&amp;B &lt;script&gt;:</b>\n1 &lt; 2");
+        assert_eq!(with_header("</b><b>", "", Priority::Silent), "<b>&lt;/b&gt;&lt;b&gt;:</b>\n");
+        assert!(with_header("Tom & Jerry", "text", Priority::Urgent).ends_with(" | Tom &amp; Jerry:</b>\ntext"));
+    }
+    #[test]
+    fn html() {
+        assert_eq!(to_html("1 < 2 && 3 > 2 <b>not bold</b>", &[]), "1 &lt; 2 &amp;&amp; 3 &gt; 2 &lt;b&gt;not bold&lt;/b&gt;");
+        assert_eq!(
+            to_html("Meeting at 10 & lunch", &[MessageEntity::bold(0, 7), MessageEntity::italic(14, 7)]),
+            "<b>Meeting</b> at 10 <i>&amp; lunch</i>",
+        );
+        // UTF-16 offsets: the emoji takes two code units
+        assert_eq!(
+            to_html("👋 Hi <all>, 🎉 party", &[MessageEntity::bold(3, 2), MessageEntity::spoiler(16, 5)]),
+            "👋 <b>Hi</b> &lt;all&gt;, 🎉 <tg-spoiler>party</tg-spoiler>",
+        );
+        // Nested and overlapping entities are balanced
+        assert_eq!(
+            to_html("abcdefgh", &[MessageEntity::italic(2, 2), MessageEntity::bold(0, 8)]),
+            "<b>ab<i>cd</i>efgh</b>",
+        );
+        assert_eq!(
+            to_html("abcdefgh", &[MessageEntity::bold(0, 5), MessageEntity::italic(3, 5)]),
+            "<b>abc<i>de</i></b><i>fgh</i>",
+        );
+        // Entities out of the text are cut or skipped
+        assert_eq!(
+            to_html("short", &[MessageEntity::bold(2, 100), MessageEntity::italic(50, 3), MessageEntity::underline(1, 0)]),
+            "sh<b>ort</b>",
+        );
+        // Links with the hostile url and mentions
+        assert_eq!(
+            to_html("docs & @admin", &[
+                entity(r#"{"type":"text_link","url":"https://example.com/?a=1&b=\"><script>","offset":0,"length":4}"#),
+                entity(r#"{"type":"mention","offset":7,"length":6}"#),
+            ]),
+            r#"<a href="https://example.com/?a=1&amp;b=%22%3E%3Cscript%3E">docs</a> &amp; @admin"#,
+        );
+        assert_eq!(
+            to_html("let a = b < c;", &[entity(r#"{"type":"pre","language":"rust\"><b>","offset":0,"length":14}"#)]),
+            r#"<pre><code class="language-rust&quot;&gt;&lt;b&gt;">let a = b &lt; c;</code></pre>"#,
+        );
+    }
+    ///
+    /// Tags aren't counted, the escaped char and the emoji are counted as Telegram does
+    #[test]
+    fn html_length() {
+        assert_eq!(html_len("<b>Lunch</b> &amp; <a href=\"https://a.b/?x=1&amp;y=2\">menu</a>"), 12);
+        assert_eq!(html_len(&escape("<5 & 🚨>")), 8);
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::Utc;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId}, ApiError, RequestError};
+use crate::{
+    db::storage::Db, kernel::error::{Error, HandlerResult}, loc::loc, subscribe::subscription::group_titles,
+    user::user_role::UserRole,
+};
+use super::sent_notice::{update, Acknowledgement, SentNotice};
+///
+/// Max number of the users listed in the acknowledgements view, to fit into the Telegram message
+const MAX_LISTED: usize = 30;
+///
+/// Acknowledgement callbacks, handled in any dialogue state
+#[derive(Debug, Clone, PartialEq)]
+pub enum AckMenu {
+    Ack(u64),       // Recipient acknowledges the notice
+    View(u64),      // Sender opens the acknowledgements
+    Refresh(u64),   // Sender refreshes the acknowledgements
+    Unknown,        // Not an acknowledgement callback
+}
+//
+//
+impl AckMenu {
+    pub fn parse(s: &str) -> Self {
+        let id = |prefix: &str| s.strip_prefix(prefix).and_then(|id| id.parse().ok());
+        match (id("/Ack/"), id("/AckView/"), id("/AckRefresh/")) {
+            (Some(id), _, _) => Self::Ack(id),
+            (_, Some(id), _) => Self::View(id),
+            (_, _, Some(id)) => Self::Refresh(id),
+            _ => Self::Unknown,
+        }
+    }
+}
+///
+/// Acknowledge button attached to every copy of the notice requires acknowledgement
+pub fn markup(id: u64) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("✅ Acknowledge"), format!("/Ack/{}", id)),
+    ]])
+}
+///
+/// Handles the acknowledgement callbacks before the dialogue state,
+/// so the recipient acknowledges the notice whatever menu is opened. Returns false for other callbacks
+pub async fn callback(bot: &Bot, q: &CallbackQuery, input: &str, db: &Db) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match AckMenu::parse(input) {
+        AckMenu::Ack(id) => {
+            acknowledge(bot, q, id, db).await?;
+            Ok(true)
+        }
+        AckMenu::View(id) => {
+            view(bot, q, id, false, db).await?;
+            Ok(true)
+        }
+        AckMenu::Refresh(id) => {
+            view(bot, q, id, true, db).await?;
+            Ok(true)
+        }
+        AckMenu::Unknown => Ok(false),
+    }
+}
+///
+/// Stores the acknowledgement of the user pressed the button and updates the sender's view
+async fn acknowledge(bot: &Bot, q: &CallbackQuery, id: u64, db: &Db) -> HandlerResult {
+    let user_id = q.from.id.to_string();
+    let mut acknowledged = false;
+    let notice = update(db, id, |notice| {
+        if !notice.acks.contains_key(&user_id) {
+            notice.acks.insert(user_id.clone(), Acknowledgement { name: q.from.full_name(), at: Utc::now() });
+            acknowledged = true;
+        }
+    }).await?;
+    let text = match acknowledged {
+        true => loc("Acknowledged, thank you"),
+        false => loc("Already acknowledged"),
+    };
+    bot.answer_callback_query(q.id.clone()).text(text).await?;
+    if acknowledged {
+        log::info!("ack.acknowledge | Notice {} acknowledged by '{}' ({})", id, q.from.full_name(), user_id);
+        if let Some(message_id) = notice.ack_view {
+            if let Err(err) = edit_view(bot, notice.sender, message_id, &notice, db).await {
+                log::warn!("ack.acknowledge | Error updating the view of the notice {}: {}", id, err);
+            }
+        }
+    }
+    Ok(())
+}
+///
+/// Shows the acknowledgements to the sender, Moder's and Admin's as a new message or `refresh`es it in place.
+/// The last view opened by the sender is updated on every next acknowledgement
+async fn view(bot: &Bot, q: &CallbackQuery, id: u64, refresh: bool, db: &Db) -> HandlerResult {
+    let chat_id = ChatId::from(q.from.id);
+    let user = db.user(&chat_id).await?;
+    let notice = db.sent_notices().await?
+        .shift_remove(&id.to_string())
+        .filter(|notice| notice.sender == chat_id || user.has_role(&[UserRole::Admin, UserRole::Moder]))
+        .ok_or_else(|| Error::NotFound(format!("ack.view | Sent notice {}", id)))?;
+    bot.answer_callback_query(q.id.clone()).await?;
+    match q.regular_message().filter(|_| refresh) {
+        Some(msg) => edit_view(bot, chat_id, msg.id, &notice, db).await?,
+        None => {
+            let msg = bot.send_message(chat_id, text(&notice, db).await?)
+                .reply_markup(view_markup(id))
+                .await?;
+            if chat_id == notice.sender {
+                update(db, id, |notice| notice.ack_view = Some(msg.id)).await?;
+            }
+        }
+    }
+    Ok(())
+}
+///
+/// Updates the view message, the view not changed since the last update is skipped
+async fn edit_view(bot: &Bot, chat_id: ChatId, message_id: MessageId, notice: &SentNotice, db: &Db) -> Result<(), Error> {
+    let result = bot.edit_message_text(chat_id, message_id, text(notice, db).await?)
+        .reply_markup(view_markup(notice.id))
+        .await;
+    match result {
+        Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+///
+///
+fn view_markup(id: u64) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Refresh"), format!("/AckRefresh/{}", id)),
+    ]])
+}
+///
+/// Returns the acknowledgements of the notice: users acknowledged
+/// and private chats the notice is delivered to, but not acknowledged yet
+async fn text(notice: &SentNotice, db: &Db) -> Result<String, Error> {
+    let groups = db.subscriptions().await?;
+    let waiting = waiting(notice);
+    let mut text = format!(
+        "{} '{}'\n{}: {}, {}: {}",
+        loc("Acknowledgements of the notice to the group"), group_titles(&groups, &notice.groups),
+        loc("acknowledged"), notice.acks.len(), loc("not acknowledged"), waiting.len(),
+    );
+    let acked = notice.acks.values().map(|ack| format!("✅ {}", ack.name));
+    let waiting = waiting.into_iter().map(|(_, name)| format!("⏳ {}", name));
+    let lines: Vec<String> = acked.chain(waiting).collect();
+    for line in lines.iter().take(MAX_LISTED) {
+        text.push('\n');
+        text.push_str(line);
+    }
+    if lines.len() > MAX_LISTED {
+        text.push_str(&format!("\n... {} {}", lines.len() - MAX_LISTED, loc("more")));
+    }
+    Ok(text)
+}
+///
+/// Returns chat ids and names of the private chats the notice is delivered to, not acknowledged yet
+pub fn waiting(notice: &SentNotice) -> Vec<(String, String)> {
+    notice.report.delivered().into_iter()
+        .filter(|(chat_id, _)| chat_id.parse().is_ok_and(|id| ChatId(id).is_user()))
+        .filter(|(chat_id, _)| !notice.acks.contains_key(chat_id))
+        .map(|(chat_id, _)| {
+            let name = notice.report.recipients[&chat_id].name.clone();
+            (chat_id, name)
+        })
+        .collect()
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use teloxide::types::MessageId;
+    use crate::notice::{report::{DeliveryReport, DeliveryStatus}, sent_notice::{Acknowledgement, SentNotice}};
+    use super::{waiting, AckMenu};
+    #[test]
+    fn parse() {
+        assert_eq!(AckMenu::parse("/Ack/5"), AckMenu::Ack(5));
+        assert_eq!(AckMenu::parse("/AckView/5"), AckMenu::View(5));
+        assert_eq!(AckMenu::parse("/AckRefresh/5"), AckMenu::Refresh(5));
+        assert_eq!(AckMenu::parse("/Ack/x"), AckMenu::Unknown);
+        assert_eq!(AckMenu::parse("/NoticeSend"), AckMenu::Unknown);
+    }
+    ///
+    /// Group chats aren't waited for, members acknowledged from the group chat are counted
+    #[test]
+    fn waiting_recipients() {
+        let mut report = DeliveryReport::default();
+        for (chat_id, name) in [(-4224543713i64, "Office-group"), (11, "Anton"), (12, "Boris"), (13, "Vera")] {
+            report.enqueue(chat_id, name);
+            report.set(&chat_id.to_string(), DeliveryStatus::Delivered(vec![MessageId(1)]));
+        }
+        report.set("13", DeliveryStatus::Failed("Bad Request".to_owned()));
+        let mut notice = SentNotice::test(1, Utc::now(), report, Default::default());
+        assert_eq!(waiting(&notice), vec![("11".to_owned(), "Anton".to_owned()), ("12".to_owned(), "Boris".to_owned())]);
+        notice.acks.insert("11".to_owned(), Acknowledgement { name: "Anton".to_owned(), at: Utc::now() });
+        notice.acks.insert("21".to_owned(), Acknowledgement { name: "Gleb".to_owned(), at: Utc::now() });
+        assert_eq!(waiting(&notice), vec![("12".to_owned(), "Boris".to_owned())]);
+    }
+}
@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+use teloxide::{
+    payloads::{
+        CopyMessageSetters, CopyMessagesSetters, EditMessageCaptionSetters, EditMessageTextSetters, SendAnimationSetters, SendAudioSetters,
+        SendDocumentSetters, SendMediaGroupSetters, SendMessageSetters, SendPhotoSetters, SendStickerSetters, SendVideoNoteSetters,
+        SendVideoSetters, SendVoiceSetters,
+    },
+    prelude::*,
+    types::{
+        InlineKeyboardMarkup, InputFile, InputMedia, InputMediaAudio, InputMediaDocument, InputMediaPhoto, InputMediaVideo, MessageId,
+        ParseMode, Recipient,
+    },
+    ApiError, RequestError,
+};
+use crate::{kernel::error::Error, message::{html_len, to_html, with_header, Priority}};
+use super::delivery::LIMITER;
+///
+/// Max length of the message text, by Telegram
+const TEXT_LEN: usize = 4096;
+///
+/// Max length of the media caption, by Telegram
+const CAPTION_LEN: usize = 1024;
+///
+/// Content of the notice, re-sent to the group and every member with the sender header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NoticeContent {
+    /// Text message, HTML with the formatting of the sender
+    Text(String),
+    /// Photo, document, video, audio, voice or animation, sent by the `file` id with the header prepended to the caption,
+    /// HTML as the text. The notice stored without the `file` is copied from the sender chat
+    Captioned {
+        from_chat: ChatId,
+        message_id: MessageId,
+        caption: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<MediaFile>,
+    },
+    /// Sticker, video note or album, sent by the `files` ids after the header message.
+    /// The notice stored without the `files` is copied from the sender chat
+    Media {
+        from_chat: ChatId,
+        message_ids: Vec<MessageId>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        files: Vec<MediaItem>,
+    },
+}
+///
+/// File of the notice by its Telegram id, sent again without the message of the sender,
+/// so the notice is delivered even if the sender deleted the message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MediaFile {
+    Photo(String),
+    Document(String),
+    Video(String),
+    Audio(String),
+    Voice(String),
+    Animation(String),
+    Sticker(String),
+    VideoNote(String),
+}
+///
+/// File of the album or the sticker, with its caption, HTML as the text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaItem {
+    pub file: MediaFile,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub caption: String,
+}
+//
+//
+impl MediaFile {
+    ///
+    /// Returns the file of the message, the largest size of the photo, or None if the message has no file
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+            return Some(Self::Photo(photo.file.id.clone()));
+        }
+        msg.document().map(|file| Self::Document(file.file.id.clone()))
+            .or_else(|| msg.video().map(|file| Self::Video(file.file.id.clone())))
+            .or_else(|| msg.audio().map(|file| Self::Audio(file.file.id.clone())))
+            .or_else(|| msg.voice().map(|file| Self::Voice(file.file.id.clone())))
+            .or_else(|| msg.animation().map(|file| Self::Animation(file.file.id.clone())))
+            .or_else(|| msg.sticker().map(|file| Self::Sticker(file.file.id.clone())))
+            .or_else(|| msg.video_note().map(|file| Self::VideoNote(file.file.id.clone())))
+    }
+    ///
+    /// Returns the album item of the file with the HTML `caption`, or None if the file can't be sent in the album
+    fn input_media(&self, caption: &str) -> Option<InputMedia> {
+        let caption = (!caption.is_empty()).then(|| caption.to_owned());
+        match self {
+            Self::Photo(id) => Some(InputMedia::Photo(InputMediaPhoto { caption, ..InputMediaPhoto::new(InputFile::file_id(id)).parse_mode(ParseMode::Html) })),
+            Self::Video(id) => Some(InputMedia::Video(InputMediaVideo { caption, ..InputMediaVideo::new(InputFile::file_id(id)).parse_mode(ParseMode::Html) })),
+            Self::Document(id) => Some(InputMedia::Document(InputMediaDocument { caption, ..InputMediaDocument::new(InputFile::file_id(id)).parse_mode(ParseMode::Html) })),
+            Self::Audio(id) => Some(InputMedia::Audio(InputMediaAudio { caption, ..InputMediaAudio::new(InputFile::file_id(id)).parse_mode(ParseMode::Html) })),
+            Self::Voice(_) | Self::Animation(_) | Self::Sticker(_) | Self::VideoNote(_) => None,
+        }
+    }
+    ///
+    /// Sends the file with the HTML `caption`, the sticker and the video note are sent without the caption
+    async fn send(&self, bot: &Bot, chat_id: Recipient, caption: &str, markup: Option<&InlineKeyboardMarkup>, priority: Priority) -> Result<Message, RequestError> {
+        macro_rules! captioned {
+            ($request:expr) => {{
+                let request = $request.caption(caption).parse_mode(ParseMode::Html).disable_notification(priority.is_silent());
+                match markup {
+                    Some(markup) => request.reply_markup(markup.clone()).send().await,
+                    None => request.send().await,
+                }
+            }};
+        }
+        match self {
+            Self::Photo(id) => captioned!(bot.send_photo(chat_id, InputFile::file_id(id))),
+            Self::Document(id) => captioned!(bot.send_document(chat_id, InputFile::file_id(id))),
+            Self::Video(id) => captioned!(bot.send_video(chat_id, InputFile::file_id(id))),
+            Self::Audio(id) => captioned!(bot.send_audio(chat_id, InputFile::file_id(id))),
+            Self::Voice(id) => captioned!(bot.send_voice(chat_id, InputFile::file_id(id))),
+            Self::Animation(id) => captioned!(bot.send_animation(chat_id, InputFile::file_id(id))),
+            Self::Sticker(id) => bot.send_sticker(chat_id, InputFile::file_id(id)).disable_notification(priority.is_silent()).send().await,
+            Self::VideoNote(id) => bot.send_video_note(chat_id, InputFile::file_id(id)).disable_notification(priority.is_silent()).send().await,
+        }
+    }
+}
+//
+//
+impl MediaItem {
+    ///
+    /// Returns the file of the message with its caption, or None if the message has no file
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        MediaFile::from_message(msg).map(|file| Self {
+            file,
+            caption: to_html(msg.caption().unwrap_or_default(), msg.caption_entities().unwrap_or_default()),
+        })
+    }
+}
+//
+//
+impl NoticeContent {
+    ///
+    /// Returns content of the message with its formatting, or None if the message kind can't be noticed
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        if let Some(text) = msg.text() {
+            return Some(Self::Text(to_html(text, msg.entities().unwrap_or_default())));
+        }
+        if msg.photo().is_some() || msg.document().is_some() || msg.video().is_some()
+            || msg.audio().is_some() || msg.voice().is_some() || msg.animation().is_some() {
+            return Some(Self::Captioned {
+                from_chat: msg.chat.id,
+                message_id: msg.id,
+                caption: to_html(msg.caption().unwrap_or_default(), msg.caption_entities().unwrap_or_default()),
+                file: MediaFile::from_message(msg),
+            });
+        }
+        if msg.sticker().is_some() || msg.video_note().is_some() {
+            return Some(Self::Media { from_chat: msg.chat.id, message_ids: vec![msg.id], files: MediaItem::from_message(msg).into_iter().collect() });
+        }
+        None
+    }
+    ///
+    /// Returns content of the album, `items` - messages of the media group in the sender chat with their files,
+    /// the album is copied from the sender chat if some file is missing
+    pub fn album(from_chat: ChatId, mut items: Vec<(MessageId, Option<MediaItem>)>) -> Self {
+        items.sort_by_key(|(id, _)| id.0);
+        let (message_ids, files): (Vec<MessageId>, Vec<Option<MediaItem>>) = items.into_iter().unzip();
+        let files = files.into_iter().collect::<Option<Vec<MediaItem>>>().unwrap_or_default();
+        Self::Media { from_chat, message_ids, files }
+    }
+    ///
+    /// Returns the length of the text or caption sent with the `header` and the limit of Telegram, if the limit is exceeded.
+    /// The urgent header is the longest, so it's used, the priority can be changed after the preview
+    pub fn too_long(&self, header: &str) -> Option<(usize, usize)> {
+        let (html, max) = match self {
+            NoticeContent::Text(text) => (text, TEXT_LEN),
+            NoticeContent::Captioned { caption, .. } => (caption, CAPTION_LEN),
+            // The header is the separate message, the captions of the album items are limited alone
+            NoticeContent::Media { files, .. } => {
+                let len = files.iter().map(|item| html_len(&item.caption)).max().unwrap_or(0);
+                return (len > CAPTION_LEN).then_some((len, CAPTION_LEN));
+            }
+        };
+        let len = html_len(&with_header(header, html, Priority::Urgent));
+        (len > max).then_some((len, max))
+    }
+    ///
+    /// Sends the content with the header to the `chat_id` through the rate-limited delivery layer,
+    /// returns ids of the sent messages. The `markup` is attached to the message with the header,
+    /// the `priority` sets the header style and the sound. The header of the media is deleted if the media isn't sent
+    pub async fn send(&self, bot: &Bot, chat_id: impl Into<Recipient>, header: &str, markup: Option<&InlineKeyboardMarkup>, priority: Priority) -> Result<Vec<MessageId>, Error> {
+        let chat_id = chat_id.into();
+        match self {
+            NoticeContent::Text(text) => {
+                let text = with_header(header, text, priority);
+                let msg = LIMITER.deliver(&chat_id, || {
+                    let request = bot.send_message(chat_id.clone(), text.clone())
+                        .parse_mode(ParseMode::Html)
+                        .disable_notification(priority.is_silent());
+                    match markup {
+                        Some(markup) => request.reply_markup(markup.clone()).send(),
+                        None => request.send(),
+                    }
+                }).await?;
+                Ok(vec![msg.id])
+            }
+            NoticeContent::Captioned { caption, file: Some(file), .. } => {
+                let caption = with_header(header, caption, priority);
+                let msg = LIMITER.deliver(&chat_id, || file.send(bot, chat_id.clone(), &caption, markup, priority)).await?;
+                Ok(vec![msg.id])
+            }
+            NoticeContent::Captioned { from_chat, message_id, caption, file: None } => {
+                let caption = with_header(header, caption, priority);
+                let message_id = LIMITER.deliver(&chat_id, || {
+                    let request = bot.copy_message(chat_id.clone(), *from_chat, *message_id)
+                        .caption(caption.clone())
+                        .parse_mode(ParseMode::Html)
+                        .disable_notification(priority.is_silent());
+                    match markup {
+                        Some(markup) => request.reply_markup(markup.clone()).send(),
+                        None => request.send(),
+                    }
+                }).await?;
+                Ok(vec![message_id])
+            }
+            NoticeContent::Media { from_chat, message_ids, files } => {
+                let header = with_header(header, "", priority);
+                let msg = LIMITER.deliver(&chat_id, || {
+                    let request = bot.send_message(chat_id.clone(), header.clone())
+                        .parse_mode(ParseMode::Html)
+                        .disable_notification(priority.is_silent());
+                    match markup {
+                        Some(markup) => request.reply_markup(markup.clone()).send(),
+                        None => request.send(),
+                    }
+                }).await?;
+                let media: Option<Vec<InputMedia>> = files.iter().map(|item| item.file.input_media(&item.caption)).collect();
+                let copied = match (files.as_slice(), media) {
+                    ([], _) => LIMITER.deliver(&chat_id, || {
+                        bot.copy_messages(chat_id.clone(), *from_chat, message_ids.clone())
+                            .disable_notification(priority.is_silent())
+                            .send()
+                    }).await,
+                    (_, Some(media)) => LIMITER.deliver(&chat_id, || {
+                        bot.send_media_group(chat_id.clone(), media.clone())
+                            .disable_notification(priority.is_silent())
+                            .send()
+                    }).await.map(|sent| sent.iter().map(|msg| msg.id).collect()),
+                    // Sticker or video note, can't be sent in the album
+                    (files, None) => async {
+                        let mut sent = vec![];
+                        for item in files {
+                            sent.push(LIMITER.deliver(&chat_id, || item.file.send(bot, chat_id.clone(), &item.caption, None, priority)).await?.id);
+                        }
+                        Ok::<_, Error>(sent)
+                    }.await,
+                };
+                match copied {
+                    Ok(copied) => Ok([msg.id].into_iter().chain(copied).collect()),
+                    Err(err) => {
+                        // The header is deleted, the recipient of the failed media keeps no trace of it
+                        if let Err(del_err) = Self::delete(bot, chat_id.clone(), &[msg.id]).await {
+                            log::warn!("NoticeContent.send | Chat {:?}, header {} of the failed media isn't deleted: {}", chat_id, msg.id, del_err);
+                        }
+                        Err(err)
+                    }
+                }
+            }
+        }
+    }
+    ///
+    /// Returns the content with the text or caption replaced by `text`,
+    /// or None if the content has no text to be corrected
+    pub fn corrected(&self, text: &str) -> Option<Self> {
+        match self {
+            NoticeContent::Text(_) => Some(NoticeContent::Text(text.to_owned())),
+            NoticeContent::Captioned { from_chat, message_id, file, .. } => Some(NoticeContent::Captioned {
+                from_chat: *from_chat,
+                message_id: *message_id,
+                caption: text.to_owned(),
+                file: file.clone(),
+            }),
+            NoticeContent::Media { .. } => None,
+        }
+    }
+    ///
+    /// Replaces the text or caption of the copy already sent to the `chat_id` with the current one,
+    /// `message_ids` - messages returned by `send`, `markup` - the one sent with the copy, to be kept.
+    /// The copy with the same text is treated as edited
+    pub async fn edit(&self, bot: &Bot, chat_id: impl Into<Recipient>, message_ids: &[MessageId], header: &str, markup: Option<&InlineKeyboardMarkup>, priority: Priority) -> Result<(), Error> {
+        let chat_id = chat_id.into();
+        let Some(&message_id) = message_ids.first() else {
+            return Ok(());
+        };
+        let result = match self {
+            NoticeContent::Text(text) => {
+                let text = with_header(header, text, priority);
+                LIMITER.deliver(&chat_id, || {
+                    let request = bot.edit_message_text(chat_id.clone(), message_id, text.clone())
+                        .parse_mode(ParseMode::Html);
+                    match markup {
+                        Some(markup) => request.reply_markup(markup.clone()).send(),
+                        None => request.send(),
+                    }
+                }).await.map(|_| ())
+            }
+            NoticeContent::Captioned { caption, .. } => {
+                let caption = with_header(header, caption, priority);
+                LIMITER.deliver(&chat_id, || {
+                    let request = bot.edit_message_caption(chat_id.clone(), message_id)
+                        .caption(caption.clone())
+                        .parse_mode(ParseMode::Html);
+                    match markup {
+                        Some(markup) => request.reply_markup(markup.clone()).send(),
+                        None => request.send(),
+                    }
+                }).await.map(|_| ())
+            }
+            NoticeContent::Media { .. } => return Ok(()),
+        };
+        match result {
+            Err(Error::Telegram(RequestError::Api(ApiError::MessageNotModified))) => Ok(()),
+            result => result,
+        }
+    }
+    ///
+    /// Deletes the copy already sent to the `chat_id`, `message_ids` - messages returned by `send`.
+    /// Messages already deleted by the recipient are skipped by Telegram
+    pub async fn delete(bot: &Bot, chat_id: impl Into<Recipient>, message_ids: &[MessageId]) -> Result<(), Error> {
+        let chat_id = chat_id.into();
+        if message_ids.is_empty() {
+            return Ok(());
+        }
+        LIMITER.deliver(&chat_id, || {
+            bot.delete_messages(chat_id.clone(), message_ids.to_vec()).send()
+        }).await?;
+        Ok(())
+    }
+    ///
+    /// Replaces the text or caption of the copy already sent to the `chat_id` with the `text`,
+    /// buttons of the copy are removed. Used when the copy can't be deleted
+    pub async fn replace(&self, bot: &Bot, chat_id: impl Into<Recipient>, message_ids: &[MessageId], text: &str) -> Result<(), Error> {
+        let chat_id = chat_id.into();
+        let Some(&message_id) = message_ids.first() else {
+            return Ok(());
+        };
+        let result = match self {
+            NoticeContent::Captioned { .. } => LIMITER.deliver(&chat_id, || {
+                bot.edit_message_caption(chat_id.clone(), message_id).caption(text.to_owned()).send()
+            }).await.map(|_| ()),
+            NoticeContent::Text(_) | NoticeContent::Media { .. } => LIMITER.deliver(&chat_id, || {
+                bot.edit_message_text(chat_id.clone(), message_id, text.to_owned()).send()
+            }).await.map(|_| ()),
+        };
+        match result {
+            Err(Error::Telegram(RequestError::Api(ApiError::MessageNotModified))) => Ok(()),
+            result => result,
+        }
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use teloxide::types::{ChatId, MessageId};
+    use super::{MediaFile, MediaItem, NoticeContent};
+    #[test]
+    fn corrected() {
+        assert_eq!(NoticeContent::Text("Meeting at 10:00".to_owned()).corrected("Meeting at 11:00"), Some(NoticeContent::Text("Meeting at 11:00".to_owned())));
+        let file = Some(MediaFile::Photo("AgAD".to_owned()));
+        let captioned = NoticeContent::Captioned { from_chat: ChatId(11), message_id: MessageId(5), caption: "Plan".to_owned(), file: file.clone() };
+        assert_eq!(
+            captioned.corrected("Fixed plan"),
+            Some(NoticeContent::Captioned { from_chat: ChatId(11), message_id: MessageId(5), caption: "Fixed plan".to_owned(), file }),
+        );
+        assert_eq!(NoticeContent::album(ChatId(11), vec![(MessageId(7), None), (MessageId(6), None)]).corrected("text"), None);
+    }
+    ///
+    /// Album is sent by the file ids in the order of the messages, copied from the sender chat if some file is missing
+    #[test]
+    fn album() {
+        let item = |id: &str| Some(MediaItem { file: MediaFile::Photo(id.to_owned()), caption: String::new() });
+        assert_eq!(
+            NoticeContent::album(ChatId(11), vec![(MessageId(7), item("B")), (MessageId(6), item("A"))]),
+            NoticeContent::Media { from_chat: ChatId(11), message_ids: vec![MessageId(6), MessageId(7)], files: vec![item("A").unwrap(), item("B").unwrap()] },
+        );
+        assert_eq!(
+            NoticeContent::album(ChatId(11), vec![(MessageId(7), item("B")), (MessageId(6), None)]),
+            NoticeContent::Media { from_chat: ChatId(11), message_ids: vec![MessageId(6), MessageId(7)], files: vec![] },
+        );
+        let stored = r#"{"Captioned":{"from_chat":11,"message_id":{"message_id":5},"caption":"Plan"}}"#;
+        assert!(matches!(serde_json::from_str(stored).unwrap(), NoticeContent::Captioned { file: None, .. }));
+    }
+    ///
+    /// Text and caption are limited with the header, the tags aren't counted
+    #[test]
+    fn too_long() {
+        let text = |len: usize| NoticeContent::Text(format!("<b>{}</b>", "a".repeat(len)));
+        let header = "Anton";
+        // "🚨 URGENT | Anton:\n"
+        let header_len = 3 + 6 + 3 + header.len() + 2;
+        assert_eq!(text(4096 - header_len).too_long(header), None);
+        assert_eq!(text(4097 - header_len).too_long(header), Some((4097, 4096)));
+        let caption = NoticeContent::Captioned { from_chat: ChatId(11), message_id: MessageId(5), caption: "&amp;".repeat(1100), file: None };
+        assert_eq!(caption.too_long(header), Some((1100 + header_len, 1024)));
+    }
+}
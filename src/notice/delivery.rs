@@ -0,0 +1,235 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+use lazy_static::lazy_static;
+use teloxide::{types::Recipient, RequestError};
+use tokio::{sync::Mutex, time::{sleep, sleep_until, Instant}};
+use crate::kernel::error::Error;
+///
+/// Min interval between any two requests, Telegram allows about 30 messages per second
+const GLOBAL_INTERVAL: Duration = Duration::from_millis(35);
+///
+/// Min interval between messages to the same private chat
+const CHAT_INTERVAL: Duration = Duration::from_secs(1);
+///
+/// Min interval between messages to the same group, Telegram allows 20 messages per minute
+const GROUP_INTERVAL: Duration = Duration::from_secs(3);
+///
+/// Max number of attempts to send the request failed with RetryAfter or network error
+const MAX_ATTEMPTS: u32 = 6;
+///
+/// Delay before the first retry of the network error, doubled on every next retry
+const BACKOFF: Duration = Duration::from_millis(500);
+///
+/// Slots of the chats are dropped when there are more of them
+const MAX_CHATS: usize = 1000;
+lazy_static! {
+    ///
+    /// Limiter shared by all the requests sending notices
+    pub static ref LIMITER: Limiter = Limiter::new(GLOBAL_INTERVAL, CHAT_INTERVAL, GROUP_INTERVAL, BACKOFF);
+}
+///
+/// Rate-limited delivery layer: spaces requests globally and per chat,
+/// retries requests failed with RetryAfter or network errors
+pub struct Limiter {
+    global: Duration,
+    chat: Duration,
+    group: Duration,
+    backoff: Duration,
+    slots: Mutex<Slots>,
+}
+///
+/// Times the next request is allowed at
+struct Slots {
+    global: Instant,
+    chats: HashMap<Recipient, Instant>,
+}
+//
+//
+impl Limiter {
+    ///
+    /// Returns Limiter with min intervals between any requests, requests to the same private chat and to the same group,
+    /// `backoff` - delay before the first retry of the network error
+    pub fn new(global: Duration, chat: Duration, group: Duration, backoff: Duration) -> Self {
+        Self { global, chat, group, backoff, slots: Mutex::new(Slots { global: Instant::now(), chats: HashMap::new() }) }
+    }
+    ///
+    /// Sends the request made by `request` to the `chat` when the limits allow,
+    /// retries it after RetryAfter and with backoff after network errors
+    pub async fn deliver<T, F, Fut>(&self, chat: &Recipient, mut request: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RequestError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            self.wait(chat).await;
+            match request().await {
+                Ok(value) => return Ok(value),
+                Err(RequestError::RetryAfter(secs)) if attempt < MAX_ATTEMPTS => {
+                    log::warn!("Limiter.deliver | Chat {}, attempt {}: retry after {:?}", recipient(chat), attempt, secs.duration());
+                    self.delay(chat, secs.duration()).await;
+                }
+                Err(err @ (RequestError::Network(_) | RequestError::Io(_))) if attempt < MAX_ATTEMPTS => {
+                    let backoff = self.backoff * 2u32.pow(attempt - 1);
+                    log::warn!("Limiter.deliver | Chat {}, attempt {}: {}, retry in {:?}", recipient(chat), attempt, err, backoff);
+                    sleep(backoff).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
+            attempt += 1;
+        }
+    }
+    ///
+    /// Waits for the slot of the chat and reserves the next one
+    async fn wait(&self, chat: &Recipient) {
+        let at = {
+            let mut slots = self.slots.lock().await;
+            let now = Instant::now();
+            if slots.chats.len() > MAX_CHATS {
+                slots.chats.retain(|_, next| *next > now);
+            }
+            let chat_next = slots.chats.get(chat).copied().unwrap_or(now);
+            let at = now.max(slots.global).max(chat_next);
+            slots.global = at + self.global;
+            let interval = match chat {
+                Recipient::Id(id) if id.is_user() => self.chat,
+                _ => self.group,
+            };
+            slots.chats.insert(chat.clone(), at + interval);
+            at
+        };
+        sleep_until(at).await;
+    }
+    ///
+    /// Postpones the next request to the chat for the `delay` asked by Telegram
+    async fn delay(&self, chat: &Recipient, delay: Duration) {
+        let next = Instant::now() + delay;
+        let mut slots = self.slots.lock().await;
+        let slot = slots.chats.entry(chat.clone()).or_insert(next);
+        *slot = (*slot).max(next);
+    }
+}
+///
+///
+fn recipient(chat: &Recipient) -> String {
+    match chat {
+        Recipient::Id(id) => id.to_string(),
+        Recipient::ChannelUsername(name) => name.clone(),
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+    use futures::future::join_all;
+    use teloxide::{prelude::*, types::Recipient};
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}, sync::Mutex};
+    use super::Limiter;
+    ///
+    /// Requests received by the mock Telegram API: attempts by chat id and successfully sent messages
+    #[derive(Default)]
+    struct MockApi {
+        attempts: HashMap<i64, u32>,
+        sent: Vec<i64>,
+    }
+    ///
+    /// Serves the Bot API requests, the first attempt to every chat except the `sender` fails with 429,
+    /// the first attempt to the `broken` chat drops the connection
+    async fn serve(listener: TcpListener, api: Arc<Mutex<MockApi>>, sender: i64, broken: i64) {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(connection(stream, api.clone(), sender, broken));
+        }
+    }
+    ///
+    ///
+    async fn connection(mut stream: TcpStream, api: Arc<Mutex<MockApi>>, sender: i64, broken: i64) {
+        let mut buf = vec![];
+        loop {
+            let head_end = loop {
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+                let mut chunk = [0; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+            };
+            let head = String::from_utf8_lossy(&buf[..head_end]).to_lowercase();
+            let len: usize = head.lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |len| len.trim().parse().unwrap());
+            while buf.len() < head_end + len {
+                let mut chunk = [0; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+            }
+            let body: serde_json::Value = serde_json::from_slice(&buf[head_end..head_end + len]).unwrap();
+            buf.drain(..head_end + len);
+            let chat_id = match &body["chat_id"] {
+                serde_json::Value::String(chat_id) => chat_id.parse().unwrap(),
+                chat_id => chat_id.as_i64().unwrap(),
+            };
+            let (status, response) = {
+                let mut api = api.lock().await;
+                let attempt = api.attempts.entry(chat_id).or_default();
+                *attempt += 1;
+                match (chat_id, *attempt) {
+                    (chat_id, 1) if chat_id == broken => return,
+                    (chat_id, 1) if chat_id != sender => (
+                        "429 Too Many Requests",
+                        r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 0","parameters":{"retry_after":0}}"#.to_owned(),
+                    ),
+                    (chat_id, attempt) => {
+                        api.sent.push(chat_id);
+                        ("200 OK", format!(
+                            r#"{{"ok":true,"result":{{"message_id":{},"date":1700000000,"chat":{{"id":{},"type":"private","first_name":"Test"}},"text":"notice"}}}}"#,
+                            attempt, chat_id,
+                        ))
+                    }
+                }
+            };
+            let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", status, response.len(), response);
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+    ///
+    /// Every chat gets exactly one message despite 429 and network errors,
+    /// the requests to the chats are sent in parallel through the single limiter
+    #[tokio::test]
+    async fn deliver_retries_until_delivered() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = reqwest::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let api = Arc::new(Mutex::new(MockApi::default()));
+        let (sender, group, members) = (1, -100, [11, 12, 13, 14, 15]);
+        tokio::spawn(serve(listener, api.clone(), sender, 12));
+        let bot = Bot::new("TOKEN").set_api_url(url);
+        let ms = Duration::from_millis;
+        let limiter = Limiter::new(ms(1), ms(10), ms(20), ms(10));
+        let mut chats = vec![sender, group];
+        chats.extend(members);
+        let results = join_all(chats.iter().map(|&chat_id| {
+            let (bot, limiter) = (&bot, &limiter);
+            async move {
+                let chat = Recipient::Id(ChatId(chat_id));
+                limiter.deliver(&chat, || bot.send_message(chat.clone(), "notice").send()).await
+            }
+        })).await;
+        for (chat_id, result) in chats.iter().zip(results) {
+            assert!(result.is_ok(), "chat {}: {:?}", chat_id, result.err());
+        }
+        let api = api.lock().await;
+        let mut sent = api.sent.clone();
+        sent.sort();
+        chats.sort();
+        assert_eq!(sent, chats);
+        assert_eq!(api.attempts[&sender], 1);
+        assert_eq!(api.attempts[&group], 2);
+        assert_eq!(api.attempts[&12], 2);
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use indexmap::IndexSet;
+use teloxide::{payloads::SendMessageSetters, prelude::*, requests::Request, types::ReplyParameters};
+use crate::{db::storage::Db, kernel::error::Error, loc::loc, subscribe::subscription::group_titles, user::user_role::UserRole};
+use super::{ack, delivery::LIMITER, outbox::recipient, report::DeliveryStatus, sent_notice::{update, SentNotice}};
+///
+/// Interval of checking the notices waiting for the acknowledgement
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+///
+/// Max number of the non-responders listed to the Moders, to fit into the Telegram message
+const MAX_LISTED: usize = 50;
+///
+/// What is due for the notice waiting for the acknowledgement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// Nothing to do now
+    Wait,
+    /// Remind the recipients not acknowledged the notice
+    Remind,
+    /// Send the recipients not acknowledged the notice to the Moders
+    Escalate,
+}
+///
+/// Returns what is due for the notice at `now` by its escalation policy
+pub fn step(notice: &SentNotice, now: DateTime<Utc>) -> Step {
+    let escalation = match notice.options.escalation {
+        Some(escalation) if notice.options.requires_ack => escalation,
+        _ => return Step::Wait,
+    };
+    if notice.escalated_at.is_some() || notice.recalled_at.is_some() || notice.expired_at.is_some() || !notice.report.is_complete() {
+        return Step::Wait;
+    }
+    if now < notice.reminded_at.unwrap_or(notice.sent_at) + escalation.interval() {
+        return Step::Wait;
+    }
+    if ack::waiting(notice).is_empty() {
+        return Step::Wait;
+    }
+    match notice.reminders < escalation.times {
+        true => Step::Remind,
+        false => Step::Escalate,
+    }
+}
+///
+/// Background task reminding the recipients not acknowledged the notices
+/// and reporting them to the Moders, started from `main` beside the dispatcher
+pub async fn run(bot: Bot, db: Db) {
+    log::info!("escalation.run | Started, check interval: {:?}", CHECK_INTERVAL);
+    loop {
+        if let Err(err) = check(&bot, &db, Utc::now()).await {
+            log::warn!("escalation.run | Error: {}", err);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+///
+///
+async fn check(bot: &Bot, db: &Db, now: DateTime<Utc>) -> Result<(), Error> {
+    for notice in db.sent_notices().await?.into_values() {
+        let result = match step(&notice, now) {
+            Step::Wait => Ok(()),
+            Step::Remind => remind(bot, db, &notice, now).await,
+            Step::Escalate => escalate(bot, db, &notice, now).await,
+        };
+        if let Err(err) = result {
+            log::warn!("escalation.check | Notice {} error: {}", notice.id, err);
+        }
+    }
+    Ok(())
+}
+///
+/// Sends the reminder with the Acknowledge button to every recipient not acknowledged the notice,
+/// as a reply to the delivered copy
+async fn remind(bot: &Bot, db: &Db, notice: &SentNotice, now: DateTime<Utc>) -> Result<(), Error> {
+    let waiting = ack::waiting(notice);
+    log::info!("escalation.remind | Notice {}, reminder {} to {} recipients", notice.id, notice.reminders + 1, waiting.len());
+    let text = loc("⏰ Reminder: please read the notice above and acknowledge it");
+    let markup = ack::markup(notice.id);
+    for (chat_id, name) in &waiting {
+        let chat = recipient(chat_id);
+        let reply_to = match &notice.report.recipients[chat_id].status {
+            DeliveryStatus::Delivered(message_ids) => message_ids.first().copied(),
+            _ => None,
+        };
+        let result = LIMITER.deliver(&chat, || {
+            let request = bot.send_message(chat.clone(), text.clone()).reply_markup(markup.clone());
+            match reply_to {
+                Some(message_id) => request.reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply()).send(),
+                None => request.send(),
+            }
+        }).await;
+        if let Err(err) = result {
+            log::warn!("escalation.remind | Error reminding notice {} to '{}' ({}): {}", notice.id, name, chat_id, err);
+        }
+    }
+    update(db, notice.id, |notice| {
+        notice.reminders += 1;
+        notice.reminded_at = Some(now);
+    }).await?;
+    Ok(())
+}
+///
+/// Sends the list of the recipients not acknowledged the notice to the Moders among the members of the notice groups,
+/// to all Moders if the groups have none, and to the sender
+async fn escalate(bot: &Bot, db: &Db, notice: &SentNotice, now: DateTime<Utc>) -> Result<(), Error> {
+    let groups = db.subscriptions().await?;
+    let users = db.users().await?;
+    let is_moder = |chat_id: &String| users.get(chat_id).is_some_and(|user| user.has_role(&[UserRole::Moder]));
+    let mut moders: IndexSet<String> = notice.groups.iter()
+        .filter_map(|group| groups.get(group))
+        .flat_map(|group| group.members.keys())
+        .filter(|chat_id| is_moder(chat_id))
+        .cloned()
+        .collect();
+    if moders.is_empty() {
+        moders = users.keys().filter(|chat_id| is_moder(chat_id)).cloned().collect();
+    }
+    moders.insert(notice.sender.to_string());
+    let waiting = ack::waiting(notice);
+    let sender = users.get(&notice.sender.to_string()).map_or(notice.sender.to_string(), |user| user.name.clone());
+    let mut text = format!(
+        "{} '{}' ({}), {}: {}, {}: {}",
+        loc("Notice to the group is not acknowledged"), group_titles(&groups, &notice.groups), sender,
+        loc("reminders"), notice.reminders, loc("not acknowledged"), waiting.len(),
+    );
+    for (chat_id, name) in waiting.iter().take(MAX_LISTED) {
+        text.push_str(&format!("\n- {} ({})", name, chat_id));
+    }
+    if waiting.len() > MAX_LISTED {
+        text.push_str(&format!("\n... {} {}", waiting.len() - MAX_LISTED, loc("more")));
+    }
+    log::info!("escalation.escalate | Notice {}, {} not acknowledged, reported to {:?}", notice.id, waiting.len(), moders);
+    for moder in &moders {
+        let chat = recipient(moder);
+        if let Err(err) = LIMITER.deliver(&chat, || bot.send_message(chat.clone(), text.clone()).send()).await {
+            log::warn!("escalation.escalate | Error reporting notice {} to the {}: {}", notice.id, moder, err);
+        }
+    }
+    update(db, notice.id, |notice| notice.escalated_at = Some(now)).await?;
+    Ok(())
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use teloxide::types::MessageId;
+    use crate::notice::{
+        options::{Escalation, NoticeOptions}, report::{DeliveryReport, DeliveryStatus},
+        sent_notice::{Acknowledgement, SentNotice},
+    };
+    use super::{step, Step};
+    ///
+    /// 'every 2h, 2 times': reminders at +2h and +4h, escalation at +6h, nothing after
+    #[test]
+    fn steps() {
+        let sent_at = Utc.with_ymd_and_hms(2024, 5, 20, 8, 0, 0).unwrap();
+        let mut report = DeliveryReport::default();
+        for chat_id in [11, 12] {
+            report.enqueue(chat_id, format!("Member {}", chat_id));
+            report.set(&chat_id.to_string(), DeliveryStatus::Delivered(vec![MessageId(1)]));
+        }
+        let mut notice = SentNotice::test(1, sent_at, report, NoticeOptions { requires_ack: true, escalation: Some(Escalation { every: 120, times: 2 }), ..Default::default() });
+        let at = |hours: i64| sent_at + Duration::hours(hours);
+        assert_eq!(step(&notice, at(1)), Step::Wait);
+        assert_eq!(step(&notice, at(2)), Step::Remind);
+        notice.reminders = 1;
+        notice.reminded_at = Some(at(2));
+        assert_eq!(step(&notice, at(3)), Step::Wait);
+        assert_eq!(step(&notice, at(4)), Step::Remind);
+        notice.reminders = 2;
+        notice.reminded_at = Some(at(4));
+        assert_eq!(step(&notice, at(5)), Step::Wait);
+        assert_eq!(step(&notice, at(6)), Step::Escalate);
+        notice.escalated_at = Some(at(6));
+        assert_eq!(step(&notice, at(8)), Step::Wait);
+        notice.escalated_at = None;
+        for chat_id in ["11", "12"] {
+            notice.acks.insert(chat_id.to_owned(), Acknowledgement { name: chat_id.to_owned(), at: at(5) });
+        }
+        assert_eq!(step(&notice, at(6)), Step::Wait);
+        notice.acks.clear();
+        notice.options.requires_ack = false;
+        assert_eq!(step(&notice, at(6)), Step::Wait);
+    }
+}
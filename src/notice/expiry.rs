@@ -0,0 +1,114 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use teloxide::prelude::*;
+use crate::{db::storage::Db, kernel::error::Error, loc::loc};
+use super::{
+    content::NoticeContent, outbox::recipient,
+    sent_notice::{update, Expired, SentNotice},
+};
+///
+/// Interval of checking the notices out of date
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+///
+/// Returns true if the copies of the notice are to be cleaned up at `now` by its time-to-live
+pub fn is_due(notice: &SentNotice, now: DateTime<Utc>) -> bool {
+    match notice.options.ttl {
+        Some(ttl) => notice.expired_at.is_none() && notice.recalled_at.is_none() && notice.report.is_complete()
+            && notice.sent_at + ttl.duration() <= now,
+        None => false,
+    }
+}
+///
+/// Background task cleaning up the notices out of date, started from `main` beside the dispatcher
+pub async fn run(bot: Bot, db: Db) {
+    log::info!("expiry.run | Started, check interval: {:?}", CHECK_INTERVAL);
+    loop {
+        if let Err(err) = check(&bot, &db, Utc::now()).await {
+            log::warn!("expiry.run | Error: {}", err);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+///
+///
+async fn check(bot: &Bot, db: &Db, now: DateTime<Utc>) -> Result<(), Error> {
+    for notice in db.sent_notices().await?.into_values().filter(|notice| is_due(notice, now)) {
+        if let Err(err) = expire(bot, db, &notice, now).await {
+            log::warn!("expiry.check | Notice {} isn't expired: {}", notice.id, err);
+        }
+    }
+    Ok(())
+}
+///
+/// Deletes every delivered copy of the notice, the copy can't be deleted is replaced with '(expired)'.
+/// The result of every copy is stored to the notice
+async fn expire(bot: &Bot, db: &Db, notice: &SentNotice, now: DateTime<Utc>) -> Result<(), Error> {
+    let text = format!("({})", loc("expired"));
+    let mut expired = IndexMap::new();
+    for (chat_id, message_ids) in notice.report.delivered() {
+        let chat = recipient(&chat_id);
+        let result = match NoticeContent::delete(bot, chat.clone(), &message_ids).await {
+            Ok(_) => Expired::Deleted,
+            Err(err) => {
+                log::debug!("expiry.expire | Notice {} can't be deleted in the {}: {}", notice.id, chat_id, err);
+                match notice.content.replace(bot, chat, &message_ids, &text).await {
+                    Ok(_) => Expired::Edited,
+                    Err(err) => {
+                        log::warn!("expiry.expire | Notice {} can't be expired in the {}: {}", notice.id, chat_id, err);
+                        Expired::Failed(err.to_string())
+                    }
+                }
+            }
+        };
+        expired.insert(chat_id, result);
+    }
+    let (deleted, edited, failed) = counts(&expired);
+    log::info!("expiry.expire | Notice {} expired, deleted: {}, edited: {}, failed: {}", notice.id, deleted, edited, failed);
+    update(db, notice.id, |notice| {
+        notice.expired_at = Some(now);
+        notice.expired = expired;
+    }).await?;
+    Ok(())
+}
+///
+/// Returns numbers of the copies deleted, edited and failed
+pub fn counts(expired: &IndexMap<String, Expired>) -> (usize, usize, usize) {
+    expired.values().fold((0, 0, 0), |(deleted, edited, failed), result| match result {
+        Expired::Deleted => (deleted + 1, edited, failed),
+        Expired::Edited => (deleted, edited + 1, failed),
+        Expired::Failed(_) => (deleted, edited, failed + 1),
+    })
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use teloxide::types::MessageId;
+    use crate::notice::{
+        options::{NoticeOptions, Ttl}, report::{DeliveryReport, DeliveryStatus},
+        sent_notice::SentNotice,
+    };
+    use super::is_due;
+    #[test]
+    fn due() {
+        let sent_at = Utc.with_ymd_and_hms(2024, 5, 20, 8, 0, 0).unwrap();
+        let mut report = DeliveryReport::default();
+        report.enqueue(11, "Anton");
+        let mut notice = SentNotice::test(1, sent_at, report, NoticeOptions { ttl: Some(Ttl { minutes: 120 }), ..Default::default() });
+        let at = |minutes: i64| sent_at + Duration::minutes(minutes);
+        assert!(!is_due(&notice, at(120)), "delivery isn't complete");
+        notice.report.set("11", DeliveryStatus::Delivered(vec![MessageId(1)]));
+        assert!(!is_due(&notice, at(119)));
+        assert!(is_due(&notice, at(120)));
+        notice.expired_at = Some(at(120));
+        assert!(!is_due(&notice, at(180)));
+        notice.expired_at = None;
+        notice.recalled_at = Some(at(30));
+        assert!(!is_due(&notice, at(180)));
+        notice.recalled_at = None;
+        notice.options.ttl = None;
+        assert!(!is_due(&notice, at(180)));
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode}};
+use crate::{
+    config::ScheduleConfig, db::storage::Db, kernel::error::{Error, HandlerResult}, loc::{loc, LocaleTag},
+    message::{edit_markup_message_or_send, escape}, states::{MainState, MyDialogue}, subscribe::subscription::{group_titles, Subscriptions},
+    user::{user::User, user_role::UserRole},
+};
+use super::{scheduled_notice::format_time, sent_notice::SentNotice};
+///
+/// Number of the notices on the page
+const PAGE_SIZE: usize = 10;
+///
+/// History menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryMenu {
+   Groups,              // Groups available to the user
+   Group(String, usize),// Page of the notices of the group
+   Notice(u64),         // Selected notice
+   Unknown(String),     // Unknown command received
+   Done,                // Exit menu
+}
+//
+//
+impl HistoryMenu {
+    pub fn parse(s: &str, _loc_tag: LocaleTag) -> Self {
+        let input = s.strip_prefix('/').map_or_else(|| ("", s), |input| ("/", input));
+        match input {
+            ("/", "done" | "Done") => Self::Done,
+            ("/", "back" | "Back") => Self::Done,
+            ("/", "exit" | "Exit") => Self::Done,
+            ("/", "History") => Self::Groups,
+            ("/", input) => {
+                if let Some(id) = input.strip_prefix("HistoryNotice/") {
+                    return id.parse().map_or_else(|_| Self::Unknown(s.to_owned()), Self::Notice);
+                }
+                match input.strip_prefix("History/").and_then(|input| input.rsplit_once('/')) {
+                    Some((group, page)) => match page.parse() {
+                        Ok(page) => Self::Group(group.to_owned(), page),
+                        Err(_) => Self::Unknown(s.to_owned()),
+                    },
+                    None => Self::Unknown(s.to_owned()),
+                }
+            }
+            (_, _) => Self::Unknown(s.to_owned()),
+        }
+    }
+}
+///
+/// Menu the History is opened from, to return on Back btn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HistoryFrom {
+    Notice,
+    Subscribe,
+}
+///
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryState {
+    pub prev_state: MainState,  // Where to go on Back btn
+    pub chat_id: ChatId,        // User id browsing the history
+    pub from: HistoryFrom,      // Menu the History is opened from
+    #[serde(default)]
+    pub group: String,          // Group being browsed
+    #[serde(default)]
+    pub page: usize,            // Page of the group being browsed
+}
+///
+/// Shows groups the user can browse the history of: subscribed ones, Moder's and Admin's see all groups
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: HistoryState, db: Db) -> HandlerResult {
+    log::debug!("history.enter | state: {:#?}", state);
+    let user = db.user(&state.chat_id).await?;
+    let groups = db.subscriptions().await?;
+    let available: Vec<(&String, String)> = groups.iter()
+        .filter(|(group_id, _)| is_available(&user, &groups, group_id))
+        .map(|(group_id, group)| (group_id, group.title.clone()))
+        .collect();
+    let text = match available.is_empty() {
+        true => loc("No groups to browse the history, subscribe to the group first"),
+        false => loc("Select group to browse the history"),
+    };
+    let state = HistoryState { group: String::new(), page: 0, ..state };
+    dialogue.update(state).await?;
+    let mut buttons: Vec<InlineKeyboardButton> = available.into_iter()
+        .map(|(group_id, title)| InlineKeyboardButton::callback(title, format!("/History/{}/0", group_id)))
+        .collect();
+    buttons.push(InlineKeyboardButton::callback(loc("⏪Back"), "/back"));
+    let markup = buttons.into_iter()
+        .fold(InlineKeyboardMarkup::default(), |acc, item| acc.append_row(vec![item]));
+    edit_markup_message_or_send(&bot, &msg, &markup, &text).await
+}
+///
+/// Handles the commands of the History menu, Done is handled by the caller returning to the menu the History is opened from
+pub async fn select(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: HistoryState, cmd: HistoryMenu, db: Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let msg = match q.regular_message() {
+        Some(msg) => msg.to_owned(),
+        None => return Ok(()),
+    };
+    match cmd {
+        HistoryMenu::Group(group, page) => {
+            let user = db.user(&state.chat_id).await?;
+            let groups = db.subscriptions().await?;
+            if !is_available(&user, &groups, &group) {
+                return Err(Error::PermissionDenied(format!("{}, {}", user.name, loc("you aren't subscribed to the group"))).into());
+            }
+            let title = escape(&groups.get(&group).map_or(group.clone(), |group| group.title.clone()));
+            let notices = group_notices(&db, &group).await?;
+            let pages = notices.len().div_ceil(PAGE_SIZE).max(1);
+            let page = page.min(pages - 1);
+            let text = match notices.is_empty() {
+                true => format!("{} '{}'", loc("No notices sent to the group"), title),
+                false => format!("{} '{}' ({}), {} {}/{}", loc("Notices of the group"), title, schedule.time_zone, loc("page"), page + 1, pages),
+            };
+            let mut rows: Vec<Vec<InlineKeyboardButton>> = vec![];
+            for notice in notices.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+                let sender = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+                rows.push(vec![InlineKeyboardButton::callback(
+                    format!("{} | {}", format_time(notice.sent_at, schedule.time_zone), sender),
+                    format!("/HistoryNotice/{}", notice.id),
+                )]);
+            }
+            let mut nav = vec![];
+            if page > 0 {
+                nav.push(InlineKeyboardButton::callback("◀", format!("/History/{}/{}", group, page - 1)));
+            }
+            if page + 1 < pages {
+                nav.push(InlineKeyboardButton::callback("▶", format!("/History/{}/{}", group, page + 1)));
+            }
+            if !nav.is_empty() {
+                rows.push(nav);
+            }
+            rows.push(vec![InlineKeyboardButton::callback(loc("⏪Back"), "/History")]);
+            dialogue.update(HistoryState { group, page, ..state }).await?;
+            edit_markup_message_or_send(&bot, &msg, &InlineKeyboardMarkup::new(rows), &text).await
+        }
+        HistoryMenu::Notice(id) => {
+            let user = db.user(&state.chat_id).await?;
+            let groups = db.subscriptions().await?;
+            let notice = db.sent_notices().await?
+                .shift_remove(&id.to_string())
+                .filter(|notice| is_shown(notice) && notice.groups.iter().any(|group| is_available(&user, &groups, group)))
+                .ok_or_else(|| Error::NotFound(format!("history.select | Sent notice {}", id)))?;
+            let sender = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+            let title = escape(&group_titles(&groups, &notice.groups));
+            notice.content.send(&bot, state.chat_id, &sender, None, notice.options.priority).await?;
+            let stats = match notice.sender == state.chat_id || user.has_role(&[UserRole::Admin, UserRole::Moder]) {
+                true => escape(&notice.report.summary()),
+                false => {
+                    let (delivered, blocked, failed) = notice.report.counts();
+                    format!("{}: {}, {}: {}, {}: {}", loc("Delivered"), delivered, loc("failed"), failed, loc("blocked"), blocked)
+                }
+            };
+            let text = format!(
+                "{} ({}), {} '{}'\n{}",
+                format_time(notice.sent_at, schedule.time_zone), schedule.time_zone, loc("to the group"), title, stats,
+            );
+            let markup = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback(loc("⏪Back"), format!("/History/{}/{}", state.group, state.page)),
+            ]]);
+            bot.send_message(state.chat_id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(markup)
+                .await?;
+            Ok(())
+        }
+        HistoryMenu::Groups | HistoryMenu::Unknown(_) | HistoryMenu::Done => enter(bot, msg, dialogue, state, db).await,
+    }
+}
+///
+/// Returns true if the user can browse the history of the group
+fn is_available(user: &User, groups: &Subscriptions, group: &str) -> bool {
+    user.has_role(&[UserRole::Admin, UserRole::Moder])
+        || groups.get(group).is_some_and(|group| group.members.contains_key(&user.id.to_string()))
+}
+///
+/// Returns notices sent to the group, not recalled and not expired, the latest first
+async fn group_notices(db: &Db, group: &str) -> Result<Vec<SentNotice>, Error> {
+    Ok(db.sent_notices().await?
+        .into_values()
+        .rev()
+        .filter(|notice| is_shown(notice) && notice.groups.iter().any(|id| id == group))
+        .collect())
+}
+///
+/// Returns false for the notice recalled or expired, it isn't shown nor sent again from the history
+fn is_shown(notice: &SentNotice) -> bool {
+    notice.recalled_at.is_none() && notice.expired_at.is_none()
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use super::HistoryMenu;
+    #[test]
+    fn parse() {
+        assert_eq!(HistoryMenu::parse("/History", 0), HistoryMenu::Groups);
+        assert_eq!(HistoryMenu::parse("/History/TKZ_OFFICE_GROUP/2", 0), HistoryMenu::Group("TKZ_OFFICE_GROUP".to_owned(), 2));
+        assert_eq!(HistoryMenu::parse("/History/-100123/0", 0), HistoryMenu::Group("-100123".to_owned(), 0));
+        assert_eq!(HistoryMenu::parse("/HistoryNotice/7", 0), HistoryMenu::Notice(7));
+        assert_eq!(HistoryMenu::parse("/back", 0), HistoryMenu::Done);
+        assert_eq!(HistoryMenu::parse("/History/x", 0), HistoryMenu::Unknown("/History/x".to_owned()));
+    }
+}
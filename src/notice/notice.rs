@@ -0,0 +1,1042 @@
+use std::{collections::HashMap, time::Duration};
+use chrono::Utc;
+use chrono_tz::Tz;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId}};
+use tokio::{sync::Mutex, time::sleep};
+use crate::{
+    config::ScheduleConfig, db::storage::Db, kernel::error::{Error, HandlerResult}, loc::{loc, LocaleTag},
+    message::{edit_markup_message_or_send, escape, reply_on_error, to_html, Priority}, states::{MainState, MyDialogue}, subscribe::subscription::{denied_groups, group_titles, Subscriptions},
+    user::{user::User, user_role::UserRole},
+};
+use super::{
+    content::{MediaItem, NoticeContent}, expiry, options::{Escalation, NoticeOptions, Rsvp, RsvpAnswer, RsvpKind, Ttl}, outbox, recall, rsvp, recurrence::Recurrence, recurring_notice::RecurringNotice, report::DeliveryReport,
+    scheduled_notice::{format_time, parse_time, ScheduledNotice}, senders, sent_notice::SentNotice, templates,
+};
+///
+/// Time to wait for the next item of the album
+const ALBUM_WAIT: Duration = Duration::from_millis(1500);
+///
+/// Max number of the sent notices listed in the Reports
+const MAX_REPORTS: usize = 20;
+///
+/// Messages of the albums being received with their files, by sender chat and media group id
+type Albums = HashMap<(ChatId, String), Vec<(MessageId, Option<MediaItem>)>>;
+lazy_static! {
+    ///
+    /// Albums being received, by sender chat and media group id
+    static ref ALBUMS: Mutex<Albums> = Mutex::new(HashMap::new());
+}
+///
+/// Notice menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoticeMenu {
+   Group(String),   // Group to be selected / unselected
+   Continue,        // Groups are selected, notice is expected
+   Send,            // Send the previewed notice
+   Schedule,        // Schedule the previewed notice
+   Repeat,          // Send the previewed notice repeatedly
+   Edit,            // Replace the previewed notice
+   Cancel,          // Drop the previewed notice
+   RequireAck,      // Switch the acknowledgement of the previewed notice
+   Escalation,      // Ask the reminders policy of the previewed notice
+   Priority(Priority), // Set the priority of the previewed notice
+   Rsvp,            // Switch the answers of the previewed notice: off, buttons, poll
+   RsvpDeadline,    // Ask the deadline of the answers of the previewed notice
+   Ttl,             // Ask the time-to-live of the previewed notice
+   Templates,       // Templates of the notice
+   Template(u64),   // Fill in the template for the notice
+   TemplateAdd,     // Admin adds the template
+   TemplateEdit(u64),   // Admin edits the template
+   TemplateDelete(u64), // Admin deletes the template
+   Senders,         // Moder edits the senders allowed to notice the groups
+   SendersGroup(String),        // Senders allowed to notice the group
+   SenderRole(String, UserRole), // Allow / disallow the role to notice the group
+   SenderUser(String, ChatId),  // Allow / disallow the user to notice the group
+   Reports,         // List of the sent notices
+   Report(u64),     // Delivery report of the sent notice
+   History,         // Browse the notices of the groups
+   Correct(u64),    // Correct the text of the sent notice in every chat
+   Recall(u64),     // Ask to delete the sent notice from every chat
+   RecallConfirm(u64), // Delete the sent notice from every chat
+   Groups,          // Back to the group selection
+   Unknown(String), // Unknown command received
+   Done,            // Exit menu
+}
+//
+//
+impl NoticeMenu {
+   pub fn parse(s: &str, _loc_tag: LocaleTag) -> Self {
+        let input = s.strip_prefix('/').map_or_else(|| ("", s), |input| ("/", input));
+        match input {
+            ("/", "done" | "Done") => Self::Done,
+            ("/", "back" | "Back") => Self::Done,
+            ("/", "exit" | "Exit") => Self::Done,
+            ("/", "NoticeSend") => Self::Send,
+            ("/", "NoticeSchedule") => Self::Schedule,
+            ("/", "NoticeRepeat") => Self::Repeat,
+            ("/", "NoticeEdit") => Self::Edit,
+            ("/", "NoticeCancel") => Self::Cancel,
+            ("/", "NoticeRequireAck") => Self::RequireAck,
+            ("/", "NoticeEscalation") => Self::Escalation,
+            ("/", "NoticeRsvp") => Self::Rsvp,
+            ("/", "NoticeRsvpDeadline") => Self::RsvpDeadline,
+            ("/", "NoticeTtl") => Self::Ttl,
+            ("/", "NoticeReports") => Self::Reports,
+            ("/", "NoticeGroups") => Self::Groups,
+            ("/", "NoticeHistory") => Self::History,
+            ("/", "NoticeContinue") => Self::Continue,
+            ("/", "NoticeTemplates") => Self::Templates,
+            ("/", "NoticeTemplateAdd") => Self::TemplateAdd,
+            ("/", "NoticeSenders") => Self::Senders,
+            ("/", input) if input.starts_with("NoticeSenders/") => Self::SendersGroup(input["NoticeSenders/".len()..].to_owned()),
+            ("/", input) if input.starts_with("NoticeSenderRole/") => match input["NoticeSenderRole/".len()..].split_once('/') {
+                Some((role, group)) => match senders::parse_role(role) {
+                    Some(role) => Self::SenderRole(group.to_owned(), role),
+                    None => Self::Unknown(s.to_owned()),
+                },
+                None => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeSenderUser/") => match input["NoticeSenderUser/".len()..].split_once('/') {
+                Some((user_id, group)) => match user_id.parse() {
+                    Ok(user_id) => Self::SenderUser(group.to_owned(), ChatId(user_id)),
+                    Err(_) => Self::Unknown(s.to_owned()),
+                },
+                None => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeReport/") => match input["NoticeReport/".len()..].parse() {
+                Ok(id) => Self::Report(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticePriority/") => match Priority::parse(&input["NoticePriority/".len()..]) {
+                Some(priority) => Self::Priority(priority),
+                None => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeTemplate/") => match input["NoticeTemplate/".len()..].parse() {
+                Ok(id) => Self::Template(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeTemplateEdit/") => match input["NoticeTemplateEdit/".len()..].parse() {
+                Ok(id) => Self::TemplateEdit(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeTemplateDelete/") => match input["NoticeTemplateDelete/".len()..].parse() {
+                Ok(id) => Self::TemplateDelete(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeCorrect/") => match input["NoticeCorrect/".len()..].parse() {
+                Ok(id) => Self::Correct(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeRecall/") => match input["NoticeRecall/".len()..].parse() {
+                Ok(id) => Self::Recall(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) if input.starts_with("NoticeRecallConfirm/") => match input["NoticeRecallConfirm/".len()..].parse() {
+                Ok(id) => Self::RecallConfirm(id),
+                Err(_) => Self::Unknown(s.to_owned()),
+            },
+            ("/", input) => Self::Group(input.to_owned()),
+            (_, _) => Self::Unknown(s.to_owned()),
+        }
+   }
+}
+///
+/// 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoticeState {
+    pub prev_state: MainState,  // Where to go on Back btn
+    #[serde(default)]
+    pub groups: Vec<String>,    // Group ids to be noticed
+    #[serde(default)]
+    pub groups_selected: bool,  // Groups are selected, notice is expected
+    pub chat_id: ChatId,        // User id doing notice
+    #[serde(default)]
+    pub content: Option<NoticeContent>, // Notice waiting for confirmation
+    #[serde(default)]
+    pub awaiting_time: bool,            // Time to schedule the notice at is expected
+    #[serde(default)]
+    pub scheduled_id: Option<u64>,      // Scheduled notice being edited
+    #[serde(default)]
+    pub awaiting_rule: bool,            // Rule to repeat the notice by is expected
+    #[serde(default)]
+    pub recurring_id: Option<u64>,      // Recurring notice being edited
+    #[serde(default)]
+    pub correcting_id: Option<u64>,     // Sent notice which corrected text is expected
+    #[serde(default)]
+    pub options: NoticeOptions,         // Options of the previewed notice
+    #[serde(default)]
+    pub awaiting_escalation: bool,      // Reminders policy of the previewed notice is expected
+    #[serde(default)]
+    pub awaiting_deadline: bool,        // Deadline of the answers of the previewed notice is expected
+    #[serde(default)]
+    pub awaiting_ttl: bool,             // Time-to-live of the previewed notice is expected
+    #[serde(default)]
+    pub template_id: Option<u64>,       // Template being filled in
+    #[serde(default)]
+    pub template_values: Vec<String>,   // Values of the template placeholders typed so far
+    #[serde(default)]
+    pub editing_template: Option<u64>,  // Template being typed by the Admin, 0 for the new one
+}
+//
+//
+impl Default for NoticeState {
+    fn default() -> Self {
+        Self { prev_state: MainState::default(), groups: vec![], groups_selected: false, chat_id: ChatId(0), content: None, awaiting_time: false, scheduled_id: None, awaiting_rule: false, recurring_id: None, correcting_id: None, options: NoticeOptions::default(), awaiting_escalation: false, awaiting_deadline: false, awaiting_ttl: false, template_id: None, template_values: vec![], editing_template: None }
+    }
+}
+///
+///  
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db) -> HandlerResult {
+    log::debug!("notice.enter | state: {:#?}", state);
+    let user = db.user(&state.chat_id).await?;
+    let groups =  match db.subscriptions().await {
+        Ok(groups) => groups,
+        Err(err) => {
+            log::warn!("notice.enter | Groups is empty, error: {:#?}", err);
+            IndexMap::new()
+        }
+    };
+    if state.groups_selected && !state.groups.is_empty() {
+        let group_title = escape(&group_titles(&groups, &state.groups));
+        let text = format!("Type a text or send a photo, document, video, voice, sticker or album for group '{}'", group_title);
+        dialogue.update(state.clone()).await?;
+        view(&bot, &msg, &state, &user, &groups, text, Some(())).await?;
+    } else {
+        let text = format!("Select groups to notice and press Continue");
+        dialogue.update(state.clone()).await?;
+        view(&bot, &msg, &state, &user, &groups, text, None).await?;
+    }
+    Ok(())
+}
+///
+/// Receives the notice content from the sender and shows the preview to be confirmed,
+/// album items are collected until the whole media group is received
+pub async fn notice(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db, schedule: ScheduleConfig) -> HandlerResult {
+    if let Some(id) = state.correcting_id {
+        return correct(bot, msg, dialogue, state, id, db, schedule).await;
+    }
+    if let Some(id) = state.editing_template {
+        return templates::save(bot, msg, dialogue, state, id, db).await;
+    }
+    check_senders(&db, state.chat_id, &state.groups).await?;
+    if let Some(id) = state.template_id {
+        return templates::fill(bot, msg, dialogue, state, id, db).await;
+    }
+    if state.awaiting_time && state.content.is_some() {
+        return schedule_at(bot, msg, dialogue, state, db, schedule).await;
+    }
+    if state.awaiting_rule && state.content.is_some() {
+        return repeat_by(bot, msg, dialogue, state, db, schedule).await;
+    }
+    if state.awaiting_escalation && state.content.is_some() {
+        return escalate_by(bot, msg, dialogue, state, db).await;
+    }
+    if state.awaiting_deadline && state.content.is_some() {
+        return close_at(bot, msg, dialogue, state, db, schedule).await;
+    }
+    if state.awaiting_ttl && state.content.is_some() {
+        return expire_in(bot, msg, dialogue, state, db).await;
+    }
+    if let Some(media_group_id) = msg.media_group_id() {
+        return album(bot, msg.clone(), media_group_id.to_owned(), dialogue, state, db).await;
+    }
+    match NoticeContent::from_message(&msg) {
+        Some(content) => preview(&bot, dialogue, state, content, &db).await,
+        None => {
+            bot.send_message(state.chat_id, loc("Notice can't be empty, send a text, photo, document, video, voice or sticker"))
+                .await?;
+            Ok(())
+        }
+    }
+}
+///
+/// Schedules the confirmed notice at the time typed by the sender
+async fn schedule_at(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db, schedule: ScheduleConfig) -> HandlerResult {
+    let at = match parse_time(msg.text().unwrap_or_default(), Utc::now(), schedule.time_zone) {
+        Ok(at) => at,
+        Err(err) => {
+            log::debug!("notice.schedule_at | {}", err);
+            bot.send_message(state.chat_id, format!("{}\n{}", loc("Wrong or passed time"), time_hint(&schedule)))
+                .await?;
+            return Ok(());
+        }
+    };
+    // The notice edited by the Moder stays the notice of its sender
+    let stored = match state.scheduled_id {
+        Some(id) => db.scheduled_notices().await?.shift_remove(&id.to_string()),
+        None => None,
+    };
+    let notice = ScheduledNotice {
+        id: state.scheduled_id.unwrap_or(0),
+        sender: stored.map_or(state.chat_id, |stored| stored.sender),
+        groups: state.groups.clone(),
+        content: state.content.clone().unwrap_or(NoticeContent::Text(String::new())),
+        at,
+        options: state.options.clone(),
+    };
+    let id = db.update_scheduled_notice(notice).await?;
+    log::info!("notice.schedule_at | Notice {} from {} to the {:?} scheduled at {}", id, state.chat_id, state.groups, at);
+    bot.send_message(state.chat_id, format!("{} {} ({})", loc("Notice scheduled at"), format_time(at, schedule.time_zone), schedule.time_zone))
+        .await?;
+    done(bot, msg, dialogue, state, db).await
+}
+///
+/// Asks the sender for the time to schedule the notice at
+pub async fn ask_time(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, schedule: &ScheduleConfig) -> HandlerResult {
+    dialogue.update(NoticeState { awaiting_time: true, ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &time_hint(schedule)).await
+}
+///
+///
+fn time_hint(schedule: &ScheduleConfig) -> String {
+    format!(
+        "{} ({}):\n2024-05-20 08:00\n20.05.2024 08:00\n08:00",
+        loc("Type the date and time to send the notice at"), schedule.time_zone,
+    )
+}
+///
+/// Saves the confirmed notice to be repeated by the rule typed by the sender, in the time zone typed after the rule,
+/// or the one of the notice being changed, or the `schedule` one.
+/// The notice changed by the Moder stays the notice of its sender
+async fn repeat_by(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db, schedule: ScheduleConfig) -> HandlerResult {
+    let now = Utc::now();
+    let stored = match state.recurring_id {
+        Some(id) => db.recurring_notices().await?.shift_remove(&id.to_string()),
+        None => None,
+    };
+    let (input, typed_zone) = split_time_zone(msg.text().unwrap_or_default());
+    let zone = match (typed_zone, &stored) {
+        (Some(zone), _) => Some(zone),
+        (None, Some(stored)) => stored.time_zone,
+        (None, None) => Some(schedule.time_zone),
+    };
+    let time_zone = zone.unwrap_or(schedule.time_zone);
+    let today = now.with_timezone(&time_zone).date_naive();
+    let rule = Recurrence::parse(input).map(|rule| rule.anchored(today));
+    let next = rule.as_ref().ok().and_then(|rule| rule.next_after(now, time_zone));
+    let (rule, next) = match (rule, next) {
+        (Ok(rule), Some(next)) => (rule, next),
+        (rule, _) => {
+            log::debug!("notice.repeat_by | Rule: {:?}", rule);
+            bot.send_message(state.chat_id, format!("{}\n{}", loc("Wrong rule or it never fires"), rule_hint(&schedule)))
+                .await?;
+            return Ok(());
+        }
+    };
+    let notice = RecurringNotice {
+        id: state.recurring_id.unwrap_or(0),
+        sender: stored.map_or(state.chat_id, |stored| stored.sender),
+        groups: state.groups.clone(),
+        content: state.content.clone().unwrap_or(NoticeContent::Text(String::new())),
+        rule: rule.clone(),
+        time_zone: zone,
+        last_sent: Some(now),
+        options: state.options.clone(),
+    };
+    let id = db.update_recurring_notice(notice).await?;
+    log::info!("notice.repeat_by | Notice {} from {} to the {:?} repeated {}", id, state.chat_id, state.groups, rule);
+    bot.send_message(state.chat_id, format!(
+        "{} '{}'\n{} {} ({})",
+        loc("Notice is repeated"), rule, loc("Next at"), format_time(next, time_zone), time_zone,
+    )).await?;
+    done(bot, msg, dialogue, state, db).await
+}
+///
+/// Returns the rule and the time zone typed after it, like 'every day at 09:00 Europe/Berlin'
+fn split_time_zone(input: &str) -> (&str, Option<Tz>) {
+    let input = input.trim();
+    match input.rsplit_once(char::is_whitespace).map(|(rule, zone)| (rule, zone.parse::<Tz>())) {
+        Some((rule, Ok(zone))) => (rule.trim_end(), Some(zone)),
+        _ => (input, None),
+    }
+}
+///
+/// Asks the sender for the rule to repeat the notice by
+pub async fn ask_rule(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, schedule: &ScheduleConfig) -> HandlerResult {
+    dialogue.update(NoticeState { awaiting_rule: true, ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &rule_hint(schedule)).await
+}
+///
+///
+fn rule_hint(schedule: &ScheduleConfig) -> String {
+    format!(
+        "{} ({}):\nevery day at 09:00\nevery 14 days at 09:00\nevery mon,fri at 09:00\nevery mon-fri at 09:00\n0 18 25 * * - {}\nevery day at 09:00 Europe/Berlin - {}",
+        loc("Type the rule to repeat the notice by"), schedule.time_zone, loc("cron: minute hour day month weekday"), loc("in the other time zone"),
+    )
+}
+///
+/// Sets the reminders policy of the previewed notice typed by the sender, 'off' drops it
+async fn escalate_by(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db) -> HandlerResult {
+    let input = msg.text().unwrap_or_default().trim();
+    let escalation = match input.eq_ignore_ascii_case("off") {
+        true => None,
+        false => match Escalation::parse(input) {
+            Ok(escalation) => Some(escalation),
+            Err(err) => {
+                log::debug!("notice.escalate_by | {}", err);
+                bot.send_message(state.chat_id, format!("{}\n{}", loc("Wrong reminders policy"), escalation_hint())).await?;
+                return Ok(());
+            }
+        },
+    };
+    let mut options = state.options.clone();
+    options.escalation = escalation;
+    let state = NoticeState { options, awaiting_escalation: false, ..state };
+    dialogue.update(state.clone()).await?;
+    ask_confirm(&bot, &state, &db).await
+}
+///
+/// Asks the sender for the policy of the reminders to the recipients not acknowledged the notice
+pub async fn ask_escalation(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState) -> HandlerResult {
+    dialogue.update(NoticeState { awaiting_escalation: true, ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &escalation_hint()).await
+}
+///
+///
+fn escalation_hint() -> String {
+    format!(
+        "{}:\nevery 2h, 3 times\nevery 30m, 1 time\nevery 1d\noff - {}",
+        loc("Type how often to remind the recipients not acknowledged the notice and how many times, then the Moders of the group get the list of them"),
+        loc("no reminders"),
+    )
+}
+///
+/// Sets the time-to-live of the previewed notice typed by the sender, 'off' drops it
+async fn expire_in(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db) -> HandlerResult {
+    let input = msg.text().unwrap_or_default().trim();
+    let ttl = match input.eq_ignore_ascii_case("off") {
+        true => None,
+        false => match Ttl::parse(input) {
+            Ok(ttl) => Some(ttl),
+            Err(err) => {
+                log::debug!("notice.expire_in | {}", err);
+                bot.send_message(state.chat_id, format!("{}\n{}", loc("Wrong time-to-live"), ttl_hint())).await?;
+                return Ok(());
+            }
+        },
+    };
+    let mut options = state.options.clone();
+    options.ttl = ttl;
+    let state = NoticeState { options, awaiting_ttl: false, ..state };
+    dialogue.update(state.clone()).await?;
+    ask_confirm(&bot, &state, &db).await
+}
+///
+/// Asks the sender for the time-to-live of the notice
+pub async fn ask_ttl(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState) -> HandlerResult {
+    dialogue.update(NoticeState { awaiting_ttl: true, ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &ttl_hint()).await
+}
+///
+///
+fn ttl_hint() -> String {
+    format!(
+        "{}:\n30m\n2h\n1d\noff - {}",
+        loc("Type how long the notice is actual, then it's deleted from every chat"), loc("never expires"),
+    )
+}
+///
+/// Sets the deadline of the answers of the previewed notice typed by the sender, 'none' drops it
+async fn close_at(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db, schedule: ScheduleConfig) -> HandlerResult {
+    let input = msg.text().unwrap_or_default().trim();
+    let deadline = match input.eq_ignore_ascii_case("none") {
+        true => None,
+        false => match parse_time(input, Utc::now(), schedule.time_zone) {
+            Ok(deadline) => Some(deadline),
+            Err(err) => {
+                log::debug!("notice.close_at | {}", err);
+                bot.send_message(state.chat_id, format!("{}\n{}", loc("Wrong or passed time"), deadline_hint(&schedule))).await?;
+                return Ok(());
+            }
+        },
+    };
+    let mut options = state.options.clone();
+    options.rsvp = options.rsvp.map(|rsvp| Rsvp { deadline, ..rsvp });
+    if let Some(deadline) = deadline {
+        bot.send_message(state.chat_id, format!(
+            "{} {} ({})", loc("Answers close at"), format_time(deadline, schedule.time_zone), schedule.time_zone,
+        )).await?;
+    }
+    let state = NoticeState { options, awaiting_deadline: false, ..state };
+    dialogue.update(state.clone()).await?;
+    ask_confirm(&bot, &state, &db).await
+}
+///
+/// Asks the sender for the deadline of the answers
+pub async fn ask_deadline(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, schedule: &ScheduleConfig) -> HandlerResult {
+    dialogue.update(NoticeState { awaiting_deadline: true, ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &deadline_hint(schedule)).await
+}
+///
+///
+fn deadline_hint(schedule: &ScheduleConfig) -> String {
+    format!(
+        "{} ({}):\n2024-05-20 08:00\n20.05.2024 08:00\n08:00\nnone - {}",
+        loc("Type the date and time the answers close at"), schedule.time_zone, loc("no deadline"),
+    )
+}
+///
+/// Shows the notice exactly as the receivers will get it,
+/// with the target group and Send / Schedule / Repeat / Edit / Cancel buttons
+pub async fn preview(bot: &Bot, dialogue: MyDialogue, state: NoticeState, content: NoticeContent, db: &Db) -> HandlerResult {
+    let groups = db.subscriptions().await?;
+    if !state.groups.iter().any(|group| groups.get(group).is_some_and(|group| group.is_active())) {
+        return Err(Error::NotFound(format!("notice.preview | Groups {:?}", state.groups)).into());
+    }
+    let user = db.user(&state.chat_id).await?;
+    if let Some((len, max)) = content.too_long(&user.name) {
+        bot.send_message(state.chat_id, format!("{}: {} / {}", loc("Notice is too long, shorten it and send again"), len, max)).await?;
+        return Ok(());
+    }
+    content.send(bot, state.chat_id, &user.name, None, state.options.priority).await?;
+    let state = NoticeState { content: Some(content), ..state };
+    dialogue.update(state.clone()).await?;
+    ask_confirm(bot, &state, db).await
+}
+///
+/// Sends the target groups, the number of the recipients and the confirmation buttons of the previewed notice
+async fn ask_confirm(bot: &Bot, state: &NoticeState, db: &Db) -> HandlerResult {
+    let groups = db.subscriptions().await?;
+    let recipients = recipients(&groups, &state.groups).recipients.len();
+    let text = format!(
+        "{} '{}'\n{}: {}",
+        loc("Send the notice above to the group"), group_titles(&groups, &state.groups), loc("Recipients"), recipients,
+    );
+    bot.send_message(state.chat_id, text)
+        .reply_markup(confirm_markup(&state.options))
+        .await?;
+    Ok(())
+}
+///
+/// Handles Send / Schedule / Repeat / Edit / Cancel and the options switches of the previewed notice
+pub async fn confirm(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: NoticeState, cmd: NoticeMenu, db: Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let msg = match q.regular_message() {
+        Some(msg) => msg.to_owned(),
+        None => return Ok(()),
+    };
+    match (cmd, &state.content) {
+        (NoticeMenu::Send, Some(content)) => {
+            check_senders(&db, state.chat_id, &state.groups).await?;
+            broadcast(state.chat_id, &state.groups, content, &state.options, &db).await?;
+            if let Some(id) = state.scheduled_id {
+                db.remove_scheduled_notice(id).await?;
+            }
+            bot.answer_callback_query(q.id).text(loc("Notice sent")).await?;
+            done(bot, msg, dialogue, state, db).await
+        }
+        (NoticeMenu::Schedule, Some(_)) => ask_time(&bot, &msg, dialogue, state, schedule).await,
+        (NoticeMenu::Repeat, Some(_)) => ask_rule(&bot, &msg, dialogue, state, schedule).await,
+        (NoticeMenu::Priority(priority), Some(_)) => {
+            let mut options = state.options.clone();
+            options.priority = priority;
+            bot.edit_message_reply_markup(msg.chat.id, msg.id)
+                .reply_markup(confirm_markup(&options))
+                .await?;
+            dialogue.update(NoticeState { options, ..state }).await?;
+            Ok(())
+        }
+        (NoticeMenu::Rsvp, Some(_)) => {
+            let mut options = state.options.clone();
+            let deadline = options.rsvp.and_then(|rsvp| rsvp.deadline);
+            options.rsvp = match options.rsvp.map(|rsvp| rsvp.kind) {
+                None => Some(Rsvp { kind: RsvpKind::Buttons, deadline }),
+                Some(RsvpKind::Buttons) => Some(Rsvp { kind: RsvpKind::Poll, deadline }),
+                Some(RsvpKind::Poll) => None,
+            };
+            bot.edit_message_reply_markup(msg.chat.id, msg.id)
+                .reply_markup(confirm_markup(&options))
+                .await?;
+            dialogue.update(NoticeState { options, ..state }).await?;
+            Ok(())
+        }
+        (NoticeMenu::RsvpDeadline, Some(_)) => ask_deadline(&bot, &msg, dialogue, state, schedule).await,
+        (NoticeMenu::Ttl, Some(_)) => ask_ttl(&bot, &msg, dialogue, state).await,
+        (NoticeMenu::Escalation, Some(_)) => ask_escalation(&bot, &msg, dialogue, state).await,
+        (NoticeMenu::RequireAck, Some(_)) => {
+            let mut options = state.options.clone();
+            options.requires_ack = !options.requires_ack;
+            bot.edit_message_reply_markup(msg.chat.id, msg.id)
+                .reply_markup(confirm_markup(&options))
+                .await?;
+            dialogue.update(NoticeState { options, ..state }).await?;
+            Ok(())
+        }
+        (NoticeMenu::Edit, _) => {
+            let state = NoticeState { content: None, awaiting_time: false, awaiting_rule: false, awaiting_escalation: false, awaiting_deadline: false, awaiting_ttl: false, template_id: None, template_values: vec![], ..state };
+            enter(bot, msg, dialogue, state, db).await
+        }
+        (NoticeMenu::Cancel, _) => {
+            bot.answer_callback_query(q.id).text(loc("Notice canceled")).await?;
+            done(bot, msg, dialogue, state, db).await
+        }
+        (cmd, content) => {
+            log::warn!("notice.confirm | Command {:?} with notice {:?} - ignored", cmd, content);
+            enter(bot, msg, dialogue, state, db).await
+        }
+    }
+}
+///
+/// Returns an error if the user isn't allowed to notice some of the selected active groups
+async fn check_senders(db: &Db, chat_id: ChatId, selected: &[String]) -> Result<(), Error> {
+    let groups = db.subscriptions().await?;
+    let user = db.user(&chat_id).await?;
+    let denied = denied_groups(&groups, &user, selected);
+    match denied.is_empty() {
+        true => Ok(()),
+        false => {
+            log::warn!("notice.check_senders | User '{}' ({}) isn't allowed to notice the {:?}", user.name, user.id, denied);
+            Err(Error::PermissionDenied(format!("{}, {} '{}'", user.name, loc("you aren't allowed to notice the group"), group_titles(&groups, &denied))))
+        }
+    }
+}
+///
+/// Collects the album item, the first item of the album waits for the rest
+/// and sends the whole album when no more items are received within ALBUM_WAIT
+async fn album(bot: Bot, msg: Message, media_group_id: String, dialogue: MyDialogue, state: NoticeState, db: Db) -> HandlerResult {
+    let key = (msg.chat.id, media_group_id);
+    {
+        let mut albums = ALBUMS.lock().await;
+        if let Some(items) = albums.get_mut(&key) {
+            items.push((msg.id, MediaItem::from_message(&msg)));
+            return Ok(());
+        }
+        albums.insert(key.clone(), vec![(msg.id, MediaItem::from_message(&msg))]);
+    }
+    tokio::spawn(async move {
+        let mut received = 0;
+        loop {
+            sleep(ALBUM_WAIT).await;
+            let len = ALBUMS.lock().await.get(&key).map_or(0, |items| items.len());
+            if len == received {
+                break;
+            }
+            received = len;
+        }
+        let items = ALBUMS.lock().await.remove(&key).unwrap_or_default();
+        log::debug!("notice.album | Album '{}' received, {} items", key.1, items.len());
+        let content = NoticeContent::album(key.0, items);
+        let result = preview(&bot, dialogue, state, content, &db).await;
+        if let Err(err) = reply_on_error(&bot, key.0, result).await {
+            log::warn!("notice.album | Error previewing album '{}': {:?}", key.1, err);
+        }
+    });
+    Ok(())
+}
+///
+/// Queues the content to the outbox with the chats and members of the groups as recipients,
+/// the outbox worker sends it with the sender header and reports the delivery to the sender
+pub async fn broadcast(sender: ChatId, selected: &[String], content: &NoticeContent, options: &NoticeOptions, db: &Db) -> Result<SentNotice, Error> {
+    let groups =  match db.subscriptions().await {
+        Ok(groups) => groups,
+        Err(err) => {
+            log::warn!("notice.broadcast | Groups is empty, error: {:#?}", err);
+            IndexMap::new()
+        }
+    };
+    log::debug!("notice.broadcast | Queueing notice from {}: '{:?}'", sender, content);
+    let report = recipients(&groups, selected);
+    let notice = SentNotice {
+        id: 0, sender, groups: selected.to_vec(), content: content.clone(), sent_at: Utc::now(), report,
+        edited_at: None, recalled_at: None, options: options.clone(), acks: IndexMap::new(), ack_view: None,
+        reminders: 0, reminded_at: None, escalated_at: None,
+        votes: IndexMap::new(), polls: IndexMap::new(), rsvp_view: None, closed_at: None, expired_at: None, expired: Default::default(),
+    };
+    let id = db.update_sent_notice(notice.clone()).await?;
+    log::info!("notice.broadcast | Notice {} from {} to the {:?} queued, recipients: {}", id, sender, selected, notice.report.recipients.len());
+    outbox::wake();
+    Ok(SentNotice { id, ..notice })
+}
+///
+/// Returns the report with the union of the chats and members of the selected groups,
+/// every chat id is queued once, even if the group chat is also a member of some group
+fn recipients(groups: &Subscriptions, selected: &[String]) -> DeliveryReport {
+    let mut report = DeliveryReport::default();
+    for group_id in selected {
+        match groups.get(group_id).filter(|group| group.is_active()) {
+            Some(group) => {
+                if let Some(chat_id) = &group.id {
+                    report.enqueue(chat_id, &group.title);
+                }
+                for receiver in group.members.values() {
+                    report.enqueue(receiver.id, &receiver.name);
+                }
+            }
+            None => log::warn!("notice.recipients | Group '{}' not found or archived in the subscriptions", group_id),
+        }
+    }
+    report
+}
+///
+/// Shows the notices sent by the user, Moder's and Admin's see all notices, the latest first
+pub async fn reports(bot: &Bot, msg: &Message, state: &NoticeState, db: &Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let groups = db.subscriptions().await?;
+    let notices = sent_notices(db, state.chat_id).await?;
+    let text = match notices.is_empty() {
+        true => loc("No sent notices"),
+        false => format!("{} ({})", loc("Sent notices"), schedule.time_zone),
+    };
+    let mut buttons: Vec<InlineKeyboardButton> = notices.iter()
+        .take(MAX_REPORTS)
+        .map(|notice| {
+            let group = group_titles(&groups, &notice.groups);
+            InlineKeyboardButton::callback(
+                format!("{} | {}", format_time(notice.sent_at, schedule.time_zone), group),
+                format!("/NoticeReport/{}", notice.id),
+            )
+        })
+        .collect();
+    buttons.push(InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeGroups"));
+    let markup = buttons.into_iter()
+        .fold(InlineKeyboardMarkup::default(), |acc, item| acc.append_row(vec![item]));
+    edit_markup_message_or_send(bot, msg, &markup, &text).await
+}
+///
+/// Shows the delivery report of the sent notice
+pub async fn report(bot: &Bot, msg: &Message, state: &NoticeState, id: u64, db: &Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let notice = sent_notices(db, state.chat_id).await?
+        .into_iter()
+        .find(|notice| notice.id == id)
+        .ok_or_else(|| Error::NotFound(format!("notice.report | Sent notice {}", id)))?;
+    let sender = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+    let group = group_titles(&db.subscriptions().await?, &notice.groups);
+    let mut text = format!(
+        "{} '{}'\n{} ({}), {}\n{}",
+        loc("Notice delivered to the group"), escape(&group), format_time(notice.sent_at, schedule.time_zone), schedule.time_zone, escape(&sender), escape(&notice.report.summary()),
+    );
+    if notice.options.priority != Priority::Normal {
+        text.push_str(&format!("\n{}: {}", loc("Priority"), notice.options.priority.title()));
+    }
+    if let Some(edited_at) = notice.edited_at {
+        text.push_str(&format!("\n{} {}", loc("Corrected at"), format_time(edited_at, schedule.time_zone)));
+    }
+    if let Some(recalled_at) = notice.recalled_at {
+        text.push_str(&format!("\n{} {}", loc("Recalled at"), format_time(recalled_at, schedule.time_zone)));
+    }
+    match (notice.expired_at, notice.options.ttl) {
+        (Some(expired_at), _) => {
+            let (deleted, edited, failed) = expiry::counts(&notice.expired);
+            text.push_str(&format!(
+                "\n{} {}, {}: {}, {}: {}, {}: {}",
+                loc("Expired at"), format_time(expired_at, schedule.time_zone), loc("deleted"), deleted, loc("marked expired"), edited, loc("failed"), failed,
+            ));
+        }
+        (None, Some(ttl)) => text.push_str(&format!("\n{} {}", loc("Expires at"), format_time(notice.sent_at + ttl.duration(), schedule.time_zone))),
+        (None, None) => {}
+    }
+    let mut rows = vec![];
+    if notice.recalled_at.is_none() && notice.expired_at.is_none() && notice.report.is_complete() && !notice.report.delivered().is_empty() {
+        let mut row = vec![];
+        if notice.content.corrected("").is_some() {
+            row.push(InlineKeyboardButton::callback(loc("Correct text"), format!("/NoticeCorrect/{}", id)));
+        }
+        row.push(InlineKeyboardButton::callback(loc("Recall"), format!("/NoticeRecall/{}", id)));
+        rows.push(row);
+    }
+    if notice.options.requires_ack {
+        text.push_str(&format!("\n{}: {}", loc("acknowledged"), notice.acks.len()));
+        if let Some(escalation) = notice.options.escalation {
+            text.push_str(&format!("\n{}: {}, {}: {}", loc("Reminders"), escalation, loc("sent"), notice.reminders));
+            if let Some(escalated_at) = notice.escalated_at {
+                text.push_str(&format!("\n{} {}", loc("Reported to the Moders at"), format_time(escalated_at, schedule.time_zone)));
+            }
+        }
+        rows.push(vec![InlineKeyboardButton::callback(loc("Acknowledgements"), format!("/AckView/{}", id))]);
+    }
+    if let Some(rsvp) = notice.options.rsvp {
+        let tally = RsvpAnswer::ALL.iter().zip(rsvp::tally(&notice))
+            .map(|(answer, count)| format!("{}: {}", answer.title(), count))
+            .collect::<Vec<String>>()
+            .join(", ");
+        text.push_str(&format!("\n{} ({}): {}", loc("Answers"), rsvp.kind.title(), tally));
+        match (notice.closed_at, rsvp.deadline) {
+            (Some(closed_at), _) => text.push_str(&format!("\n{} {}", loc("Answers closed at"), format_time(closed_at, schedule.time_zone))),
+            (None, Some(deadline)) => text.push_str(&format!("\n{} {}", loc("Answers close at"), format_time(deadline, schedule.time_zone))),
+            (None, None) => {}
+        }
+        rows.push(vec![InlineKeyboardButton::callback(loc("Answers"), format!("/RsvpView/{}", id))]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeReports")]);
+    edit_markup_message_or_send(bot, msg, &InlineKeyboardMarkup::new(rows), &text).await
+}
+///
+/// Asks the sender for the corrected text of the sent notice
+pub async fn ask_correction(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: &Db) -> HandlerResult {
+    let notice = correctable(db, state.chat_id, id).await?;
+    dialogue.update(NoticeState { correcting_id: Some(notice.id), ..state }).await?;
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &loc("Type the corrected text, it replaces the notice text in every chat")).await
+}
+///
+/// Replaces the text of every delivered copy of the sent notice with the text typed by the sender
+async fn correct(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: Db, schedule: ScheduleConfig) -> HandlerResult {
+    let Some(text) = msg.text() else {
+        bot.send_message(state.chat_id, loc("Type the corrected text, it replaces the notice text in every chat")).await?;
+        return Ok(());
+    };
+    let notice = correctable(&db, state.chat_id, id).await?;
+    let text = to_html(text, msg.entities().unwrap_or_default());
+    let sender = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+    if let Some((len, max)) = notice.content.corrected(&text).and_then(|content| content.too_long(&sender)) {
+        bot.send_message(state.chat_id, format!("{}: {} / {}", loc("Notice is too long, shorten it and send again"), len, max)).await?;
+        return Ok(());
+    }
+    let (_, updated, total) = recall::correct(&bot, &db, id, &text).await?;
+    bot.send_message(state.chat_id, format!("{}: {} / {}", loc("Notice corrected in the chats"), updated, total)).await?;
+    let state = NoticeState { correcting_id: None, ..state };
+    dialogue.update(state.clone()).await?;
+    report(&bot, &msg, &state, id, &db, &schedule).await
+}
+///
+/// Asks the sender to confirm deleting of the sent notice from every chat
+pub async fn ask_recall(bot: &Bot, msg: &Message, state: &NoticeState, id: u64, db: &Db) -> HandlerResult {
+    let notice = correctable(db, state.chat_id, id).await?;
+    let text = format!("{}: {}?", loc("Delete the notice from every chat"), notice.report.delivered().len());
+    let markup = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("Recall"), format!("/NoticeRecallConfirm/{}", id)),
+        InlineKeyboardButton::callback(loc("⏪Back"), format!("/NoticeReport/{}", id)),
+    ]]);
+    edit_markup_message_or_send(bot, msg, &markup, &text).await
+}
+///
+/// Deletes every delivered copy of the sent notice, confirmed by the sender
+pub async fn recall(bot: &Bot, q: &CallbackQuery, state: &NoticeState, id: u64, db: &Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let Some(msg) = q.regular_message() else {
+        return Ok(());
+    };
+    correctable(db, state.chat_id, id).await?;
+    let (_, deleted, total) = recall::recall(bot, db, id).await?;
+    bot.answer_callback_query(q.id.clone())
+        .text(format!("{}: {} / {}", loc("Notice deleted from the chats"), deleted, total))
+        .await?;
+    report(bot, msg, state, id, db, schedule).await
+}
+///
+/// Returns the sent notice the user can correct or recall: own or any for Moder's and Admin's,
+/// delivered to every recipient and not recalled yet
+async fn correctable(db: &Db, chat_id: ChatId, id: u64) -> Result<SentNotice, Error> {
+    let notice = sent_notices(db, chat_id).await?
+        .into_iter()
+        .find(|notice| notice.id == id)
+        .ok_or_else(|| Error::NotFound(format!("notice.correctable | Sent notice {}", id)))?;
+    match notice.recalled_at.is_none() && notice.expired_at.is_none() && notice.report.is_complete() {
+        true => Ok(notice),
+        false => Err(Error::PermissionDenied(loc("Notice is being delivered, already recalled or expired"))),
+    }
+}
+///
+/// Returns notices sent by the user, or all for Moder's and Admin's, the latest first
+async fn sent_notices(db: &Db, chat_id: ChatId) -> Result<Vec<SentNotice>, Error> {
+    let user = db.user(&chat_id).await?;
+    let all = user.has_role(&[UserRole::Admin, UserRole::Moder]);
+    Ok(db.sent_notices().await?
+        .into_values()
+        .rev()
+        .filter(|notice| all || notice.sender == chat_id)
+        .collect())
+}
+///
+/// Returns to the group selection
+async fn done(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db) -> HandlerResult {
+    let state = NoticeState { prev_state: state.prev_state, chat_id: state.chat_id,  ..Default::default() };
+    dialogue.update(state.clone()).await?;
+    crate::notice::notice::enter(bot, msg, dialogue, state, db).await?;
+    Ok(())
+}
+///
+/// Send / Schedule / Repeat / Edit / Cancel buttons of the notice preview, with the options switches
+fn confirm_markup(options: &NoticeOptions) -> InlineKeyboardMarkup {
+    let ack = match options.requires_ack {
+        true => format!("✅ {}", loc("Requires acknowledgement")),
+        false => loc("Requires acknowledgement"),
+    };
+    let mut options_row = vec![InlineKeyboardButton::callback(ack, "/NoticeRequireAck")];
+    if options.requires_ack {
+        let escalation = options.escalation.map_or(loc("off"), |escalation| escalation.to_string());
+        options_row.push(InlineKeyboardButton::callback(format!("{}: {}", loc("Reminders"), escalation), "/NoticeEscalation"));
+    }
+    let priority_row = Priority::ALL.iter()
+        .map(|priority| {
+            let title = match *priority == options.priority {
+                true => format!("✅ {}", priority.title()),
+                false => priority.title(),
+            };
+            InlineKeyboardButton::callback(title, format!("/NoticePriority/{}", priority.as_str()))
+        })
+        .collect();
+    let mut rsvp_row = vec![InlineKeyboardButton::callback(
+        format!("{}: {}", loc("Answers"), options.rsvp.map_or(loc("off"), |rsvp| rsvp.kind.title())),
+        "/NoticeRsvp",
+    )];
+    if let Some(rsvp) = options.rsvp {
+        let deadline = match rsvp.deadline {
+            Some(_) => format!("✅ {}", loc("Deadline")),
+            None => loc("Deadline"),
+        };
+        rsvp_row.push(InlineKeyboardButton::callback(deadline, "/NoticeRsvpDeadline"));
+    }
+    let ttl_row = vec![InlineKeyboardButton::callback(
+        format!("{}: {}", loc("Expires in"), options.ttl.map_or(loc("off"), |ttl| ttl.to_string())),
+        "/NoticeTtl",
+    )];
+    InlineKeyboardMarkup::new(vec![
+        priority_row,
+        options_row,
+        rsvp_row,
+        ttl_row,
+        vec![
+            InlineKeyboardButton::callback(loc("Send now"), "/NoticeSend"),
+            InlineKeyboardButton::callback(loc("Schedule"), "/NoticeSchedule"),
+            InlineKeyboardButton::callback(loc("Repeat"), "/NoticeRepeat"),
+        ],
+        vec![
+            InlineKeyboardButton::callback(loc("Edit"), "/NoticeEdit"),
+            InlineKeyboardButton::callback(loc("Cancel"), "/NoticeCancel"),
+        ],
+    ])
+}
+///
+/// Menu buttons to select the groups to notice, selected ones are checked
+pub async fn view(bot: &Bot, msg: &Message, state: &NoticeState, user: &User, groups: &Subscriptions, text: impl Into<String>, is_message: Option<()>) -> HandlerResult {
+    let markup = markup(groups, &state.groups, user, is_message).await?;
+    crate::message::edit_markup_message_or_send(bot, msg, &markup, &text.into()).await?;
+    Ok(())
+}
+///
+/// Groups the user is allowed to notice, with Continue / Templates / Reports / History buttons,
+/// Moder's and Admin's also get the button to edit the senders of the groups
+async fn markup(groups: &Subscriptions, selected: &[String], user: &User, is_message: Option<()>) -> Result<InlineKeyboardMarkup, String> {
+    let mut buttons: Vec<InlineKeyboardButton> = match is_message {
+        Some(_) => vec![],
+        None => groups
+            .iter()
+            .filter(|(_, group)| group.can_notice(user))
+            .map(|(group_id, group)| {
+                InlineKeyboardButton::callback(
+                    if selected.contains(group_id) {
+                        format!("✅ {}", group.title)
+                    } else {
+                        group.title.clone()
+                    },
+                    format!("/{}", group_id),
+            )})
+            .collect(),
+    };
+    if is_message.is_none() && !selected.is_empty() {
+        buttons.push(InlineKeyboardButton::callback(loc("Continue"), "/NoticeContinue"));
+    }
+    if is_message.is_some() {
+        buttons.push(InlineKeyboardButton::callback(loc("Templates"), "/NoticeTemplates"));
+    }
+    if is_message.is_none() {
+        buttons.push(InlineKeyboardButton::callback(loc("Reports"), "/NoticeReports"));
+        buttons.push(InlineKeyboardButton::callback(loc("History"), "/NoticeHistory"));
+        if user.has_role(&[UserRole::Admin, UserRole::Moder]) {
+            buttons.push(InlineKeyboardButton::callback(loc("Senders"), "/NoticeSenders"));
+        }
+    }
+    let button_back = InlineKeyboardButton::callback(
+        loc("⏪Back"), // "⏪Back"
+        format!("/back")
+    );
+    buttons.push(button_back);
+    let markup = buttons.into_iter()
+    .fold(InlineKeyboardMarkup::default(), |acc, item| acc.append_row(vec![item]));
+    Ok(markup)
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use teloxide::types::ChatId;
+    use crate::{subscribe::subscription::Subscriptions, user::user_role::UserRole};
+    use super::{NoticeMenu, Priority};
+    ///
+    /// Time zone typed after the rule is split off, the cron fields aren't taken for the time zone
+    #[test]
+    fn split_time_zone() {
+        assert_eq!(super::split_time_zone(" every day at 09:00  Europe/Berlin "), ("every day at 09:00", Some(chrono_tz::Europe::Berlin)));
+        assert_eq!(super::split_time_zone("every day at 09:00"), ("every day at 09:00", None));
+        assert_eq!(super::split_time_zone("0 18 25 * *"), ("0 18 25 * *", None));
+        assert_eq!(super::split_time_zone("Europe/Berlin"), ("Europe/Berlin", None));
+    }
+    ///
+    /// Chats in several selected groups and the group chat being a member get a single copy
+    #[test]
+    fn recipients() {
+        let member = |id: &str, name: &str| format!(
+            r#""{id}": {{"id": "{id}", "name": "{name}", "contact": null, "address": null, "subscriptions": [], "last_seen": "", "role": []}}"#,
+        );
+        let groups: Subscriptions = serde_json::from_str(&format!(
+            r#"{{
+                "TKZ_SPB_GROUP": {{"title": "ТКЗ СПБ", "members": {{{}}}}},
+                "TKZ_OFFICE_GROUP": {{"id": "-4224543713", "title": "Office-group", "members": {{{}, {}, {}}}}},
+                "SA_LAB_GROUP": {{"title": "S&A Lab", "members": {{{}}}}}
+            }}"#,
+            member("-4101721392", "@Office-group-2"),
+            member("-4101721392", "@Office-group-1"), member("-4224543713", "@Office-group"), member("7038555815", "Anton"),
+            member("7038555816", "Boris"),
+        )).unwrap();
+        let report = super::recipients(&groups, &["TKZ_SPB_GROUP".to_owned(), "TKZ_OFFICE_GROUP".to_owned()]);
+        assert_eq!(report.pending(), vec!["-4101721392", "-4224543713", "7038555815"]);
+        assert_eq!(report.recipients["-4101721392"].name, "@Office-group-2");
+        assert_eq!(report.recipients["-4224543713"].name, "Office-group");
+        let report = super::recipients(&groups, &["SA_LAB_GROUP".to_owned(), "UNKNOWN".to_owned()]);
+        assert_eq!(report.pending(), vec!["7038555816"]);
+    }
+    #[test]
+    fn parse() {
+        assert_eq!(NoticeMenu::parse("/NoticeContinue", 0), NoticeMenu::Continue);
+        assert_eq!(NoticeMenu::parse("/NoticeSend", 0), NoticeMenu::Send);
+        assert_eq!(NoticeMenu::parse("/NoticeSchedule", 0), NoticeMenu::Schedule);
+        assert_eq!(NoticeMenu::parse("/NoticeRepeat", 0), NoticeMenu::Repeat);
+        assert_eq!(NoticeMenu::parse("/NoticeEdit", 0), NoticeMenu::Edit);
+        assert_eq!(NoticeMenu::parse("/NoticeCancel", 0), NoticeMenu::Cancel);
+        assert_eq!(NoticeMenu::parse("/NoticeRequireAck", 0), NoticeMenu::RequireAck);
+        assert_eq!(NoticeMenu::parse("/NoticeEscalation", 0), NoticeMenu::Escalation);
+        assert_eq!(NoticeMenu::parse("/NoticeRsvp", 0), NoticeMenu::Rsvp);
+        assert_eq!(NoticeMenu::parse("/NoticeRsvpDeadline", 0), NoticeMenu::RsvpDeadline);
+        assert_eq!(NoticeMenu::parse("/NoticeTtl", 0), NoticeMenu::Ttl);
+        assert_eq!(NoticeMenu::parse("/NoticeTemplates", 0), NoticeMenu::Templates);
+        assert_eq!(NoticeMenu::parse("/NoticeTemplate/3", 0), NoticeMenu::Template(3));
+        assert_eq!(NoticeMenu::parse("/NoticeTemplateAdd", 0), NoticeMenu::TemplateAdd);
+        assert_eq!(NoticeMenu::parse("/NoticeTemplateEdit/3", 0), NoticeMenu::TemplateEdit(3));
+        assert_eq!(NoticeMenu::parse("/NoticeTemplateDelete/3", 0), NoticeMenu::TemplateDelete(3));
+        assert_eq!(NoticeMenu::parse("/NoticeSenders", 0), NoticeMenu::Senders);
+        assert_eq!(NoticeMenu::parse("/NoticeSenders/TKZ_SPB_GROUP", 0), NoticeMenu::SendersGroup("TKZ_SPB_GROUP".to_owned()));
+        assert_eq!(NoticeMenu::parse("/NoticeSenderRole/moder/-100123", 0), NoticeMenu::SenderRole("-100123".to_owned(), UserRole::Moder));
+        assert_eq!(NoticeMenu::parse("/NoticeSenderRole/admin/-100123", 0), NoticeMenu::Unknown("/NoticeSenderRole/admin/-100123".to_owned()));
+        assert_eq!(NoticeMenu::parse("/NoticeSenderUser/7038555815/TKZ_SPB_GROUP", 0), NoticeMenu::SenderUser("TKZ_SPB_GROUP".to_owned(), ChatId(7038555815)));
+        assert_eq!(NoticeMenu::parse("/NoticePriority/urgent", 0), NoticeMenu::Priority(Priority::Urgent));
+        assert_eq!(NoticeMenu::parse("/NoticePriority/loud", 0), NoticeMenu::Unknown("/NoticePriority/loud".to_owned()));
+        assert_eq!(NoticeMenu::parse("/NoticeReports", 0), NoticeMenu::Reports);
+        assert_eq!(NoticeMenu::parse("/NoticeReport/5", 0), NoticeMenu::Report(5));
+        assert_eq!(NoticeMenu::parse("/NoticeGroups", 0), NoticeMenu::Groups);
+        assert_eq!(NoticeMenu::parse("/NoticeHistory", 0), NoticeMenu::History);
+        assert_eq!(NoticeMenu::parse("/NoticeCorrect/3", 0), NoticeMenu::Correct(3));
+        assert_eq!(NoticeMenu::parse("/NoticeRecall/3", 0), NoticeMenu::Recall(3));
+        assert_eq!(NoticeMenu::parse("/NoticeRecallConfirm/3", 0), NoticeMenu::RecallConfirm(3));
+        assert_eq!(NoticeMenu::parse("/back", 0), NoticeMenu::Done);
+        assert_eq!(NoticeMenu::parse("/-100123", 0), NoticeMenu::Group("-100123".to_owned()));
+        assert_eq!(NoticeMenu::parse("text", 0), NoticeMenu::Unknown("text".to_owned()));
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{loc::loc, message::Priority};
+///
+/// Min interval between the reminders
+const MIN_INTERVAL: u64 = 10;
+///
+/// Max number of the reminders
+const MAX_TIMES: u32 = 10;
+///
+/// Options of the notice chosen by the sender in the preview
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoticeOptions {
+    /// Every copy has the Acknowledge button, the sender sees who acknowledged
+    #[serde(default)]
+    pub requires_ack: bool,
+    /// Reminders to the recipients not acknowledged the notice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<Escalation>,
+    /// Sound, header style and pinning of the copies
+    #[serde(default)]
+    pub priority: Priority,
+    /// Yes / No / Maybe answers collected from the recipients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rsvp: Option<Rsvp>,
+    /// Time-to-live of the copies, deleted or marked expired after
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<Ttl>,
+}
+///
+/// Time-to-live of the notice after it's sent, stored as text, like '2h'
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Ttl {
+    /// Interval in minutes
+    pub minutes: u64,
+}
+//
+//
+impl Ttl {
+    ///
+    /// Parses the time-to-live typed by the sender: '30m', '2h', '1d'
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let minutes = parse_interval(&input.trim().to_lowercase())?;
+        if minutes < MIN_INTERVAL {
+            return Err(format!("Ttl.parse | Interval {}m is less than {}m", minutes, MIN_INTERVAL));
+        }
+        Ok(Self { minutes })
+    }
+    ///
+    /// Returns the time-to-live as the duration
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.minutes as i64)
+    }
+}
+//
+//
+impl fmt::Display for Ttl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_interval(self.minutes))
+    }
+}
+//
+//
+impl TryFrom<String> for Ttl {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+//
+//
+impl From<Ttl> for String {
+    fn from(value: Ttl) -> Self {
+        value.to_string()
+    }
+}
+///
+/// Answers collected from the recipients of the notice into one tally
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rsvp {
+    pub kind: RsvpKind,
+    /// Answers aren't accepted after, the sender gets the final summary
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+}
+///
+/// How the recipients answer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RsvpKind {
+    /// Inline buttons attached to the copy
+    Buttons,
+    /// Native Telegram poll sent after the copy
+    Poll,
+}
+//
+//
+impl RsvpKind {
+    ///
+    /// Returns the localized title of the kind
+    pub fn title(&self) -> String {
+        match self {
+            Self::Buttons => loc("buttons"),
+            Self::Poll => loc("poll"),
+        }
+    }
+}
+///
+/// Answer of the recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RsvpAnswer {
+    Yes,
+    No,
+    Maybe,
+}
+//
+//
+impl RsvpAnswer {
+    /// In the order of the poll options
+    pub const ALL: [RsvpAnswer; 3] = [RsvpAnswer::Yes, RsvpAnswer::No, RsvpAnswer::Maybe];
+    ///
+    /// Parses the answer stored in the callback data
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "yes" => Some(Self::Yes),
+            "no" => Some(Self::No),
+            "maybe" => Some(Self::Maybe),
+            _ => None,
+        }
+    }
+    ///
+    /// Returns the answer as stored in the callback data
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Yes => "yes",
+            Self::No => "no",
+            Self::Maybe => "maybe",
+        }
+    }
+    ///
+    /// Returns the localized title of the answer
+    pub fn title(&self) -> String {
+        match self {
+            Self::Yes => loc("Yes"),
+            Self::No => loc("No"),
+            Self::Maybe => loc("Maybe"),
+        }
+    }
+}
+///
+/// Policy of chasing the recipients not acknowledged the notice:
+/// remind them `every` interval up to `times` times, then send the list of them to the Moders of the groups.
+/// Stored as text, like 'every 2h, 3 times'
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Escalation {
+    /// Interval in minutes
+    pub every: u64,
+    pub times: u32,
+}
+//
+//
+impl Escalation {
+    ///
+    /// Parses the policy typed by the sender:
+    /// - 'every 2h, 3 times', 'every 30m, 1 time', 'every 1d 2 times'
+    /// - 'every 4h' - escalated after the first interval, no reminders
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim().to_lowercase();
+        let words: Vec<&str> = input.split(|c: char| c.is_whitespace() || c == ',').filter(|word| !word.is_empty()).collect();
+        let (every, times) = match words.as_slice() {
+            ["every", every] => (*every, 0),
+            ["every", every, times, "time" | "times"] => (*every, times.parse().map_err(|_| format!("Escalation.parse | Wrong times in '{}'", input))?),
+            _ => return Err(format!("Escalation.parse | Wrong policy '{}'", input)),
+        };
+        let every = parse_interval(every)?;
+        if every < MIN_INTERVAL {
+            return Err(format!("Escalation.parse | Interval {}m is less than {}m", every, MIN_INTERVAL));
+        }
+        if times > MAX_TIMES {
+            return Err(format!("Escalation.parse | More than {} reminders", MAX_TIMES));
+        }
+        Ok(Self { every, times })
+    }
+    ///
+    /// Returns the interval between the reminders
+    pub fn interval(&self) -> Duration {
+        Duration::minutes(self.every as i64)
+    }
+}
+//
+//
+impl fmt::Display for Escalation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "every {}", format_interval(self.every))?;
+        match self.times {
+            0 => Ok(()),
+            1 => write!(f, ", 1 time"),
+            times => write!(f, ", {} times", times),
+        }
+    }
+}
+///
+/// Parses the interval like '30m', '2h', '1d' into minutes
+fn parse_interval(input: &str) -> Result<u64, String> {
+    let (value, unit) = input.split_at(input.len() - input.chars().last().map_or(0, |c| c.len_utf8()));
+    let value: u64 = value.parse().map_err(|_| format!("options.parse_interval | Wrong interval '{}'", input))?;
+    match unit {
+        "m" => Ok(value),
+        "h" => Ok(value * 60),
+        "d" => Ok(value * 60 * 24),
+        _ => Err(format!("options.parse_interval | Wrong interval unit '{}', expected m, h or d", input)),
+    }
+}
+///
+/// Formats the interval in minutes in the largest whole unit
+fn format_interval(minutes: u64) -> String {
+    match minutes {
+        minutes if minutes % (60 * 24) == 0 => format!("{}d", minutes / (60 * 24)),
+        minutes if minutes % 60 == 0 => format!("{}h", minutes / 60),
+        minutes => format!("{}m", minutes),
+    }
+}
+//
+//
+impl TryFrom<String> for Escalation {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+//
+//
+impl From<Escalation> for String {
+    fn from(value: Escalation) -> Self {
+        value.to_string()
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use super::{Escalation, Ttl};
+    #[test]
+    fn parse() {
+        assert_eq!(Escalation::parse("every 2h, 3 times"), Ok(Escalation { every: 120, times: 3 }));
+        assert_eq!(Escalation::parse("Every 30m 1 time"), Ok(Escalation { every: 30, times: 1 }));
+        assert_eq!(Escalation::parse("every 1d, 2 times"), Ok(Escalation { every: 1440, times: 2 }));
+        assert_eq!(Escalation::parse("every 4h"), Ok(Escalation { every: 240, times: 0 }));
+        assert!(Escalation::parse("every 5m, 3 times").is_err());
+        assert!(Escalation::parse("every 2x, 3 times").is_err());
+        assert!(Escalation::parse("every 2h, 30 times").is_err());
+        assert!(Escalation::parse("twice").is_err());
+        for policy in ["every 2h, 3 times", "every 90m, 1 time", "every 1d", "every 25h, 2 times"] {
+            assert_eq!(Escalation::parse(policy).unwrap().to_string(), policy);
+        }
+        assert_eq!(Ttl::parse("2h"), Ok(Ttl { minutes: 120 }));
+        assert_eq!(Ttl::parse(" 90M"), Ok(Ttl { minutes: 90 }));
+        assert_eq!(Ttl::parse("1d").map(|ttl| ttl.to_string()), Ok("1d".to_owned()));
+        assert!(Ttl::parse("5m").is_err());
+        assert!(Ttl::parse("soon").is_err());
+    }
+}
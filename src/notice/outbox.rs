@@ -0,0 +1,162 @@
+use std::{collections::HashSet, time::Duration};
+use lazy_static::lazy_static;
+use teloxide::{prelude::*, requests::Request, types::{InlineKeyboardMarkup, MessageId, Recipient}};
+use tokio::sync::{Mutex, Notify};
+use crate::{db::storage::Db, kernel::error::Error, loc::loc, message::Priority, subscribe::subscription::group_titles};
+use super::{
+    ack, delivery::LIMITER, options::{NoticeOptions, RsvpKind}, report::DeliveryStatus, rsvp,
+    sent_notice::{update, PollCopy, SentNotice},
+};
+///
+/// Interval of checking the outbox, if no notice is queued meanwhile
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+///
+/// Number of the recipients the delivery state is stored for at once
+const BATCH: usize = 10;
+lazy_static! {
+    ///
+    /// Wakes the outbox worker up when the notice is queued
+    static ref QUEUED: Notify = Notify::new();
+    ///
+    /// Ids of the notices being delivered
+    static ref IN_PROGRESS: Mutex<HashSet<u64>> = Mutex::new(HashSet::new());
+}
+///
+/// Wakes the outbox worker up to deliver just queued notice
+pub fn wake() {
+    QUEUED.notify_one();
+}
+///
+/// Background task delivering the sent notices from the outbox,
+/// started from `main` beside the dispatcher. Deliveries interrupted by the restart are failed,
+/// pending ones are resumed
+pub async fn run(bot: Bot, db: Db) {
+    log::info!("outbox.run | Started, poll interval: {:?}", POLL_INTERVAL);
+    if let Err(err) = fail_interrupted(&db).await {
+        log::warn!("outbox.run | Error: {}", err);
+    }
+    loop {
+        match db.sent_notices().await {
+            Ok(notices) => {
+                for notice in notices.into_values().filter(|notice| !notice.report.pending().is_empty()) {
+                    if IN_PROGRESS.lock().await.insert(notice.id) {
+                        let (bot, db) = (bot.clone(), db.clone());
+                        tokio::spawn(async move {
+                            if let Err(err) = deliver(&bot, &db, notice.id).await {
+                                log::warn!("outbox.run | Notice {} error: {}", notice.id, err);
+                            }
+                            IN_PROGRESS.lock().await.remove(&notice.id);
+                        });
+                    }
+                }
+            }
+            Err(err) => log::warn!("outbox.run | Error: {}", err),
+        }
+        tokio::select! {
+            _ = QUEUED.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+///
+/// Marks the deliveries interrupted by the restart as failed
+async fn fail_interrupted(db: &Db) -> Result<(), Error> {
+    let interrupted = db.sent_notices().await?.into_values()
+        .filter(|notice| notice.report.recipients.values().any(|delivery| delivery.status == DeliveryStatus::Sending));
+    for notice in interrupted {
+        update(db, notice.id, |notice| {
+            if notice.report.fail_interrupted() {
+                log::warn!("outbox.fail_interrupted | Notice {} delivery was interrupted", notice.id);
+            }
+        }).await?;
+    }
+    Ok(())
+}
+///
+/// Delivers the notice to every pending recipient by batches of `BATCH`, the state of the batch is stored
+/// before and after sending, so the delivery is resumed after the restart, the recipients of the interrupted batch are failed.
+/// The sender removed from the users is shown by the chat id.
+/// The urgent notice is pinned in the group chats, the poll is sent after the copy of the notice with the answers by poll.
+/// The report summary is sent to the sender when no pending recipients left
+pub async fn deliver(bot: &Bot, db: &Db, id: u64) -> Result<SentNotice, Error> {
+    let mut notice = db.sent_notices().await?
+        .shift_remove(&id.to_string())
+        .ok_or_else(|| Error::NotFound(format!("outbox.deliver | Sent notice {}", id)))?;
+    let sender = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+    let groups = db.subscriptions().await?;
+    let group_title = group_titles(&groups, &notice.groups);
+    log::debug!("outbox.deliver | Delivering notice {} from '{}' ({}) to the '{}'", id, sender, notice.sender, group_title);
+    let markup = markup(id, &notice.options);
+    let is_poll = notice.options.rsvp.is_some_and(|rsvp| rsvp.kind == RsvpKind::Poll);
+    let pending = notice.report.pending();
+    for batch in pending.chunks(BATCH) {
+        update(db, id, |notice| batch.iter().for_each(|chat_id| notice.report.set(chat_id, DeliveryStatus::Sending))).await?;
+        let mut statuses = vec![];
+        for chat_id in batch {
+            let mut result = notice.content.send(bot, recipient(chat_id), &sender, markup.as_ref(), notice.options.priority).await;
+            if let (Ok(message_ids), true) = (&mut result, is_poll) {
+                match rsvp::send_poll(bot, chat_id).await {
+                    Ok((poll_id, message_id)) => {
+                        message_ids.push(message_id);
+                        // Stored at once, the answers to the poll can come right away
+                        update(db, id, |notice| { notice.polls.insert(poll_id, PollCopy { chat_id: chat_id.clone(), message_id }); }).await?;
+                    }
+                    Err(err) => log::warn!("outbox.deliver | Error sending poll of the notice {} to the {}: {}", id, chat_id, err),
+                }
+            }
+            match &result {
+                Ok(message_ids) => if notice.options.priority == Priority::Urgent {
+                    if let Some(&message_id) = message_ids.first() {
+                        pin(bot, chat_id, message_id).await;
+                    }
+                }
+                Err(err) => log::warn!("outbox.deliver | Error sending notice {} to the {}: {:#?}", id, chat_id, err),
+            }
+            statuses.push((chat_id, DeliveryStatus::from_result(result)));
+        }
+        notice = update(db, id, |notice| statuses.into_iter().for_each(|(chat_id, status)| notice.report.set(chat_id, status))).await?;
+    }
+    let text = format!("{} '{}'\n{}", loc("Notice delivered to the group"), group_title, notice.report.summary());
+    if let Err(err) = LIMITER.deliver(&notice.sender.into(), || bot.send_message(notice.sender, text.clone()).send()).await {
+        log::warn!("outbox.deliver | Error sending report of the notice {} to the '{}' ({}): {:#?}", id, sender, notice.sender, err);
+    }
+    if notice.options.rsvp.is_some() && notice.rsvp_view.is_none() {
+        if let Err(err) = rsvp::send_view(bot, db, &notice).await {
+            log::warn!("outbox.deliver | Error sending answers of the notice {} to the '{}' ({}): {}", id, sender, notice.sender, err);
+        }
+    }
+    Ok(notice)
+}
+///
+/// Returns the buttons attached to every copy of the notice: Acknowledge and the answers by buttons
+pub fn markup(id: u64, options: &NoticeOptions) -> Option<InlineKeyboardMarkup> {
+    let mut rows = vec![];
+    if options.requires_ack {
+        rows.extend(ack::markup(id).inline_keyboard);
+    }
+    if options.rsvp.is_some_and(|rsvp| rsvp.kind == RsvpKind::Buttons) {
+        rows.push(rsvp::buttons(id));
+    }
+    (!rows.is_empty()).then(|| InlineKeyboardMarkup::new(rows))
+}
+///
+/// Pins the message in the group chat, the chat the bot has no rights to pin in is skipped
+async fn pin(bot: &Bot, chat_id: &str, message_id: MessageId) {
+    let chat = recipient(chat_id);
+    if let Recipient::Id(id) = &chat {
+        if id.is_user() {
+            return;
+        }
+    }
+    if let Err(err) = LIMITER.deliver(&chat, || bot.pin_chat_message(chat.clone(), message_id).send()).await {
+        log::info!("outbox.pin | Message {} isn't pinned in the {}: {}", message_id, chat_id, err);
+    }
+}
+///
+/// Returns the recipient by the chat id stored in the report
+pub fn recipient(chat_id: &str) -> Recipient {
+    match chat_id.parse() {
+        Ok(id) => Recipient::Id(ChatId(id)),
+        Err(_) => Recipient::ChannelUsername(chat_id.to_owned()),
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::Utc;
+use teloxide::prelude::*;
+use crate::{db::storage::Db, kernel::error::Error};
+use super::{content::NoticeContent, outbox::{self, recipient}, sent_notice::{update, SentNotice}};
+///
+/// Replaces the text or caption of every delivered copy of the sent notice with `text` (HTML),
+/// stores the corrected content. Returns the notice, the number of the copies updated and the total number of copies
+pub async fn correct(bot: &Bot, db: &Db, id: u64, text: &str) -> Result<(SentNotice, usize, usize), Error> {
+    let notice = load(db, id).await?;
+    let content = notice.content.corrected(text)
+        .ok_or_else(|| Error::NotFound(format!("recall.correct | Text of the sent notice {}", id)))?;
+    let header = db.user(&notice.sender).await.map_or(notice.sender.to_string(), |user| user.name);
+    let markup = outbox::markup(id, &notice.options);
+    let copies = notice.report.delivered();
+    let mut updated = 0;
+    for (chat_id, message_ids) in &copies {
+        match content.edit(bot, recipient(chat_id), message_ids, &header, markup.as_ref(), notice.options.priority).await {
+            Ok(_) => updated += 1,
+            Err(err) => log::warn!("recall.correct | Error editing notice {} in the {}: {}", id, chat_id, err),
+        }
+    }
+    let notice = update(db, id, |notice| {
+        notice.content = content;
+        notice.edited_at = Some(Utc::now());
+    }).await?;
+    log::info!("recall.correct | Notice {} corrected in {} of {} chats", id, updated, copies.len());
+    Ok((notice, updated, copies.len()))
+}
+///
+/// Deletes every delivered copy of the sent notice and marks it recalled.
+/// Returns the notice, the number of the copies deleted and the total number of copies
+pub async fn recall(bot: &Bot, db: &Db, id: u64) -> Result<(SentNotice, usize, usize), Error> {
+    let notice = load(db, id).await?;
+    let copies = notice.report.delivered();
+    let mut deleted = 0;
+    for (chat_id, message_ids) in &copies {
+        match NoticeContent::delete(bot, recipient(chat_id), message_ids).await {
+            Ok(_) => deleted += 1,
+            Err(err) => log::warn!("recall.recall | Error deleting notice {} in the {}: {}", id, chat_id, err),
+        }
+    }
+    let notice = update(db, id, |notice| notice.recalled_at = Some(Utc::now())).await?;
+    log::info!("recall.recall | Notice {} recalled from {} of {} chats", id, deleted, copies.len());
+    Ok((notice, deleted, copies.len()))
+}
+///
+///
+async fn load(db: &Db, id: u64) -> Result<SentNotice, Error> {
+    db.sent_notices().await?
+        .shift_remove(&id.to_string())
+        .ok_or_else(|| Error::NotFound(format!("recall.load | Sent notice {}", id)))
+}
@@ -0,0 +1,300 @@
+use std::fmt;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+///
+/// How far the next occurrence is searched, covers the cron rules firing on Feb 29 only
+const MAX_DAYS: u32 = 366 * 4 + 1;
+///
+/// Rule of the recurring notice, stored and typed by the user as text:
+/// - 'every day at 09:00'
+/// - 'every 14 days at 18:00 from 2024-05-20' - without `from` days are counted from 1970-01-01
+/// - 'every mon,fri at 09:00', 'every mon-fri at 09:00'
+/// - '0 9 25 * *' - cron: minute hour day-of-month month day-of-week
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Days { every: u32, from: Option<NaiveDate>, at: NaiveTime },
+    Weekdays { days: Vec<Weekday>, at: NaiveTime },
+    Cron(Cron),
+}
+//
+//
+impl Recurrence {
+    ///
+    /// Parses the rule, see `Recurrence` for the formats
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim().to_lowercase();
+        let Some(rule) = input.strip_prefix("every ") else {
+            return Cron::parse(&input).map(Self::Cron);
+        };
+        let (days, rest) = rule.split_once(" at ")
+            .ok_or_else(|| format!("Recurrence.parse | 'at HH:MM' expected in '{}'", input))?;
+        let (at, from) = match rest.split_once(" from ") {
+            Some((at, from)) => {
+                let from = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d")
+                    .map_err(|err| format!("Recurrence.parse | Wrong date '{}': {}", from, err))?;
+                (at, Some(from))
+            }
+            None => (rest, None),
+        };
+        let at = NaiveTime::parse_from_str(at.trim(), "%H:%M")
+            .map_err(|err| format!("Recurrence.parse | Wrong time '{}': {}", at, err))?;
+        let days = days.trim();
+        if days == "day" {
+            return Ok(Self::Days { every: 1, from, at });
+        }
+        if let Some(every) = days.strip_suffix(" days") {
+            let every: u32 = every.trim().parse()
+                .map_err(|err| format!("Recurrence.parse | Wrong number of days '{}': {}", every, err))?;
+            if every == 0 {
+                return Err(format!("Recurrence.parse | Number of days must be positive in '{}'", input));
+            }
+            return Ok(Self::Days { every, from, at });
+        }
+        if from.is_some() {
+            return Err(format!("Recurrence.parse | 'from' is allowed for days only in '{}'", input));
+        }
+        Ok(Self::Weekdays { days: weekdays(days)?, at })
+    }
+    ///
+    /// Returns the rule with `from` set to the `date`, if the rule counts days and `from` is missing
+    pub fn anchored(self, date: NaiveDate) -> Self {
+        match self {
+            Self::Days { every, from: None, at } => Self::Days { every, from: Some(date), at },
+            rule => rule,
+        }
+    }
+    ///
+    /// Returns the first time of the rule later than `after`, or None if the rule never fires
+    pub fn next_after(&self, after: DateTime<Utc>, time_zone: Tz) -> Option<DateTime<Utc>> {
+        let mut date = after.with_timezone(&time_zone).date_naive();
+        for _ in 0..MAX_DAYS {
+            for time in self.times(date) {
+                if let Some(at) = time_zone.from_local_datetime(&date.and_time(time)).earliest() {
+                    let at = at.with_timezone(&Utc);
+                    if at > after {
+                        return Some(at);
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+    ///
+    /// Returns the times the rule fires at on the `date`, ascending
+    fn times(&self, date: NaiveDate) -> Vec<NaiveTime> {
+        match self {
+            Self::Days { every, from, at } => {
+                let from = from.unwrap_or(NaiveDate::default());
+                let days = (date - from).num_days();
+                match days >= 0 && days % (*every as i64) == 0 {
+                    true => vec![*at],
+                    false => vec![],
+                }
+            }
+            Self::Weekdays { days, at } => match days.contains(&date.weekday()) {
+                true => vec![*at],
+                false => vec![],
+            },
+            Self::Cron(cron) => cron.times(date),
+        }
+    }
+}
+//
+//
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Days { every, from, at } => {
+                match every {
+                    1 => write!(f, "every day at {}", at.format("%H:%M"))?,
+                    _ => write!(f, "every {} days at {}", every, at.format("%H:%M"))?,
+                }
+                match from {
+                    Some(from) => write!(f, " from {}", from.format("%Y-%m-%d")),
+                    None => Ok(()),
+                }
+            }
+            Self::Weekdays { days, at } => {
+                let days: Vec<String> = days.iter().map(|day| day.to_string().to_lowercase()).collect();
+                write!(f, "every {} at {}", days.join(","), at.format("%H:%M"))
+            }
+            Self::Cron(cron) => write!(f, "{}", cron.source),
+        }
+    }
+}
+//
+//
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+//
+//
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+///
+/// Parses list of the weekdays, like 'mon,wed', 'mon-fri'
+fn weekdays(input: &str) -> Result<Vec<Weekday>, String> {
+    let mut days = vec![];
+    for item in input.split(',') {
+        let parse = |day: &str| day.trim().parse::<Weekday>()
+            .map_err(|_| format!("Recurrence.weekdays | Wrong day '{}'", day));
+        match item.split_once('-') {
+            Some((first, last)) => {
+                let (mut day, last) = (parse(first)?, parse(last)?);
+                days.push(day);
+                while day != last {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse(item)?),
+        }
+    }
+    days.sort_by_key(|day| day.num_days_from_monday());
+    days.dedup();
+    Ok(days)
+}
+///
+/// Cron expression: minute hour day-of-month month day-of-week,
+/// fields are '*', numbers, ranges 'a-b', steps '*/n', 'a-b/n' and lists of them,
+/// day-of-week 0 and 7 are Sunday; if both day fields are restricted, either of them matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+//
+//
+impl Cron {
+    ///
+    ///
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = input.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!("Cron.parse | 5 fields expected in '{}'", input));
+        };
+        let mut weekdays_mask = field(weekdays, 0, 7)?;
+        if weekdays_mask & (1 << 7) != 0 {
+            weekdays_mask = (weekdays_mask | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: field(minutes, 0, 59)?,
+            hours: field(hours, 0, 23)?,
+            days: field(days, 1, 31)?,
+            months: field(months, 1, 12)?,
+            weekdays: weekdays_mask,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+    ///
+    /// Returns the times the expression fires at on the `date`, ascending
+    fn times(&self, date: NaiveDate) -> Vec<NaiveTime> {
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        let day = match self.any_day || self.any_weekday {
+            true => day && weekday,
+            false => day || weekday,
+        };
+        if !(day && bit(self.months, date.month())) {
+            return vec![];
+        }
+        (0..24).filter(|hour| bit(self.hours, *hour))
+            .flat_map(|hour| (0..60).filter(|minute| bit(self.minutes, *minute)).map(move |minute| (hour, minute)))
+            .filter_map(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0))
+            .collect()
+    }
+}
+///
+/// Returns the mask of the values of the cron field
+fn field(input: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0;
+    for item in input.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("Cron.field | Wrong step in '{}'", item))?;
+                if step == 0 {
+                    return Err(format!("Cron.field | Step must be positive in '{}'", item));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let value = |value: &str| match value.parse::<u32>() {
+            Ok(value) if (min..=max).contains(&value) => Ok(value),
+            _ => Err(format!("Cron.field | Value '{}' out of {}-{} in '{}'", value, min, max, item)),
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((first, last)) => (value(first)?, value(last)?),
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if first > last {
+            return Err(format!("Cron.field | Wrong range '{}'", item));
+        }
+        for value in (first..=last).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+///
+///
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use super::Recurrence;
+    #[test]
+    fn parse() {
+        for rule in ["every day at 09:00", "every 14 days at 18:30 from 2024-05-20", "every mon,fri at 09:00", "0 9 25 * *", "*/15 8-18 * * 1-5"] {
+            assert_eq!(Recurrence::parse(rule).map(|rule| rule.to_string()), Ok(rule.to_owned()));
+        }
+        assert_eq!(Recurrence::parse("Every Mon-Wed at 09:00").map(|rule| rule.to_string()), Ok("every mon,tue,wed at 09:00".to_owned()));
+        assert_eq!(Recurrence::parse("every 3 days at 09:00").map(|rule| rule.anchored(NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()).to_string()), Ok("every 3 days at 09:00 from 2024-05-20".to_owned()));
+        for rule in ["every 0 days at 09:00", "every day", "every holiday at 09:00", "every mon at 25:00", "0 9 * *", "60 9 * * *", "0 9 * * 8", "0 9 */0 * *"] {
+            assert!(Recurrence::parse(rule).is_err(), "{}", rule);
+        }
+    }
+    #[test]
+    fn next_after() {
+        let tz = chrono_tz::Europe::Moscow;
+        // Monday 2024-05-20 10:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2024, 5, 20, 7, 0, 0).unwrap();
+        let next = |rule: &str| Recurrence::parse(rule).unwrap().next_after(now, tz);
+        assert_eq!(next("every day at 09:00"), Some(Utc.with_ymd_and_hms(2024, 5, 21, 6, 0, 0).unwrap()));
+        assert_eq!(next("every day at 10:30"), Some(Utc.with_ymd_and_hms(2024, 5, 20, 7, 30, 0).unwrap()));
+        assert_eq!(next("every 14 days at 09:00 from 2024-05-13"), Some(Utc.with_ymd_and_hms(2024, 5, 27, 6, 0, 0).unwrap()));
+        assert_eq!(next("every 2 days at 09:00 from 2024-06-01"), Some(Utc.with_ymd_and_hms(2024, 6, 1, 6, 0, 0).unwrap()));
+        assert_eq!(next("every mon,fri at 09:00"), Some(Utc.with_ymd_and_hms(2024, 5, 24, 6, 0, 0).unwrap()));
+        assert_eq!(next("0 18 25 * *"), Some(Utc.with_ymd_and_hms(2024, 5, 25, 15, 0, 0).unwrap()));
+        assert_eq!(next("*/20 10 * * 1"), Some(Utc.with_ymd_and_hms(2024, 5, 20, 7, 20, 0).unwrap()));
+        // both day fields restricted - either matches, 2024-05-24 is Friday
+        assert_eq!(next("0 9 31 * 5"), Some(Utc.with_ymd_and_hms(2024, 5, 24, 6, 0, 0).unwrap()));
+        assert_eq!(next("0 9 29 2 *"), Some(Utc.with_ymd_and_hms(2028, 2, 29, 6, 0, 0).unwrap()));
+        assert_eq!(next("0 9 30 2 *"), None);
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
+use crate::{
+    config::ScheduleConfig, db::storage::Db, kernel::error::{Error, HandlerResult}, loc::{loc, LocaleTag},
+    message::edit_markup_message_or_send, states::{MainState, MyDialogue}, subscribe::subscription::group_titles,
+    user::user_role::UserRole,
+};
+use super::{notice::{self, NoticeState}, recurring_notice::RecurringNotice, scheduled_notice::format_time};
+///
+/// Recurring notices menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecurringMenu {
+   List,            // List of the recurring notices
+   Notice(u64),     // Selected notice
+   Rule(u64),       // Change the rule of the notice
+   Delete(u64),     // Remove the notice
+   Unknown(String), // Unknown command received
+   Done,            // Exit menu
+}
+//
+//
+impl RecurringMenu {
+    pub fn parse(s: &str, _loc_tag: LocaleTag) -> Self {
+        let input = s.strip_prefix('/').map_or_else(|| ("", s), |input| ("/", input));
+        match input {
+            ("/", "done" | "Done") => Self::Done,
+            ("/", "back" | "Back") => Self::Done,
+            ("/", "exit" | "Exit") => Self::Done,
+            ("/", "Recurring") => Self::List,
+            ("/", input) => {
+                let mut input = input.split('/');
+                match (input.next(), input.next().map(|id| id.parse()), input.next()) {
+                    (Some("Recurring"), Some(Ok(id)), None) => Self::Notice(id),
+                    (Some("Recurring"), Some(Ok(id)), Some("Rule")) => Self::Rule(id),
+                    (Some("Recurring"), Some(Ok(id)), Some("Delete")) => Self::Delete(id),
+                    _ => Self::Unknown(s.to_owned()),
+                }
+            }
+            (_, _) => Self::Unknown(s.to_owned()),
+        }
+    }
+}
+///
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringState {
+    pub prev_state: MainState,  // Where to go on Back btn
+    pub chat_id: ChatId,        // User id managing the notices
+}
+///
+/// Shows recurring notices of the user, Moder's and Admin's see all notices
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: RecurringState, db: Db) -> HandlerResult {
+    log::debug!("recurring.enter | state: {:#?}", state);
+    dialogue.update(state.clone()).await?;
+    let notices = notices(&db, state.chat_id).await?;
+    let groups = db.subscriptions().await?;
+    let text = match notices.is_empty() {
+        true => loc("No recurring notices"),
+        false => loc("Recurring notices"),
+    };
+    let mut buttons: Vec<InlineKeyboardButton> = notices.iter()
+        .map(|notice| {
+            let group = group_titles(&groups, &notice.groups);
+            InlineKeyboardButton::callback(
+                format!("{} | {}", notice.rule, group),
+                format!("/Recurring/{}", notice.id),
+            )
+        })
+        .collect();
+    buttons.push(InlineKeyboardButton::callback(loc("⏪Back"), "/back"));
+    let markup = buttons.into_iter()
+        .fold(InlineKeyboardMarkup::default(), |acc, item| acc.append_row(vec![item]));
+    edit_markup_message_or_send(&bot, &msg, &markup, &text).await
+}
+///
+/// Handles the commands of the Recurring menu
+pub async fn select(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: RecurringState, cmd: RecurringMenu, db: Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let msg = match q.regular_message() {
+        Some(msg) => msg.to_owned(),
+        None => return Ok(()),
+    };
+    match cmd {
+        RecurringMenu::Notice(id) => {
+            let recurring = notice(&db, state.chat_id, id).await?;
+            let sender = db.user(&recurring.sender).await?;
+            let group = group_titles(&db.subscriptions().await?, &recurring.groups);
+            recurring.content.send(&bot, state.chat_id, &sender.name, None, recurring.options.priority).await?;
+            let time_zone = recurring.time_zone(schedule.time_zone);
+            let next = recurring.next(Utc::now(), schedule.time_zone)
+                .map_or("-".to_owned(), |next| format_time(next, time_zone));
+            let text = format!(
+                "{} '{}' ({})\n{} '{}'\n{} {}",
+                loc("Notice above is repeated"), recurring.rule, time_zone, loc("to the group"), group, loc("Next at"), next,
+            );
+            let markup = InlineKeyboardMarkup::new(vec![
+                vec![
+                    InlineKeyboardButton::callback(loc("Change rule"), format!("/Recurring/{}/Rule", id)),
+                    InlineKeyboardButton::callback(loc("Delete notice"), format!("/Recurring/{}/Delete", id)),
+                ],
+                vec![
+                    InlineKeyboardButton::callback(loc("⏪Back"), "/Recurring"),
+                ],
+            ]);
+            bot.send_message(state.chat_id, text)
+                .reply_markup(markup)
+                .await?;
+            Ok(())
+        }
+        RecurringMenu::Rule(id) => {
+            let recurring = notice(&db, state.chat_id, id).await?;
+            let state = NoticeState {
+                prev_state: state.prev_state,
+                groups: recurring.groups,
+                groups_selected: true,
+                chat_id: state.chat_id,
+                content: Some(recurring.content),
+                options: recurring.options,
+                recurring_id: Some(id),
+                ..Default::default()
+            };
+            notice::ask_rule(&bot, &msg, dialogue, state, schedule).await
+        }
+        RecurringMenu::Delete(id) => {
+            notice(&db, state.chat_id, id).await?;
+            db.remove_recurring_notice(id).await?;
+            log::info!("recurring.select | Notice {} deleted by {}", id, state.chat_id);
+            bot.answer_callback_query(q.id).text(loc("Recurring notice deleted")).await?;
+            enter(bot, msg, dialogue, state, db).await
+        }
+        RecurringMenu::List | RecurringMenu::Unknown(_) => enter(bot, msg, dialogue, state, db).await,
+        RecurringMenu::Done => crate::states::reload(bot, &msg, dialogue, state.prev_state, &db).await,
+    }
+}
+///
+/// Returns notices available to the user
+async fn notices(db: &Db, chat_id: ChatId) -> Result<Vec<RecurringNotice>, Error> {
+    let user = db.user(&chat_id).await?;
+    let all = user.has_role(&[UserRole::Admin, UserRole::Moder]);
+    Ok(db.recurring_notices().await?
+        .into_values()
+        .filter(|notice| all || notice.sender == chat_id)
+        .collect())
+}
+///
+/// Returns the notice if it is available to the user
+async fn notice(db: &Db, chat_id: ChatId, id: u64) -> Result<RecurringNotice, Error> {
+    notices(db, chat_id).await?
+        .into_iter()
+        .find(|notice| notice.id == id)
+        .ok_or_else(|| Error::NotFound(format!("recurring.notice | Recurring notice {}", id)))
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use super::RecurringMenu;
+    #[test]
+    fn parse() {
+        assert_eq!(RecurringMenu::parse("/Recurring", 0), RecurringMenu::List);
+        assert_eq!(RecurringMenu::parse("/Recurring/3", 0), RecurringMenu::Notice(3));
+        assert_eq!(RecurringMenu::parse("/Recurring/3/Rule", 0), RecurringMenu::Rule(3));
+        assert_eq!(RecurringMenu::parse("/Recurring/3/Delete", 0), RecurringMenu::Delete(3));
+        assert_eq!(RecurringMenu::parse("/back", 0), RecurringMenu::Done);
+        assert_eq!(RecurringMenu::parse("/Recurring/x", 0), RecurringMenu::Unknown("/Recurring/x".to_owned()));
+    }
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use teloxide::types::ChatId;
+use super::{content::NoticeContent, options::NoticeOptions, recurrence::Recurrence, sent_notice::deserialize_groups};
+///
+/// Recurring notices by id
+pub type RecurringNotices = IndexMap<String, RecurringNotice>;
+///
+/// Notice to be sent to the group repeatedly by the rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringNotice {
+    /// Assigned by the storage, 0 for the new notice
+    pub id: u64,
+    /// Chat id of the user created the notice
+    pub sender: ChatId,
+    /// Group ids to be noticed, the single group id of the notice stored before is accepted
+    #[serde(alias = "group", deserialize_with = "deserialize_groups")]
+    pub groups: Vec<String>,
+    pub content: NoticeContent,
+    pub rule: Recurrence,
+    /// Time zone of the rule, the `schedule` config one if missing
+    #[serde(default)]
+    pub time_zone: Option<Tz>,
+    /// Time the notice was sent last time, or created at,
+    /// the notice added to the file without it starts from the bot start
+    #[serde(default)]
+    pub last_sent: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub options: NoticeOptions,
+}
+//
+//
+impl RecurringNotice {
+    ///
+    /// Returns the time zone of the rule
+    pub fn time_zone(&self, default: Tz) -> Tz {
+        self.time_zone.unwrap_or(default)
+    }
+    ///
+    /// Returns the next time to send the notice at, or None if the rule never fires
+    pub fn next(&self, now: DateTime<Utc>, default_tz: Tz) -> Option<DateTime<Utc>> {
+        self.rule.next_after(self.last_sent.unwrap_or(now), self.time_zone(default_tz))
+    }
+}
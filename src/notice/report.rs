@@ -0,0 +1,190 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use teloxide::{types::MessageId, ApiError, RequestError};
+use crate::{kernel::error::Error, loc::loc};
+///
+/// Max number of the failed recipients listed in the summary, to fit into the Telegram message
+const MAX_LISTED: usize = 30;
+///
+/// Result of the delivery to the single recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    /// Waiting in the outbox
+    Pending,
+    /// Request is being sent, if left after the restart the result is unknown
+    Sending,
+    /// Messages sent to the recipient chat
+    Delivered(Vec<MessageId>),
+    /// Recipient blocked the bot, left, was deactivated or never started the bot
+    Blocked(String),
+    /// Any other error
+    Failed(String),
+}
+//
+//
+impl DeliveryStatus {
+    ///
+    /// Returns status of the delivery by the result of sending
+    pub fn from_result(result: Result<Vec<MessageId>, Error>) -> Self {
+        match result {
+            Ok(message_ids) => Self::Delivered(message_ids),
+            Err(Error::Telegram(RequestError::Api(
+                err @ (ApiError::BotBlocked | ApiError::BotKicked | ApiError::BotKickedFromSupergroup
+                | ApiError::UserDeactivated | ApiError::CantInitiateConversation | ApiError::ChatNotFound)
+            ))) => Self::Blocked(err.to_string()),
+            Err(Error::Telegram(RequestError::Api(err))) => Self::Failed(err.to_string()),
+            Err(err) => Self::Failed(err.to_string()),
+        }
+    }
+}
+///
+/// Delivery to the single recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Name of the member or title of the group chat
+    pub name: String,
+    pub status: DeliveryStatus,
+}
+///
+/// Per-recipient results of the notice broadcast, by recipient chat id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryReport {
+    pub recipients: IndexMap<String, Delivery>,
+}
+//
+//
+impl DeliveryReport {
+    ///
+    /// Adds the recipient waiting for the delivery, the recipient already added is kept as is
+    pub fn enqueue(&mut self, chat_id: impl ToString, name: impl Into<String>) {
+        self.recipients.entry(chat_id.to_string())
+            .or_insert_with(|| Delivery { name: name.into(), status: DeliveryStatus::Pending });
+    }
+    ///
+    /// Stores the status of the delivery to the recipient
+    pub fn set(&mut self, chat_id: &str, status: DeliveryStatus) {
+        if let Some(delivery) = self.recipients.get_mut(chat_id) {
+            delivery.status = status;
+        }
+    }
+    ///
+    /// Returns chat ids of the recipients waiting for the delivery
+    pub fn pending(&self) -> Vec<String> {
+        self.recipients.iter()
+            .filter(|(_, delivery)| delivery.status == DeliveryStatus::Pending)
+            .map(|(chat_id, _)| chat_id.clone())
+            .collect()
+    }
+    ///
+    /// Returns true if no recipient is waiting for the delivery or being sent
+    pub fn is_complete(&self) -> bool {
+        self.recipients.values().all(|delivery| !matches!(delivery.status, DeliveryStatus::Pending | DeliveryStatus::Sending))
+    }
+    ///
+    /// Returns chat ids of the recipients received the notice, with ids of the delivered messages
+    pub fn delivered(&self) -> Vec<(String, Vec<MessageId>)> {
+        self.recipients.iter()
+            .filter_map(|(chat_id, delivery)| match &delivery.status {
+                DeliveryStatus::Delivered(message_ids) => Some((chat_id.clone(), message_ids.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+    ///
+    /// Marks deliveries interrupted by the restart as failed, they aren't repeated,
+    /// so the recipient never gets the notice twice. Returns true if any is found
+    pub fn fail_interrupted(&mut self) -> bool {
+        let mut found = false;
+        for delivery in self.recipients.values_mut().filter(|delivery| delivery.status == DeliveryStatus::Sending) {
+            delivery.status = DeliveryStatus::Failed("Delivery interrupted by the restart".to_owned());
+            found = true;
+        }
+        found
+    }
+    ///
+    /// Returns the number of the delivered, blocked and failed recipients
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.recipients.values().fold((0, 0, 0), |(delivered, blocked, failed), delivery| match delivery.status {
+            DeliveryStatus::Delivered(_) => (delivered + 1, blocked, failed),
+            DeliveryStatus::Blocked(_) => (delivered, blocked + 1, failed),
+            DeliveryStatus::Failed(_) => (delivered, blocked, failed + 1),
+            DeliveryStatus::Pending | DeliveryStatus::Sending => (delivered, blocked, failed),
+        })
+    }
+    ///
+    /// Returns the counts and the list of recipients not received the notice, with the reason
+    pub fn summary(&self) -> String {
+        let (delivered, blocked, failed) = self.counts();
+        let mut text = format!("{}: {}, {}: {}, {}: {}", loc("Delivered"), delivered, loc("failed"), failed, loc("blocked"), blocked);
+        let in_progress = self.recipients.len() - delivered - failed - blocked;
+        if in_progress > 0 {
+            text.push_str(&format!(", {}: {}", loc("pending"), in_progress));
+        }
+        let undelivered: Vec<String> = self.recipients.iter()
+            .filter_map(|(chat_id, delivery)| match &delivery.status {
+                DeliveryStatus::Delivered(_) | DeliveryStatus::Pending | DeliveryStatus::Sending => None,
+                DeliveryStatus::Blocked(reason) | DeliveryStatus::Failed(reason) => Some(format!("- {} ({}): {}", delivery.name, chat_id, reason)),
+            })
+            .collect();
+        for line in undelivered.iter().take(MAX_LISTED) {
+            text.push('\n');
+            text.push_str(line);
+        }
+        if undelivered.len() > MAX_LISTED {
+            text.push_str(&format!("\n... {} {}", undelivered.len() - MAX_LISTED, loc("more")));
+        }
+        text
+    }
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use teloxide::{types::MessageId, ApiError, RequestError};
+    use crate::{kernel::error::Error, loc::loc};
+    use super::{DeliveryReport, DeliveryStatus};
+    #[test]
+    fn summary() {
+        let mut report = DeliveryReport::default();
+        let results = [
+            (-100123, "Office-group", Ok(vec![MessageId(1)])),
+            (11, "Anton", Ok(vec![MessageId(2), MessageId(3)])),
+            (12, "Boris", Err(Error::Telegram(RequestError::Api(ApiError::BotBlocked)))),
+            (13, "Vera", Err(Error::Telegram(RequestError::Api(ApiError::Unknown("Bad Request: message is too long".to_owned()))))),
+        ];
+        for (chat_id, name, result) in results {
+            report.enqueue(chat_id, name);
+            report.set(&chat_id.to_string(), DeliveryStatus::from_result(result));
+        }
+        assert_eq!(report.counts(), (2, 1, 1));
+        assert_eq!(report.recipients["11"].status, DeliveryStatus::Delivered(vec![MessageId(2), MessageId(3)]));
+        assert_eq!(report.summary(), format!(
+            "{}: 2, {}: 1, {}: 1\n- Boris (12): Forbidden: bot was blocked by the user\n- Vera (13): Unknown error: \"Bad Request: message is too long\"",
+            loc("Delivered"), loc("failed"), loc("blocked"),
+        ));
+    }
+    ///
+    /// Recipient being sent at the restart is failed, not sent again
+    #[test]
+    fn outbox() {
+        let mut report = DeliveryReport::default();
+        report.enqueue(11, "Anton");
+        report.enqueue(12, "Boris");
+        report.enqueue(13, "Vera");
+        report.enqueue(11, "Anton duplicate");
+        assert_eq!(report.pending(), vec!["11", "12", "13"]);
+        assert!(!report.is_complete());
+        assert_eq!(report.recipients["11"].name, "Anton");
+        report.set("11", DeliveryStatus::Delivered(vec![MessageId(1)]));
+        report.set("12", DeliveryStatus::Sending);
+        assert_eq!(report.pending(), vec!["13"]);
+        assert!(report.fail_interrupted());
+        assert!(!report.fail_interrupted());
+        assert_eq!(report.pending(), vec!["13"]);
+        assert_eq!(report.counts(), (1, 0, 1));
+        assert!(report.summary().starts_with(&format!("{}: 1, {}: 1, {}: 0, {}: 1", loc("Delivered"), loc("failed"), loc("blocked"), loc("pending"))));
+        assert_eq!(report.delivered(), vec![("11".to_owned(), vec![MessageId(1)])]);
+        report.set("13", DeliveryStatus::Delivered(vec![MessageId(2)]));
+        assert!(report.is_complete());
+    }
+}
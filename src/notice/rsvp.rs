@@ -0,0 +1,316 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use teloxide::{prelude::*, requests::Request, types::{InlineKeyboardButton, MessageId, PollAnswer}, ApiError, RequestError};
+use crate::{
+    db::storage::Db, kernel::error::{Error, HandlerResult}, loc::loc, subscribe::subscription::group_titles,
+    user::user_role::UserRole,
+};
+use super::{
+    delivery::LIMITER, options::RsvpAnswer, outbox::recipient,
+    sent_notice::{update, SentNotice, Vote},
+};
+///
+/// Interval of checking the answers to be closed by the deadline
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+///
+/// Max number of the users listed in the answers view, to fit into the Telegram message
+const MAX_LISTED: usize = 30;
+///
+/// Answer callbacks, handled in any dialogue state
+#[derive(Debug, Clone, PartialEq)]
+pub enum RsvpMenu {
+    Vote(u64, RsvpAnswer),  // Recipient answers the notice
+    View(u64),              // Sender opens the answers
+    Unknown,                // Not an answer callback
+}
+//
+//
+impl RsvpMenu {
+    pub fn parse(s: &str) -> Self {
+        if let Some(id) = s.strip_prefix("/RsvpView/").and_then(|id| id.parse().ok()) {
+            return Self::View(id);
+        }
+        let vote = s.strip_prefix("/Rsvp/")
+            .and_then(|input| input.split_once('/'))
+            .and_then(|(id, answer)| Some((id.parse().ok()?, RsvpAnswer::parse(answer)?)));
+        match vote {
+            Some((id, answer)) => Self::Vote(id, answer),
+            None => Self::Unknown,
+        }
+    }
+}
+///
+/// Yes / No / Maybe buttons attached to every copy of the notice answered with the buttons
+pub fn buttons(id: u64) -> Vec<InlineKeyboardButton> {
+    RsvpAnswer::ALL.iter()
+        .map(|answer| InlineKeyboardButton::callback(answer.title(), format!("/Rsvp/{}/{}", id, answer.as_str())))
+        .collect()
+}
+///
+/// Sends the native poll after the copy of the notice, returns the poll id and the poll message
+pub async fn send_poll(bot: &Bot, chat_id: &str) -> Result<(String, MessageId), Error> {
+    let chat = recipient(chat_id);
+    let options: Vec<String> = RsvpAnswer::ALL.iter().map(|answer| answer.title()).collect();
+    let msg = LIMITER.deliver(&chat, || {
+        bot.send_poll(chat.clone(), loc("Your answer to the notice above"), options.clone())
+            .is_anonymous(false)
+            .send()
+    }).await?;
+    let poll_id = msg.poll()
+        .map(|poll| poll.id.clone())
+        .ok_or_else(|| Error::NotFound(format!("rsvp.send_poll | Poll in the message {} to the {}", msg.id, chat_id)))?;
+    Ok((poll_id, msg.id))
+}
+///
+/// Handles the answer callbacks before the dialogue state,
+/// so the recipient answers the notice whatever menu is opened. Returns false for other callbacks
+pub async fn callback(bot: &Bot, q: &CallbackQuery, input: &str, db: &Db) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match RsvpMenu::parse(input) {
+        RsvpMenu::Vote(id, answer) => {
+            let text = match vote(bot, db, id, q.from.id.to_string(), q.from.full_name(), Some(answer)).await? {
+                true => format!("{}: {}", loc("Your answer"), answer.title()),
+                false => loc("Answers are closed"),
+            };
+            bot.answer_callback_query(q.id.clone()).text(text).await?;
+            Ok(true)
+        }
+        RsvpMenu::View(id) => {
+            view(bot, q, id, db).await?;
+            Ok(true)
+        }
+        RsvpMenu::Unknown => Ok(false),
+    }
+}
+///
+/// Stores the answer to the native poll, the retracted answer is removed.
+/// Polls not sent with the notices are skipped
+pub async fn poll_answer(bot: &Bot, answer: &PollAnswer, db: &Db) -> Result<(), Error> {
+    let Some(user) = answer.voter.user() else {
+        return Ok(());
+    };
+    let Some(notice) = db.sent_notices().await?.into_values().find(|notice| notice.polls.contains_key(&answer.poll_id)) else {
+        log::debug!("rsvp.poll_answer | Poll {} isn't a notice poll", answer.poll_id);
+        return Ok(());
+    };
+    let vote_answer = answer.option_ids.first().and_then(|index| RsvpAnswer::ALL.get(*index as usize)).copied();
+    vote(bot, db, notice.id, user.id.to_string(), user.full_name(), vote_answer).await?;
+    Ok(())
+}
+///
+/// Stores the answer of the user, None removes it, and updates the sender's view.
+/// Returns false if the answers are closed
+async fn vote(bot: &Bot, db: &Db, id: u64, user_id: String, name: String, answer: Option<RsvpAnswer>) -> Result<bool, Error> {
+    let now = Utc::now();
+    let mut accepted = false;
+    let notice = update(db, id, |notice| {
+        if is_open(notice, now) {
+            accepted = true;
+            match answer {
+                Some(answer) => { notice.votes.insert(user_id.clone(), Vote { name: name.clone(), answer, at: now }); }
+                None => { notice.votes.shift_remove(&user_id); }
+            }
+        }
+    }).await?;
+    if accepted {
+        log::info!("rsvp.vote | Notice {} answered {:?} by '{}' ({})", id, answer, name, user_id);
+        if let Some(message_id) = notice.rsvp_view {
+            if let Err(err) = edit_view(bot, notice.sender, message_id, &notice, db).await {
+                log::warn!("rsvp.vote | Error updating the view of the notice {}: {}", id, err);
+            }
+        }
+    }
+    Ok(accepted)
+}
+///
+/// Returns true if the notice accepts the answers at `now`
+fn is_open(notice: &SentNotice, now: DateTime<Utc>) -> bool {
+    match notice.options.rsvp {
+        Some(rsvp) => notice.closed_at.is_none() && rsvp.deadline.is_none_or(|deadline| now < deadline),
+        None => false,
+    }
+}
+///
+/// Shows the answers to the sender, Moder's and Admin's as a new message,
+/// the last view opened by the sender is updated on every next answer
+async fn view(bot: &Bot, q: &CallbackQuery, id: u64, db: &Db) -> HandlerResult {
+    let chat_id = ChatId::from(q.from.id);
+    let user = db.user(&chat_id).await?;
+    let notice = db.sent_notices().await?
+        .shift_remove(&id.to_string())
+        .filter(|notice| notice.sender == chat_id || user.has_role(&[UserRole::Admin, UserRole::Moder]))
+        .ok_or_else(|| Error::NotFound(format!("rsvp.view | Sent notice {}", id)))?;
+    bot.answer_callback_query(q.id.clone()).await?;
+    let msg = bot.send_message(chat_id, text(&notice, db).await?).await?;
+    if chat_id == notice.sender {
+        update(db, id, |notice| notice.rsvp_view = Some(msg.id)).await?;
+    }
+    Ok(())
+}
+///
+/// Sends the live answers view to the sender when the notice is delivered
+pub async fn send_view(bot: &Bot, db: &Db, notice: &SentNotice) -> Result<(), Error> {
+    let text = text(notice, db).await?;
+    let msg = LIMITER.deliver(&notice.sender.into(), || bot.send_message(notice.sender, text.clone()).send()).await?;
+    update(db, notice.id, |notice| notice.rsvp_view = Some(msg.id)).await?;
+    Ok(())
+}
+///
+/// Updates the view message, the view not changed since the last update is skipped
+async fn edit_view(bot: &Bot, chat_id: ChatId, message_id: MessageId, notice: &SentNotice, db: &Db) -> Result<(), Error> {
+    match bot.edit_message_text(chat_id, message_id, text(notice, db).await?).await {
+        Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+///
+/// Returns the number of the answers in the order of `RsvpAnswer::ALL`
+pub fn tally(notice: &SentNotice) -> [usize; 3] {
+    let mut tally = [0; 3];
+    for vote in notice.votes.values() {
+        if let Some(index) = RsvpAnswer::ALL.iter().position(|answer| *answer == vote.answer) {
+            tally[index] += 1;
+        }
+    }
+    tally
+}
+///
+/// Returns names of the private chats the notice is delivered to, not answered yet
+fn not_answered(notice: &SentNotice) -> Vec<String> {
+    notice.report.delivered().into_iter()
+        .filter(|(chat_id, _)| chat_id.parse().is_ok_and(|id| ChatId(id).is_user()))
+        .filter(|(chat_id, _)| !notice.votes.contains_key(chat_id))
+        .map(|(chat_id, _)| notice.report.recipients[&chat_id].name.clone())
+        .collect()
+}
+///
+/// Returns the tally and the answers of the notice
+async fn text(notice: &SentNotice, db: &Db) -> Result<String, Error> {
+    let groups = db.subscriptions().await?;
+    let waiting = not_answered(notice);
+    let tally = RsvpAnswer::ALL.iter().zip(tally(notice))
+        .map(|(answer, count)| format!("{}: {}", answer.title(), count))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut text = format!(
+        "{} '{}'\n{}, {}: {}",
+        loc("Answers to the notice to the group"), group_titles(&groups, &notice.groups), tally, loc("no answer"), waiting.len(),
+    );
+    if notice.closed_at.is_some() {
+        text.push_str(&format!("\n{}", loc("Answers are closed")));
+    }
+    let mut lines: Vec<String> = RsvpAnswer::ALL.iter()
+        .flat_map(|answer| notice.votes.values()
+            .filter(move |vote| vote.answer == *answer)
+            .map(move |vote| format!("{} - {}", vote.name, answer.title())))
+        .collect();
+    lines.extend(waiting.into_iter().map(|name| format!("⏳ {}", name)));
+    for line in lines.iter().take(MAX_LISTED) {
+        text.push('\n');
+        text.push_str(line);
+    }
+    if lines.len() > MAX_LISTED {
+        text.push_str(&format!("\n... {} {}", lines.len() - MAX_LISTED, loc("more")));
+    }
+    Ok(text)
+}
+///
+/// Returns true if the answers of the notice are to be closed at `now`
+pub fn is_due(notice: &SentNotice, now: DateTime<Utc>) -> bool {
+    let deadline = notice.options.rsvp.and_then(|rsvp| rsvp.deadline);
+    notice.closed_at.is_none() && notice.recalled_at.is_none() && notice.report.is_complete()
+        && deadline.is_some_and(|deadline| deadline <= now)
+}
+///
+/// Background task closing the answers by the deadline, started from `main` beside the dispatcher
+pub async fn run(bot: Bot, db: Db) {
+    log::info!("rsvp.run | Started, check interval: {:?}", CHECK_INTERVAL);
+    loop {
+        if let Err(err) = check(&bot, &db, Utc::now()).await {
+            log::warn!("rsvp.run | Error: {}", err);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+///
+///
+async fn check(bot: &Bot, db: &Db, now: DateTime<Utc>) -> Result<(), Error> {
+    for notice in db.sent_notices().await?.into_values().filter(|notice| is_due(notice, now)) {
+        if let Err(err) = close(bot, db, &notice, now).await {
+            log::warn!("rsvp.check | Notice {} answers aren't closed: {}", notice.id, err);
+        }
+    }
+    Ok(())
+}
+///
+/// Stops the native polls of the notice, the later answers aren't accepted,
+/// and sends the final summary to the sender. The answers are stored closed after the summary is sent,
+/// so the summary failed by the network is retried on the next check
+async fn close(bot: &Bot, db: &Db, notice: &SentNotice, now: DateTime<Utc>) -> Result<(), Error> {
+    for poll in notice.polls.values() {
+        let chat = recipient(&poll.chat_id);
+        if let Err(err) = LIMITER.deliver(&chat, || bot.stop_poll(chat.clone(), poll.message_id).send()).await {
+            log::warn!("rsvp.close | Error stopping poll of the notice {} in the {}: {}", notice.id, poll.chat_id, err);
+        }
+    }
+    let closed = SentNotice { closed_at: Some(now), ..notice.clone() };
+    let text = text(&closed, db).await?;
+    match LIMITER.deliver(&notice.sender.into(), || bot.send_message(notice.sender, text.clone()).send()).await {
+        Ok(_) => {}
+        Err(Error::Telegram(RequestError::Api(err))) => log::warn!("rsvp.close | Summary of the notice {} isn't sent to the {}: {}", notice.id, notice.sender, err),
+        Err(err) => return Err(err),
+    }
+    let notice = update(db, notice.id, |notice| notice.closed_at = Some(now)).await?;
+    log::info!("rsvp.close | Notice {} answers closed: {:?}", notice.id, tally(&notice));
+    if let Some(message_id) = notice.rsvp_view {
+        if let Err(err) = edit_view(bot, notice.sender, message_id, &notice, db).await {
+            log::warn!("rsvp.close | Error updating the view of the notice {}: {}", notice.id, err);
+        }
+    }
+    Ok(())
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use teloxide::types::MessageId;
+    use crate::notice::{
+        options::{NoticeOptions, Rsvp, RsvpAnswer, RsvpKind}, report::{DeliveryReport, DeliveryStatus},
+        sent_notice::{SentNotice, Vote},
+    };
+    use super::{is_due, is_open, not_answered, tally, RsvpMenu};
+    #[test]
+    fn parse() {
+        assert_eq!(RsvpMenu::parse("/Rsvp/5/yes"), RsvpMenu::Vote(5, RsvpAnswer::Yes));
+        assert_eq!(RsvpMenu::parse("/Rsvp/5/maybe"), RsvpMenu::Vote(5, RsvpAnswer::Maybe));
+        assert_eq!(RsvpMenu::parse("/RsvpView/5"), RsvpMenu::View(5));
+        assert_eq!(RsvpMenu::parse("/Rsvp/5/later"), RsvpMenu::Unknown);
+        assert_eq!(RsvpMenu::parse("/Ack/5"), RsvpMenu::Unknown);
+    }
+    ///
+    /// One tally from every copy, the answers are closed by the deadline
+    #[test]
+    fn votes() {
+        let sent_at = Utc.with_ymd_and_hms(2024, 5, 20, 8, 0, 0).unwrap();
+        let deadline = sent_at + Duration::hours(4);
+        let mut report = DeliveryReport::default();
+        for (chat_id, name) in [(-4224543713i64, "Office-group"), (11, "Anton"), (12, "Boris"), (13, "Vera")] {
+            report.enqueue(chat_id, name);
+            report.set(&chat_id.to_string(), DeliveryStatus::Delivered(vec![MessageId(1)]));
+        }
+        let mut notice = SentNotice::test(1, sent_at, report, NoticeOptions { rsvp: Some(Rsvp { kind: RsvpKind::Buttons, deadline: Some(deadline) }), ..Default::default() });
+        for (user_id, name, answer) in [("11", "Anton", RsvpAnswer::Yes), ("21", "Gleb", RsvpAnswer::Yes), ("12", "Boris", RsvpAnswer::Maybe)] {
+            notice.votes.insert(user_id.to_owned(), Vote { name: name.to_owned(), answer, at: sent_at });
+        }
+        assert_eq!(tally(&notice), [2, 0, 1]);
+        assert_eq!(not_answered(&notice), vec!["Vera".to_owned()]);
+        assert!(is_open(&notice, sent_at + Duration::hours(1)));
+        assert!(!is_due(&notice, sent_at + Duration::hours(1)));
+        assert!(!is_open(&notice, deadline));
+        assert!(is_due(&notice, deadline));
+        notice.closed_at = Some(deadline);
+        assert!(!is_due(&notice, deadline + Duration::hours(1)));
+        notice.options.rsvp = None;
+        assert!(!is_open(&notice, sent_at));
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
+use crate::{
+    config::ScheduleConfig, db::storage::Db, kernel::error::{Error, HandlerResult}, loc::{loc, LocaleTag},
+    message::edit_markup_message_or_send, states::{MainState, MyDialogue}, subscribe::subscription::group_titles,
+    user::user_role::UserRole,
+};
+use super::{notice::{self, NoticeState}, scheduled_notice::ScheduledNotice};
+///
+/// Scheduled notices menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduledMenu {
+   List,            // List of the pending notices
+   Notice(u64),     // Selected notice
+   Reschedule(u64), // Change the time of the notice
+   Edit(u64),       // Replace the content of the notice
+   Cancel(u64),     // Remove the notice
+   Unknown(String), // Unknown command received
+   Done,            // Exit menu
+}
+//
+//
+impl ScheduledMenu {
+    pub fn parse(s: &str, _loc_tag: LocaleTag) -> Self {
+        let input = s.strip_prefix('/').map_or_else(|| ("", s), |input| ("/", input));
+        match input {
+            ("/", "done" | "Done") => Self::Done,
+            ("/", "back" | "Back") => Self::Done,
+            ("/", "exit" | "Exit") => Self::Done,
+            ("/", "Scheduled") => Self::List,
+            ("/", input) => {
+                let mut input = input.split('/');
+                match (input.next(), input.next().map(|id| id.parse()), input.next()) {
+                    (Some("Scheduled"), Some(Ok(id)), None) => Self::Notice(id),
+                    (Some("Scheduled"), Some(Ok(id)), Some("Reschedule")) => Self::Reschedule(id),
+                    (Some("Scheduled"), Some(Ok(id)), Some("Edit")) => Self::Edit(id),
+                    (Some("Scheduled"), Some(Ok(id)), Some("Cancel")) => Self::Cancel(id),
+                    _ => Self::Unknown(s.to_owned()),
+                }
+            }
+            (_, _) => Self::Unknown(s.to_owned()),
+        }
+    }
+}
+///
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledState {
+    pub prev_state: MainState,  // Where to go on Back btn
+    pub chat_id: ChatId,        // User id managing the notices
+}
+///
+/// Shows pending notices of the user, Moder's and Admin's see all notices
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: ScheduledState, db: Db, schedule: &ScheduleConfig) -> HandlerResult {
+    log::debug!("scheduled.enter | state: {:#?}", state);
+    dialogue.update(state.clone()).await?;
+    let notices = notices(&db, state.chat_id).await?;
+    let groups = db.subscriptions().await?;
+    let text = match notices.is_empty() {
+        true => loc("No scheduled notices"),
+        false => format!("{} ({})", loc("Scheduled notices"), schedule.time_zone),
+    };
+    let mut buttons: Vec<InlineKeyboardButton> = notices.iter()
+        .map(|notice| {
+            let group = group_titles(&groups, &notice.groups);
+            InlineKeyboardButton::callback(
+                format!("{} | {}", notice.local_time(schedule.time_zone), group),
+                format!("/Scheduled/{}", notice.id),
+            )
+        })
+        .collect();
+    buttons.push(InlineKeyboardButton::callback(loc("⏪Back"), "/back"));
+    let markup = buttons.into_iter()
+        .fold(InlineKeyboardMarkup::default(), |acc, item| acc.append_row(vec![item]));
+    edit_markup_message_or_send(&bot, &msg, &markup, &text).await
+}
+///
+/// Handles the commands of the Scheduled menu
+pub async fn select(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: ScheduledState, cmd: ScheduledMenu, db: Db, schedule: &ScheduleConfig) -> HandlerResult {
+    let msg = match q.regular_message() {
+        Some(msg) => msg.to_owned(),
+        None => return Ok(()),
+    };
+    match cmd {
+        ScheduledMenu::Notice(id) => {
+            let scheduled = notice(&db, state.chat_id, id).await?;
+            let sender = db.user(&scheduled.sender).await?;
+            let group = group_titles(&db.subscriptions().await?, &scheduled.groups);
+            scheduled.content.send(&bot, state.chat_id, &sender.name, None, scheduled.options.priority).await?;
+            let text = format!(
+                "{} {} ({})\n{} '{}'",
+                loc("Notice above is scheduled at"), scheduled.local_time(schedule.time_zone), schedule.time_zone, loc("to the group"), group,
+            );
+            let markup = InlineKeyboardMarkup::new(vec![
+                vec![
+                    InlineKeyboardButton::callback(loc("Reschedule"), format!("/Scheduled/{}/Reschedule", id)),
+                    InlineKeyboardButton::callback(loc("Edit"), format!("/Scheduled/{}/Edit", id)),
+                ],
+                vec![
+                    InlineKeyboardButton::callback(loc("Cancel notice"), format!("/Scheduled/{}/Cancel", id)),
+                    InlineKeyboardButton::callback(loc("⏪Back"), "/Scheduled"),
+                ],
+            ]);
+            bot.send_message(state.chat_id, text)
+                .reply_markup(markup)
+                .await?;
+            Ok(())
+        }
+        ScheduledMenu::Reschedule(id) => {
+            let scheduled = notice(&db, state.chat_id, id).await?;
+            let state = NoticeState {
+                prev_state: state.prev_state,
+                groups: scheduled.groups,
+                groups_selected: true,
+                chat_id: state.chat_id,
+                content: Some(scheduled.content),
+                options: scheduled.options,
+                scheduled_id: Some(id),
+                ..Default::default()
+            };
+            notice::ask_time(&bot, &msg, dialogue, state, schedule).await
+        }
+        ScheduledMenu::Edit(id) => {
+            let scheduled = notice(&db, state.chat_id, id).await?;
+            let state = NoticeState {
+                prev_state: state.prev_state,
+                groups: scheduled.groups,
+                groups_selected: true,
+                chat_id: state.chat_id,
+                options: scheduled.options,
+                scheduled_id: Some(id),
+                ..Default::default()
+            };
+            notice::enter(bot, msg, dialogue, state, db).await
+        }
+        ScheduledMenu::Cancel(id) => {
+            notice(&db, state.chat_id, id).await?;
+            db.remove_scheduled_notice(id).await?;
+            log::info!("scheduled.select | Notice {} canceled by {}", id, state.chat_id);
+            bot.answer_callback_query(q.id).text(loc("Scheduled notice canceled")).await?;
+            enter(bot, msg, dialogue, state, db, schedule).await
+        }
+        ScheduledMenu::List | ScheduledMenu::Unknown(_) => enter(bot, msg, dialogue, state, db, schedule).await,
+        ScheduledMenu::Done => crate::states::reload(bot, &msg, dialogue, state.prev_state, &db).await,
+    }
+}
+///
+/// Returns notices available to the user, sorted by time
+async fn notices(db: &Db, chat_id: ChatId) -> Result<Vec<ScheduledNotice>, Error> {
+    let user = db.user(&chat_id).await?;
+    let all = user.has_role(&[UserRole::Admin, UserRole::Moder]);
+    let mut notices: Vec<ScheduledNotice> = db.scheduled_notices().await?
+        .into_values()
+        .filter(|notice| all || notice.sender == chat_id)
+        .collect();
+    notices.sort_by_key(|notice| notice.at);
+    Ok(notices)
+}
+///
+/// Returns the notice if it is available to the user
+async fn notice(db: &Db, chat_id: ChatId, id: u64) -> Result<ScheduledNotice, Error> {
+    notices(db, chat_id).await?
+        .into_iter()
+        .find(|notice| notice.id == id)
+        .ok_or_else(|| Error::NotFound(format!("scheduled.notice | Scheduled notice {}", id)))
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use super::ScheduledMenu;
+    #[test]
+    fn parse() {
+        assert_eq!(ScheduledMenu::parse("/Scheduled", 0), ScheduledMenu::List);
+        assert_eq!(ScheduledMenu::parse("/Scheduled/12", 0), ScheduledMenu::Notice(12));
+        assert_eq!(ScheduledMenu::parse("/Scheduled/12/Reschedule", 0), ScheduledMenu::Reschedule(12));
+        assert_eq!(ScheduledMenu::parse("/Scheduled/12/Edit", 0), ScheduledMenu::Edit(12));
+        assert_eq!(ScheduledMenu::parse("/Scheduled/12/Cancel", 0), ScheduledMenu::Cancel(12));
+        assert_eq!(ScheduledMenu::parse("/back", 0), ScheduledMenu::Done);
+        assert_eq!(ScheduledMenu::parse("/Scheduled/x", 0), ScheduledMenu::Unknown("/Scheduled/x".to_owned()));
+    }
+}
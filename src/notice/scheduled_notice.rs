@@ -0,0 +1,90 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use teloxide::types::ChatId;
+use super::{content::NoticeContent, options::NoticeOptions, sent_notice::deserialize_groups};
+///
+/// Scheduled notices by id
+pub type ScheduledNotices = IndexMap<String, ScheduledNotice>;
+///
+/// Notice to be sent to the group at the specified time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledNotice {
+    /// Assigned by the storage, 0 for the new notice
+    pub id: u64,
+    /// Chat id of the user scheduled the notice
+    pub sender: ChatId,
+    /// Group ids to be noticed, the single group id of the notice stored before is accepted
+    #[serde(alias = "group", deserialize_with = "deserialize_groups")]
+    pub groups: Vec<String>,
+    pub content: NoticeContent,
+    /// Time the notice to be sent at
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub options: NoticeOptions,
+}
+//
+//
+impl ScheduledNotice {
+    ///
+    /// Returns sending time as text in the time zone
+    pub fn local_time(&self, time_zone: Tz) -> String {
+        format_time(self.at, time_zone)
+    }
+}
+///
+/// Returns time as text in the time zone, like '2024-05-20 08:00'
+pub fn format_time(time: DateTime<Utc>, time_zone: Tz) -> String {
+    time.with_timezone(&time_zone).format("%Y-%m-%d %H:%M").to_string()
+}
+///
+/// Parses the time entered by user in the time zone, the time must be later than `now`
+/// - '2024-05-20 08:00', '20.05.2024 08:00'
+/// - '08:00' - the nearest such time, today or tomorrow
+pub fn parse_time(input: &str, now: DateTime<Utc>, time_zone: Tz) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    let local = match NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%d.%m.%Y %H:%M")) {
+        Ok(local) => local,
+        Err(_) => {
+            let time = NaiveTime::parse_from_str(input, "%H:%M")
+                .map_err(|_| format!("parse_time | Wrong time '{}'", input))?;
+            let today: NaiveDate = now.with_timezone(&time_zone).date_naive();
+            let local = today.and_time(time);
+            if to_utc(local, time_zone)? > now { local } else { (today + Days::new(1)).and_time(time) }
+        }
+    };
+    let time = to_utc(local, time_zone)?;
+    if time <= now {
+        return Err(format!("parse_time | Time '{}' is already passed", input));
+    }
+    Ok(time)
+}
+///
+///
+fn to_utc(local: NaiveDateTime, time_zone: Tz) -> Result<DateTime<Utc>, String> {
+    time_zone.from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| format!("parse_time | Time '{}' doesn't exist in the '{}'", local, time_zone))
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::parse_time;
+    #[test]
+    fn parse() {
+        let tz = chrono_tz::Europe::Moscow;
+        // 2024-05-20 10:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2024, 5, 20, 7, 0, 0).unwrap();
+        assert_eq!(parse_time("2024-05-21 08:00", now, tz), Ok(Utc.with_ymd_and_hms(2024, 5, 21, 5, 0, 0).unwrap()));
+        assert_eq!(parse_time("21.05.2024 08:00", now, tz), Ok(Utc.with_ymd_and_hms(2024, 5, 21, 5, 0, 0).unwrap()));
+        assert_eq!(parse_time("12:30", now, tz), Ok(Utc.with_ymd_and_hms(2024, 5, 20, 9, 30, 0).unwrap()));
+        assert_eq!(parse_time(" 08:00 ", now, tz), Ok(Utc.with_ymd_and_hms(2024, 5, 21, 5, 0, 0).unwrap()));
+        assert!(parse_time("2024-05-20 09:00", now, tz).is_err());
+        assert!(parse_time("tomorrow", now, tz).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use teloxide::prelude::*;
+use crate::{config::ScheduleConfig, db::storage::Db, kernel::error::Error, loc::loc, subscribe::subscription::{denied_groups, group_titles}};
+use super::{content::NoticeContent, notice::broadcast, options::NoticeOptions, recurring_notice::RecurringNotice};
+///
+/// Interval of checking the scheduled and recurring notices
+const POLL_INTERVAL: Duration = Duration::from_secs(20);
+///
+/// Background task queueing the scheduled and recurring notices to the outbox when their time comes,
+/// started from `main` beside the dispatcher
+pub async fn run(bot: Bot, db: Db, schedule: ScheduleConfig) {
+    log::info!("scheduler.run | Started, poll interval: {:?}", POLL_INTERVAL);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        if let Err(err) = send_due(&bot, &db, now).await {
+            log::warn!("scheduler.run | Error: {}", err);
+        }
+        if let Err(err) = send_recurring(&bot, &db, now, &schedule).await {
+            log::warn!("scheduler.run | Recurring error: {}", err);
+        }
+    }
+}
+///
+/// Queues notices scheduled at `now` or earlier and removes them from the storage
+async fn send_due(bot: &Bot, db: &Db, now: DateTime<Utc>) -> Result<(), Error> {
+    let due = db.scheduled_notices().await?
+        .into_values()
+        .filter(|notice| notice.at <= now);
+    for notice in due {
+        log::info!("scheduler.send_due | Sending notice {} from {} to the {:?} scheduled at {}", notice.id, notice.sender, notice.groups, notice.at);
+        if let Err(err) = send(bot, db, notice.id, notice.sender, &notice.groups, &notice.content, &notice.options).await {
+            log::warn!("scheduler.send_due | Notice {} error: {}", notice.id, err);
+        }
+        if let Err(err) = db.remove_scheduled_notice(notice.id).await {
+            log::warn!("scheduler.send_due | Notice {} isn't removed: {}", notice.id, err);
+        }
+    }
+    Ok(())
+}
+///
+/// Queues recurring notices whose next time is `now` or earlier, missed times are queued once.
+/// Notices without `last_sent` (added to the file by hand) start from `now`
+async fn send_recurring(bot: &Bot, db: &Db, now: DateTime<Utc>, schedule: &ScheduleConfig) -> Result<(), Error> {
+    for notice in db.recurring_notices().await?.into_values() {
+        let id = notice.id;
+        if notice.last_sent.is_none() {
+            log::info!("scheduler.send_recurring | Notice {} '{}' starts at {}", notice.id, notice.rule, now);
+            if let Err(err) = db.update_recurring_notice(RecurringNotice { last_sent: Some(now), ..notice }).await {
+                log::warn!("scheduler.send_recurring | Notice {} start isn't stored: {}", id, err);
+            }
+            continue;
+        }
+        match notice.next(now, schedule.time_zone) {
+            Some(next) if next <= now => {
+                log::info!("scheduler.send_recurring | Sending notice {} from {} to the {:?} repeated '{}' at {}", notice.id, notice.sender, notice.groups, notice.rule, next);
+                if let Err(err) = send(bot, db, notice.id, notice.sender, &notice.groups, &notice.content, &notice.options).await {
+                    log::warn!("scheduler.send_recurring | Notice {} error: {}", notice.id, err);
+                }
+                if let Err(err) = db.update_recurring_notice(RecurringNotice { last_sent: Some(now), ..notice }).await {
+                    log::warn!("scheduler.send_recurring | Notice {} sending time isn't stored: {}", id, err);
+                }
+            }
+            Some(_) => {}
+            None => log::warn!("scheduler.send_recurring | Notice {} rule '{}' never fires", notice.id, notice.rule),
+        }
+    }
+    Ok(())
+}
+///
+/// Queues the notice to the groups the sender is still allowed to notice
+async fn send(bot: &Bot, db: &Db, id: u64, sender: ChatId, selected: &[String], content: &NoticeContent, options: &NoticeOptions) -> Result<(), Error> {
+    let groups = allowed(bot, db, id, sender, selected).await?;
+    if !groups.is_empty() {
+        broadcast(sender, &groups, content, options, db).await?;
+    }
+    Ok(())
+}
+///
+/// Returns the groups of the notice the sender is still allowed to notice, the senders of the group
+/// or the role of the sender could be changed after the notice was created.
+/// The denied groups are skipped, the sender is told about them
+async fn allowed(bot: &Bot, db: &Db, id: u64, sender: ChatId, selected: &[String]) -> Result<Vec<String>, Error> {
+    let groups = db.subscriptions().await?;
+    let denied = match db.user(&sender).await {
+        Ok(user) => denied_groups(&groups, &user, selected),
+        Err(Error::NotFound(_)) => selected.to_vec(),
+        Err(err) => return Err(err),
+    };
+    if denied.is_empty() {
+        return Ok(selected.to_vec());
+    }
+    log::warn!("scheduler.allowed | Notice {} from {} isn't sent to the {:?}, the sender isn't allowed to notice them", id, sender, denied);
+    let text = format!("{} '{}'", loc("Notice isn't sent to the groups you aren't allowed to notice anymore"), group_titles(&groups, &denied));
+    if let Err(err) = bot.send_message(sender, text).await {
+        log::warn!("scheduler.allowed | Notice {}, error telling the sender {}: {}", id, sender, err);
+    }
+    Ok(selected.iter().filter(|group_id| !denied.contains(group_id)).cloned().collect())
+}
@@ -0,0 +1,111 @@
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
+use crate::{
+    db::storage::Db, kernel::error::{Error, HandlerResult}, loc::loc, message::{edit_markup_message_or_send, escape},
+    subscribe::subscription::{Senders, Subscription}, user::{user::User, user_role::UserRole},
+};
+use super::notice::NoticeState;
+///
+/// Roles can be allowed to notice the group, Admin's can notice every group anyway
+pub const ROLES: [UserRole; 2] = [UserRole::Moder, UserRole::Sender];
+///
+/// Shows the active groups with the senders allowed to notice them, to be edited by the Moder
+pub async fn enter(bot: &Bot, msg: &Message, state: &NoticeState, db: &Db) -> HandlerResult {
+    moder(db, state.chat_id).await?;
+    let groups = db.subscriptions().await?;
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = groups.iter()
+        .filter(|(_, group)| group.is_active())
+        .map(|(group_id, group)| vec![InlineKeyboardButton::callback(
+            format!("{}: {}", group.title, summary(&group.senders)),
+            format!("/NoticeSenders/{}", group_id),
+        )])
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeGroups")]);
+    edit_markup_message_or_send(bot, msg, &InlineKeyboardMarkup::new(rows), &loc("Select group to edit the senders allowed to notice it")).await
+}
+///
+/// Shows the roles and the users allowed to notice the group, allowed ones are checked
+pub async fn group(bot: &Bot, msg: &Message, state: &NoticeState, group_id: &str, db: &Db) -> HandlerResult {
+    moder(db, state.chat_id).await?;
+    let group = subscription(db, group_id).await?;
+    let users = db.users().await?;
+    let check = |allowed: bool, title: String| match allowed {
+        true => format!("✅ {}", title),
+        false => title,
+    };
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = vec![ROLES.iter()
+        .map(|role| InlineKeyboardButton::callback(
+            check(group.senders.roles.contains(role), role_title(role)),
+            format!("/NoticeSenderRole/{}/{}", role, group_id),
+        ))
+        .collect()];
+    rows.extend(users.values()
+        .filter(|user| user.has_role(&ROLES) || group.senders.users.contains(&user.id.to_string()))
+        .map(|user| vec![InlineKeyboardButton::callback(
+            check(group.senders.users.contains(&user.id.to_string()), user.name.clone()),
+            format!("/NoticeSenderUser/{}/{}", user.id, group_id),
+        )]));
+    rows.push(vec![InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeSenders")]);
+    let text = format!(
+        "{} '{}'\n{}",
+        loc("Senders allowed to notice the group"), escape(&group.title), loc("Every Sender can notice the group if nobody is checked, Admins can notice every group"),
+    );
+    edit_markup_message_or_send(bot, msg, &InlineKeyboardMarkup::new(rows), &text).await
+}
+///
+/// Allows or disallows the role to notice the group
+pub async fn toggle_role(bot: &Bot, msg: &Message, state: &NoticeState, group_id: &str, role: UserRole, db: &Db) -> HandlerResult {
+    let user = moder(db, state.chat_id).await?;
+    let switched = role.clone();
+    db.update_senders(group_id, Box::new(move |senders| senders.toggle_role(switched))).await?;
+    log::info!("senders.toggle_role | Role '{}' to notice the group '{}' switched by '{}' ({})", role, group_id, user.name, user.id);
+    group(bot, msg, state, group_id, db).await
+}
+///
+/// Allows or disallows the user to notice the group
+pub async fn toggle_user(bot: &Bot, msg: &Message, state: &NoticeState, group_id: &str, user_id: ChatId, db: &Db) -> HandlerResult {
+    let user = moder(db, state.chat_id).await?;
+    db.update_senders(group_id, Box::new(move |senders| senders.toggle_user(&user_id.to_string()))).await?;
+    log::info!("senders.toggle_user | User {} to notice the group '{}' switched by '{}' ({})", user_id, group_id, user.name, user.id);
+    group(bot, msg, state, group_id, db).await
+}
+///
+/// Returns the role allowed to notice the group by its name
+pub fn parse_role(input: &str) -> Option<UserRole> {
+    ROLES.iter().find(|role| role.to_string() == input).cloned()
+}
+///
+///
+fn role_title(role: &UserRole) -> String {
+    match role {
+        UserRole::Moder => loc("Moders"),
+        _ => loc("Senders"),
+    }
+}
+///
+/// Returns allowed roles and the number of the allowed users
+fn summary(senders: &Senders) -> String {
+    if senders.is_empty() {
+        return loc("every Sender");
+    }
+    let mut parts: Vec<String> = senders.roles.iter().map(role_title).collect();
+    if !senders.users.is_empty() {
+        parts.push(format!("{} {}", senders.users.len(), loc("users")));
+    }
+    parts.join(", ")
+}
+///
+///
+async fn subscription(db: &Db, group_id: &str) -> Result<Subscription, Error> {
+    db.subscriptions().await?
+        .shift_remove(group_id)
+        .ok_or_else(|| Error::NotFound(format!("senders.subscription | Group '{}'", group_id)))
+}
+///
+/// Returns the user if it's Moder or Admin, the senders of the groups are edited by them only
+async fn moder(db: &Db, chat_id: ChatId) -> Result<User, Error> {
+    let user = db.user(&chat_id).await?;
+    match user.has_role(&[UserRole::Admin, UserRole::Moder]) {
+        true => Ok(user),
+        false => Err(Error::PermissionDenied(format!("{}, {}", user.name, loc("only Moder can edit the senders of the groups")))),
+    }
+}
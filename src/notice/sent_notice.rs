@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize};
+use teloxide::types::{ChatId, MessageId};
+use tokio::sync::Mutex;
+use crate::{db::storage::Db, kernel::error::Error};
+use super::{content::NoticeContent, options::{NoticeOptions, RsvpAnswer}, report::DeliveryReport};
+lazy_static! {
+    ///
+    /// Serializes changes of the stored sent notices, made by the outbox and by the users
+    static ref UPDATING: Mutex<()> = Mutex::new(());
+}
+///
+/// Sent notices by id
+pub type SentNotices = IndexMap<String, SentNotice>;
+///
+/// Notice already sent to the group, with the delivery report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentNotice {
+    /// Assigned by the storage, 0 for the new notice
+    pub id: u64,
+    /// Chat id of the user sent the notice
+    pub sender: ChatId,
+    /// Group ids noticed, the single group id of the notice stored before is accepted
+    #[serde(alias = "group", deserialize_with = "deserialize_groups")]
+    pub groups: Vec<String>,
+    pub content: NoticeContent,
+    pub sent_at: DateTime<Utc>,
+    pub report: DeliveryReport,
+    /// Time the delivered copies were last corrected
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Time the delivered copies were deleted
+    #[serde(default)]
+    pub recalled_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub options: NoticeOptions,
+    /// Users acknowledged the notice, by user id
+    #[serde(default)]
+    pub acks: IndexMap<String, Acknowledgement>,
+    /// Message in the sender chat with the acknowledgements, updated on every acknowledgement
+    #[serde(default)]
+    pub ack_view: Option<MessageId>,
+    /// Number of the reminders sent to the recipients not acknowledged the notice
+    #[serde(default)]
+    pub reminders: u32,
+    /// Time the last reminder was sent at
+    #[serde(default)]
+    pub reminded_at: Option<DateTime<Utc>>,
+    /// Time the recipients not acknowledged the notice were reported to the Moders
+    #[serde(default)]
+    pub escalated_at: Option<DateTime<Utc>>,
+    /// Answers of the users, by user id
+    #[serde(default)]
+    pub votes: IndexMap<String, Vote>,
+    /// Native polls sent after the copies, by poll id
+    #[serde(default)]
+    pub polls: IndexMap<String, PollCopy>,
+    /// Message in the sender chat with the answers, updated on every answer
+    #[serde(default)]
+    pub rsvp_view: Option<MessageId>,
+    /// Time the answers were closed at by the deadline
+    #[serde(default)]
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Time the copies were cleaned up at by the time-to-live
+    #[serde(default)]
+    pub expired_at: Option<DateTime<Utc>>,
+    /// Cleanup result of every copy, by chat id
+    #[serde(default)]
+    pub expired: IndexMap<String, Expired>,
+}
+///
+/// Acknowledgement of the notice by the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Acknowledgement {
+    pub name: String,
+    pub at: DateTime<Utc>,
+}
+///
+/// Answer of the user to the notice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vote {
+    pub name: String,
+    pub answer: RsvpAnswer,
+    pub at: DateTime<Utc>,
+}
+///
+/// Cleanup result of the copy of the notice out of date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expired {
+    Deleted,
+    /// Copy can't be deleted, replaced with '(expired)'
+    Edited,
+    /// Copy can't be deleted nor edited, with the error
+    Failed(String),
+}
+///
+/// Native poll sent to the chat after the copy of the notice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollCopy {
+    pub chat_id: String,
+    pub message_id: MessageId,
+}
+//
+//
+impl SentNotice {
+    ///
+    /// Returns the text notice of the user 7 to the 'TKZ_OFFICE_GROUP', nothing is done with it yet
+    #[cfg(test)]
+    pub fn test(id: u64, sent_at: DateTime<Utc>, report: DeliveryReport, options: NoticeOptions) -> Self {
+        Self {
+            id,
+            sender: ChatId(7),
+            groups: vec!["TKZ_OFFICE_GROUP".to_owned()],
+            content: NoticeContent::Text("notice".to_owned()),
+            sent_at,
+            report,
+            edited_at: None,
+            recalled_at: None,
+            options,
+            acks: Default::default(),
+            ack_view: None,
+            reminders: 0,
+            reminded_at: None,
+            escalated_at: None,
+            votes: Default::default(),
+            polls: Default::default(),
+            rsvp_view: None,
+            closed_at: None,
+            expired_at: None,
+            expired: Default::default(),
+        }
+    }
+}
+///
+/// Loads the stored sent notice, applies `change` to it and stores it back,
+/// concurrent changes of the sent notices are applied one by one, so none of them is lost
+pub async fn update(db: &Db, id: u64, change: impl FnOnce(&mut SentNotice)) -> Result<SentNotice, Error> {
+    let _updating = UPDATING.lock().await;
+    let mut notice = db.sent_notices().await?
+        .shift_remove(&id.to_string())
+        .ok_or_else(|| Error::NotFound(format!("sent_notice.update | Sent notice {}", id)))?;
+    change(&mut notice);
+    db.update_sent_notice(notice.clone()).await?;
+    Ok(notice)
+}
+///
+/// Deserializes group ids from the list or from the single group id
+pub fn deserialize_groups<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Groups {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Groups::deserialize(deserializer)? {
+        Groups::One(group) => vec![group],
+        Groups::Many(groups) => groups,
+    })
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use teloxide::types::ChatId;
+    use crate::notice::{content::NoticeContent, report::DeliveryReport};
+    use super::SentNotice;
+    #[test]
+    fn groups() {
+        let notice = SentNotice {
+            sender: ChatId(7038555815),
+            groups: vec!["TKZ_SPB_GROUP".to_owned(), "TKZ_OFFICE_GROUP".to_owned()],
+            content: NoticeContent::Text("Meeting at 10:00".to_owned()),
+            ..SentNotice::test(1, Utc.with_ymd_and_hms(2024, 5, 20, 8, 0, 0).unwrap(), DeliveryReport::default(), Default::default())
+        };
+        let json = serde_json::to_string(&notice).unwrap();
+        assert!(json.contains(r#""groups":["TKZ_SPB_GROUP","TKZ_OFFICE_GROUP"]"#));
+        assert_eq!(serde_json::from_str::<SentNotice>(&json).unwrap(), notice);
+        let stored = r#"{"id":1,"sender":7038555815,"group":"TKZ_SPB_GROUP","content":{"Text":"Meeting at 10:00"},"sent_at":"2024-05-20T08:00:00Z","report":{"recipients":{}}}"#;
+        assert_eq!(serde_json::from_str::<SentNotice>(stored).unwrap().groups, vec!["TKZ_SPB_GROUP"]);
+    }
+}
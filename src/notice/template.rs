@@ -0,0 +1,108 @@
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+///
+/// Notice templates by id
+pub type Templates = IndexMap<String, NoticeTemplate>;
+///
+/// Text of the notice sent over and over, with the `{placeholders}` filled in by the sender,
+/// like 'Office closed on {date} because of {reason}'
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoticeTemplate {
+    /// Assigned by the storage, 0 for the new template
+    pub id: u64,
+    pub title: String,
+    pub text: String,
+}
+//
+//
+impl NoticeTemplate {
+    ///
+    /// Parses the template typed by the Admin: the first line is the title, the rest is the text
+    pub fn parse(id: u64, input: &str) -> Result<Self, String> {
+        let (title, text) = input.trim().split_once('\n')
+            .ok_or_else(|| format!("NoticeTemplate.parse | No text in '{}'", input))?;
+        let (title, text) = (title.trim(), text.trim());
+        if title.is_empty() || text.is_empty() {
+            return Err(format!("NoticeTemplate.parse | Empty title or text in '{}'", input));
+        }
+        Ok(Self { id, title: title.to_owned(), text: text.to_owned() })
+    }
+    ///
+    /// Returns names of the placeholders in the order of the first occurrence
+    pub fn placeholders(&self) -> Vec<String> {
+        let placeholders: IndexSet<&str> = parts(&self.text).into_iter()
+            .filter_map(|part| match part {
+                Part::Placeholder(name) => Some(name),
+                Part::Text(_) => None,
+            })
+            .collect();
+        placeholders.into_iter().map(str::to_owned).collect()
+    }
+    ///
+    /// Returns the text with the placeholders replaced by the `values` in the order of `placeholders`,
+    /// placeholders without the value are kept as is
+    pub fn fill(&self, values: &[String]) -> String {
+        let values: IndexMap<String, &String> = self.placeholders().into_iter().zip(values).collect();
+        parts(&self.text).into_iter()
+            .map(|part| match part {
+                Part::Placeholder(name) => values.get(name).map_or(format!("{{{}}}", name), |value| value.to_string()),
+                Part::Text(text) => text.to_owned(),
+            })
+            .collect()
+    }
+}
+///
+/// Part of the template text
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Text(&'a str),
+    /// Name inside the braces, on the single line
+    Placeholder(&'a str),
+}
+///
+/// Splits the template text into the plain text and the placeholders,
+/// braces without the name inside are kept as the plain text
+fn parts(text: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut plain = 0;
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find('{').map(|start| pos + start) {
+        let inner = &text[start + 1..];
+        match inner.find(['{', '}', '\n']).filter(|end| inner[*end..].starts_with('}')) {
+            Some(end) if !inner[..end].trim().is_empty() => {
+                parts.push(Part::Text(&text[plain..start]));
+                parts.push(Part::Placeholder(inner[..end].trim()));
+                plain = start + 1 + end + 1;
+                pos = plain;
+            }
+            _ => pos = start + 1,
+        }
+    }
+    parts.push(Part::Text(&text[plain..]));
+    parts
+}
+//
+//
+#[cfg(test)]
+mod tests {
+    use super::NoticeTemplate;
+    #[test]
+    fn fill() {
+        let template = NoticeTemplate::parse(1, "Office closed\nOffice closed on {date} because of { reason }.\nOpen again after {date}, {}").unwrap();
+        assert_eq!(template.title, "Office closed");
+        assert_eq!(template.placeholders(), vec!["date".to_owned(), "reason".to_owned()]);
+        assert_eq!(
+            template.fill(&["20.05".to_owned(), "the {heating} repair".to_owned()]),
+            "Office closed on 20.05 because of the {heating} repair.\nOpen again after 20.05, {}",
+        );
+        assert_eq!(
+            template.fill(&["{reason}".to_owned()]),
+            "Office closed on {reason} because of {reason}.\nOpen again after {reason}, {}",
+        );
+        let template = NoticeTemplate::parse(2, "Lunch\nLunch menu: soup {\nsalad}").unwrap();
+        assert!(template.placeholders().is_empty());
+        assert_eq!(template.fill(&[]), "Lunch menu: soup {\nsalad}");
+        assert!(NoticeTemplate::parse(3, "Title only").is_err());
+        assert!(NoticeTemplate::parse(3, " \ntext").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode}};
+use crate::{
+    db::storage::Db, kernel::error::{Error, HandlerResult}, loc::loc, message::{edit_markup_message_or_send, escape},
+    states::MyDialogue, user::{user::User, user_role::UserRole},
+};
+use super::{content::NoticeContent, notice::{preview, NoticeState}, template::NoticeTemplate};
+///
+/// Shows the templates to be picked for the notice to the selected groups,
+/// Admin's also get the buttons to add, edit and delete the templates
+pub async fn enter(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, db: &Db) -> HandlerResult {
+    let user = db.user(&state.chat_id).await?;
+    let is_admin = user.has_role(&[UserRole::Admin]);
+    let templates = db.templates().await?;
+    let state = NoticeState { template_id: None, template_values: vec![], editing_template: None, ..state };
+    dialogue.update(state.clone()).await?;
+    let text = match templates.is_empty() {
+        true => loc("No templates yet"),
+        false => loc("Select template of the notice"),
+    };
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = templates.values()
+        .map(|template| {
+            let mut row = vec![InlineKeyboardButton::callback(template.title.clone(), format!("/NoticeTemplate/{}", template.id))];
+            if is_admin {
+                row.push(InlineKeyboardButton::callback("✏", format!("/NoticeTemplateEdit/{}", template.id)));
+                row.push(InlineKeyboardButton::callback("🗑", format!("/NoticeTemplateDelete/{}", template.id)));
+            }
+            row
+        })
+        .collect();
+    if is_admin {
+        rows.push(vec![InlineKeyboardButton::callback(format!("➕ {}", loc("Add template")), "/NoticeTemplateAdd")]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeContinue")]);
+    edit_markup_message_or_send(bot, msg, &InlineKeyboardMarkup::new(rows), &text).await
+}
+///
+/// Starts filling in the picked template, the template without placeholders goes to the preview at once
+pub async fn select(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: &Db) -> HandlerResult {
+    let template = template(db, id).await?;
+    let state = NoticeState { template_id: Some(id), template_values: vec![], ..state };
+    match template.placeholders().first() {
+        Some(placeholder) => {
+            dialogue.update(state).await?;
+            edit_markup_message_or_send(bot, msg, &back_markup(), &placeholder_hint(&template, placeholder)).await
+        }
+        None => {
+            let state = NoticeState { template_id: None, ..state };
+            preview(bot, dialogue, state, NoticeContent::Text(escape(&template.fill(&[]))), db).await
+        }
+    }
+}
+///
+/// Takes the value of the next placeholder typed by the sender,
+/// the template filled in completely goes to the preview
+pub async fn fill(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: Db) -> HandlerResult {
+    let template = template(&db, id).await?;
+    let placeholders = template.placeholders();
+    let Some(value) = msg.text() else {
+        let placeholder = placeholders.get(state.template_values.len()).map_or("", String::as_str);
+        bot.send_message(state.chat_id, placeholder_hint(&template, placeholder))
+            .parse_mode(ParseMode::Html)
+            .reply_markup(back_markup())
+            .await?;
+        return Ok(());
+    };
+    let mut values = state.template_values.clone();
+    values.push(value.trim().to_owned());
+    match placeholders.get(values.len()) {
+        Some(placeholder) => {
+            dialogue.update(NoticeState { template_values: values, ..state.clone() }).await?;
+            bot.send_message(state.chat_id, placeholder_hint(&template, placeholder))
+                .parse_mode(ParseMode::Html)
+                .reply_markup(back_markup())
+                .await?;
+            Ok(())
+        }
+        None => {
+            let content = NoticeContent::Text(escape(&template.fill(&values)));
+            let state = NoticeState { template_id: None, template_values: vec![], ..state };
+            preview(&bot, dialogue, state, content, &db).await
+        }
+    }
+}
+///
+/// Asks the Admin for the new template or the edited one, `id` is 0 for the new template
+pub async fn ask_edit(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: &Db) -> HandlerResult {
+    admin(db, state.chat_id).await?;
+    let mut text = loc("Type the title of the template on the first line and its text on the next lines, {name} in the text is asked when the template is used");
+    if id != 0 {
+        let template = template(db, id).await?;
+        text.push_str(&format!("\n\n{}\n{}", escape(&template.title), escape(&template.text)));
+    }
+    dialogue.update(NoticeState { editing_template: Some(id), ..state }).await?;
+    edit_markup_message_or_send(bot, msg, &back_markup(), &text).await
+}
+///
+/// Stores the template typed by the Admin
+pub async fn save(bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, id: u64, db: Db) -> HandlerResult {
+    let user = admin(&db, state.chat_id).await?;
+    let template = match NoticeTemplate::parse(id, msg.text().unwrap_or_default()) {
+        Ok(template) => template,
+        Err(err) => {
+            log::debug!("templates.save | {}", err);
+            bot.send_message(state.chat_id, loc("Type the title of the template on the first line and its text on the next lines, {name} in the text is asked when the template is used"))
+                .reply_markup(back_markup())
+                .await?;
+            return Ok(());
+        }
+    };
+    let id = db.update_template(template).await?;
+    log::info!("templates.save | Template {} saved by '{}' ({})", id, user.name, user.id);
+    bot.send_message(state.chat_id, loc("Template saved")).await?;
+    enter(&bot, &msg, dialogue, state, &db).await
+}
+///
+/// Removes the template by the Admin
+pub async fn delete(bot: &Bot, q: &CallbackQuery, dialogue: MyDialogue, state: NoticeState, id: u64, db: &Db) -> HandlerResult {
+    let user = admin(db, state.chat_id).await?;
+    let msg = match q.regular_message() {
+        Some(msg) => msg,
+        None => return Ok(()),
+    };
+    if let Some(template) = db.remove_template(id).await? {
+        log::info!("templates.delete | Template {} '{}' deleted by '{}' ({})", id, template.title, user.name, user.id);
+        bot.answer_callback_query(q.id.clone()).text(loc("Template deleted")).await?;
+    }
+    enter(bot, msg, dialogue, state, db).await
+}
+///
+///
+fn placeholder_hint(template: &NoticeTemplate, placeholder: &str) -> String {
+    format!("{} '{}'\n{}\n\n{}: {}", loc("Template"), escape(&template.title), escape(&template.text), loc("Type the value of"), escape(placeholder))
+}
+///
+///
+fn back_markup() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(loc("⏪Back"), "/NoticeTemplates"),
+    ]])
+}
+///
+///
+async fn template(db: &Db, id: u64) -> Result<NoticeTemplate, Error> {
+    db.templates().await?
+        .shift_remove(&id.to_string())
+        .ok_or_else(|| Error::NotFound(format!("templates.template | Template {}", id)))
+}
+///
+/// Returns the user if it's Admin, templates are managed by the Admins only
+async fn admin(db: &Db, chat_id: ChatId) -> Result<User, Error> {
+    let user = db.user(&chat_id).await?;
+    match user.has_role(&[UserRole::Admin]) {
+        true => Ok(user),
+        false => Err(Error::PermissionDenied(format!("{}, {}", user.name, loc("only Admin can edit the templates")))),
+    }
+}
@@ -2,18 +2,19 @@ use std::time::Duration;
 use chrono::Utc;
 use derive_more::From;
 use indexmap::IndexMap;
-use teloxide::{dispatching::{dialogue::{self, InMemStorage}, UpdateFilterExt, UpdateHandler }, prelude::*, types::User};
+use serde::{Deserialize, Serialize};
+use teloxide::{dispatching::{dialogue::{self, ErasedStorage}, UpdateFilterExt, UpdateHandler }, prelude::*, types::User};
 use tokio::time::sleep;
 use crate::{
-    db, help::HelpState, kernel::error::{HandlerResult, StrError}, links::{LinksMenu, LinksState}, menu::{self, MainMenu}, message::{edit_text_message_or_send, send_message_with_header}, notice::{self, NoticeMenu, NoticeState}, subscribe::subscribe::{SubscribeMenu, SubscribeState}, user::{
-        grant_access::{GrantAccessMenu, GrantAccessState}, request_access::RequestAccessState, user_role::UserRole
+    config::ScheduleConfig, db::storage::Db, help::HelpState, kernel::error::{Error, HandlerResult}, links::{LinksMenu, LinksState}, menu::{self, MainMenu}, message::{edit_text_message_or_send, reply_on_error, send_message_with_header, Priority}, notice::{history::{HistoryFrom, HistoryMenu, HistoryState}, notice::{self, NoticeMenu, NoticeState}, recurring::{RecurringMenu, RecurringState}, scheduled::{ScheduledMenu, ScheduledState}}, subscribe::{subscribe::{SubscribeMenu, SubscribeState}, subscription::Subscription}, user::{
+        grant_access::{GrantAccessMenu, GrantAccessState}, request_access::RequestAccessState, user::User as DbUser, user_role::UserRole
     }, BOT_NAME
 };
 use crate::loc::*;
-pub type MyDialogue = Dialogue<State, InMemStorage<State>>;
+pub type MyDialogue = Dialogue<State, ErasedStorage<State>>;
 ///
 /// FSM states
-#[derive(Debug, Clone, From)]
+#[derive(Debug, Clone, From, Serialize, Deserialize)]
 pub enum State {
    Start(StartState),   // initial state
    Main(MainState),     // main menu state
@@ -23,31 +24,36 @@ pub enum State {
    RequestAccess(RequestAccessState),    // in RequestAccess menu
    GrantAccess(GrantAccessState),    // in RequestAccess menu
    Help(HelpState),                     // In the Halp menu
+   Scheduled(ScheduledState),           // In the Scheduled notices menu
+   Recurring(RecurringState),           // In the Recurring notices menu
+   History(HistoryState),               // In the Notice history menu
 //    GeneralMessage(MessageState), // general commands, enter text of message to send
 }
 //
 //
 impl Default for State {
    fn default() -> Self {
-      Self::Start(StartState { restarted: true })
+      Self::Start(StartState { restarted: false })
    }
 }
+//
+//
+impl State {
+    ///
+    /// State of the user, which dialogue could not be restored after the bot restart
+    pub fn restarted() -> Self {
+        Self::Start(StartState { restarted: true })
+    }
+}
 ///
 /// 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct StartState {
    pub restarted: bool,
 }
-//
-//
-impl Default for StartState {
-    fn default() -> Self {
-        Self { restarted: false }
-    }
-}
 ///
 /// 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MainState {
    pub prev_state: StartState,
    pub chat_id: ChatId,
@@ -72,6 +78,9 @@ impl From<State> for MainState {
             State::RequestAccess(state) => MainState { prev_state: StartState::default(), chat_id: state.user.id },
             State::GrantAccess(state) => MainState { prev_state: StartState::default(), chat_id: state.user.id },
             State::Help(state) => MainState { prev_state: StartState::default(), chat_id: state.user.id },
+            State::Scheduled(state) => MainState { prev_state: StartState::default(), chat_id: state.chat_id },
+            State::Recurring(state) => MainState { prev_state: StartState::default(), chat_id: state.chat_id },
+            State::History(state) => MainState { prev_state: StartState::default(), chat_id: state.chat_id },
             // _ => MainState { prev_state: (), chat_id: value }
             // panic!("MainState.try_from | Illegal input state '{:?}'", value)
         }
@@ -84,16 +93,25 @@ pub fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'stat
         .branch(
             // Private message handler
             dptree::filter(|msg: Message| { msg.chat.is_private() })
-            .branch(dptree::case![State::Start(state)].endpoint(start))
-            .branch(dptree::case![State::Main(state)].endpoint(command))
-            .branch(dptree::case![State::Links(state)].endpoint(command))
-            .branch(dptree::case![State::Notice(state)].endpoint(notice::notice))
-            .branch(dptree::case![State::Subscribe(state)].endpoint(command))
+            .branch(dptree::case![State::Start(state)].endpoint(|bot: Bot, msg: Message, dialogue: MyDialogue, state: StartState, db: Db| async move {
+                reply_on_error(&bot.clone(), msg.chat.id, start(bot, msg, dialogue, state, db).await).await
+            }))
+            .branch(dptree::case![State::Notice(state)].endpoint(|bot: Bot, msg: Message, dialogue: MyDialogue, state: NoticeState, db: Db, schedule: ScheduleConfig| async move {
+                reply_on_error(&bot.clone(), msg.chat.id, notice::notice(bot, msg, dialogue, state, db, schedule).await).await
+            }))
+            .branch(
+                dptree::filter(|state: State| matches!(state, State::Main(_) | State::Links(_) | State::Subscribe(_) | State::Scheduled(_) | State::Recurring(_) | State::History(_)))
+                .endpoint(|bot: Bot, msg: Message, dialogue: MyDialogue, state: State, db: Db, schedule: ScheduleConfig| async move {
+                    reply_on_error(&bot.clone(), msg.chat.id, command(bot, msg, dialogue, state, db, schedule).await).await
+                })
+            )
             // .branch(dptree::case![State::GeneralMessage(state)].endpoint(crate::general::update_input))
         )
         .branch(dptree::entry().endpoint(chat_message_handler));
     let callback_query_handler = Update::filter_callback_query()
-        .endpoint(callback);
+        .endpoint(|bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: State, db: Db, schedule: ScheduleConfig| async move {
+            reply_on_error(&bot.clone(), ChatId::from(q.from.id), callback(bot, q, dialogue, state, db, schedule).await).await
+        });
     // let chat_member_update_handler = Update::filter_my_chat_member()// filter_chat_member()
     //     .branch(dptree::filter(|m: ChatMemberUpdated| {
     //         m.new_chat_member.is_member() //m.old_chat_member.is_left() && 
@@ -104,14 +122,14 @@ pub fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'stat
     //     })
     //     .endpoint(left_chat_member),
     // );
-    dialogue::enter::<Update, InMemStorage<State>, State, _>()
+    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
         .branch(message_handler)
         .branch(callback_query_handler)
         // .branch(chat_member_update_handler)
 }
 ///
 /// Callback on bot was added to chat
-pub async fn new_chat_member(chat_member: &ChatMemberUpdated) -> HandlerResult {
+pub async fn new_chat_member(chat_member: &ChatMemberUpdated, db: &Db) -> HandlerResult {
     let user = chat_member.old_chat_member.user.clone();
     let chat_id = chat_member.chat.id;
     let chat_id_string = chat_id.to_string();
@@ -123,23 +141,58 @@ pub async fn new_chat_member(chat_member: &ChatMemberUpdated) -> HandlerResult {
     let username = user.mention().unwrap_or_else(|| format!("{} ({})", user.full_name(), user.id));
     log::debug!("states.new_chat_member | MyChatMember(added): user {}, chat: {}", username, chat_name);
     // bot.send_message(chat_member.chat.id, format!("Welcome to {telegram_group_name} {username}!")).await?;
-    db::insert_subscription(&chat_id_string, chat_title).await?;
+    db.insert_subscription(&chat_id_string, chat_title).await?;
     Ok(())
 }
 ///
 /// Callback on bot was removed from chat
-pub async fn left_chat_member(chat_member: &ChatMemberUpdated) -> HandlerResult {
+pub async fn left_chat_member(bot: &Bot, chat_member: &ChatMemberUpdated, db: &Db) -> HandlerResult {
     let chat_name = format!("{} ({})", chat_member.chat.username().unwrap_or("-"), chat_member.chat.id);
     let user = &chat_member.old_chat_member.user;
     let username = user.mention().unwrap_or_else(|| format!("{} ({})", user.full_name(), user.id));
     log::debug!("states.left_chat_member | MyChatMember(removed):user {}, chat: {}", username, chat_name);
     // bot.send_message(chat_member.chat.id, format!("Goodbye {username}!")).await?;
-    db::remove_subscription(chat_member.chat.id).await?;
+    match db.remove_subscription(chat_member.chat.id).await? {
+        Some(group) => {
+            log::info!("states.left_chat_member | Group '{}' ({}) archived", group.title, chat_name);
+            notify_group_archived(bot, &group, db).await;
+        }
+        None => log::debug!("states.left_chat_member | Chat {} isn't registered as group", chat_name),
+    }
     Ok(())
 }
 ///
+/// Tells Moder's and Admin's of the group, the group went away.
+/// If group has no such members, all Moder's and Admin's are told
+async fn notify_group_archived(bot: &Bot, group: &Subscription, db: &Db) {
+    let users = match db.users().await {
+        Ok(users) => users,
+        Err(err) => {
+            log::warn!("states.notify_group_archived | Error: {:#?}", err);
+            return;
+        }
+    };
+    let moders: Vec<&DbUser> = users.values()
+        .filter(|user| user.has_role(&[UserRole::Admin, UserRole::Moder]))
+        .collect();
+    let group_moders: Vec<&DbUser> = moders.iter()
+        .filter(|user| group.members.contains_key(&user.id.to_string()))
+        .copied()
+        .collect();
+    let receivers = if group_moders.is_empty() { moders } else { group_moders };
+    let text = loc(format!(
+        "The bot was removed from the group '{}', the group is archived and hidden from the Notice and Subscribe menus. Add the bot to the group again to restore it with all {} members",
+        group.title, group.members.len(),
+    ));
+    for receiver in receivers {
+        if let Err(err) = send_message_with_header(bot, receiver.id, BOT_NAME, &text, Priority::Normal).await {
+            log::warn!("states.notify_group_archived | Error sending message to the '{}' ({}): {:#?}", receiver.name, receiver.id, err);
+        }
+    }
+}
+///
 /// Command | Start
-async fn start(bot: Bot, msg: Message, dialogue: MyDialogue, state: StartState) -> HandlerResult {
+async fn start(bot: Bot, msg: Message, dialogue: MyDialogue, state: StartState, db: Db) -> HandlerResult {
     // Extract user id
     let user = msg.from.clone();
     if user.is_none() {
@@ -152,12 +205,17 @@ async fn start(bot: Bot, msg: Message, dialogue: MyDialogue, state: StartState)
     let user = user.unwrap();
     let user_id = user.id;
     // Insert or update info about user
-    update_last_seen_full(&user).await?;
+    update_last_seen_full(&db, &user).await?;
     log::debug!("states.start | user {} ({})", user.full_name(), user_id);
     let cmd_raw = msg.text().unwrap_or_default();
     match cmd_raw {
-        "/start" | "/Start" => crate::states::enter(&bot, &msg, dialogue, MainState { prev_state: state, chat_id: msg.chat.id }).await,
+        "/start" | "/Start" => crate::states::enter(&bot, &msg, dialogue, MainState { prev_state: StartState { restarted: false }, chat_id: msg.chat.id }, &db).await,
         _ => {
+            // Dialogue of the user could not be restored after the bot restart
+            if state.restarted {
+                bot.send_message(msg.chat.id, loc("Sorry, the bot has been restarted")).await?;
+                dialogue.update(StartState { restarted: false }).await?;
+            }
             let text =  loc(format!("Please type '/Start' to begin"));
             bot.send_message(msg.chat.id, text)
                 .await?;
@@ -168,34 +226,34 @@ async fn start(bot: Bot, msg: Message, dialogue: MyDialogue, state: StartState)
 }
 ///
 /// 
-pub async fn enter(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: MainState) -> HandlerResult {
+pub async fn enter(bot: &Bot, msg: &Message, dialogue: MyDialogue, state: MainState, db: &Db) -> HandlerResult {
     dialogue.update(state).await?;
-    let user = db::user(&msg.chat.id).await?;
-    menu::enter(bot, msg, &user).await?;
+    let user = db.user(&msg.chat.id).await?;
+    menu::enter(bot, msg, &user, db).await?;
     Ok(())
 }
 
 ///
 /// 
-pub async fn reload(bot: Bot, msg: &Message, dialogue: MyDialogue, state: MainState) -> HandlerResult {
+pub async fn reload(bot: Bot, msg: &Message, dialogue: MyDialogue, state: MainState, db: &Db) -> HandlerResult {
     dialogue.update(state).await?;
-    let user = db::user(&msg.chat.id).await?;
-    menu::reload(&bot, msg, &user).await?;
+    let user = db.user(&msg.chat.id).await?;
+    menu::reload(&bot, msg, &user, db).await?;
     Ok(())
 }
-pub async fn exit(bot: Bot, msg: Message, dialogue: MyDialogue, state: MainState) -> HandlerResult {
+pub async fn exit(bot: Bot, msg: Message, dialogue: MyDialogue, state: MainState, db: &Db) -> HandlerResult {
     dialogue.update(state.prev_state).await?;
-    let user = db::user(&msg.chat.id).await?;
+    let user = db.user(&msg.chat.id).await?;
     menu::exit(&bot, &msg, &user).await?;
     Ok(())
 }
 ///
 /// Handles command from users
 // #[async_recursion]
-pub async fn command(bot: Bot, msg: Message, dialogue: MyDialogue, state: State) -> HandlerResult {
+pub async fn command(bot: Bot, msg: Message, dialogue: MyDialogue, state: State, db: Db, schedule: ScheduleConfig) -> HandlerResult {
     let dbgid = "states";
     let chat_id = msg.chat.id;
-    let user = db::user(&msg.chat.id).await?;
+    let user = db.user(&msg.chat.id).await?;
     let user_name = format!("{} {}", msg.chat.first_name().unwrap_or(""), msg.chat.first_name().unwrap_or(""));
     let cmd_raw = msg.text().unwrap_or_default();
     log::debug!("{}.command | Input '{}', from: {} ({:?})", dbgid, cmd_raw, user.name, msg.from);
@@ -212,47 +270,52 @@ pub async fn command(bot: Bot, msg: Message, dialogue: MyDialogue, state: State)
             };
             // Update FSM
             dialogue.update(new_state.to_owned()).await?;
-            crate::states::enter(&bot, &msg, dialogue, new_state).await?;
+            crate::states::enter(&bot, &msg, dialogue, new_state, &db).await?;
         }
         State::Main(main_state) => {
             let user_id = main_state.chat_id;
             log::debug!("{}.command | State: {:?}", dbgid, main_state);
             match cmd {
                 MainMenu::RequestAccess => {
-                    crate::user::request_access::enter(bot, msg, dialogue, RequestAccessState {prev_state: main_state, user: user}).await?;
+                    crate::user::request_access::enter(bot, msg, dialogue, RequestAccessState {prev_state: main_state, user}, db).await?;
                 }
                 MainMenu::Links(level) => {
                     if user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender, UserRole::Member]) {
-                        crate::links::enter(bot, msg, dialogue, LinksState {prev_state: main_state, level, child: IndexMap::new(), chat_id: user_id}).await?
+                        crate::links::enter(bot, msg, dialogue, LinksState {prev_state: main_state, level, child: IndexMap::new(), chat_id: user_id}, db).await?
                     } else {
-                        send_message_with_header(
-                            &bot, chat_id, BOT_NAME,
-                            &loc(format!("{}, you can't access shared resources according to your roles: \n{:?}", user.name, user.role))
-                        ).await?;
+                        return Err(Error::PermissionDenied(loc(format!("{}, you can't access shared resources according to your roles: \n{:?}", user.name, user.role))).into());
                     }
                 }
                 MainMenu::Notice => {
                     if user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender]) {
-                        crate::notice::enter(bot, msg, dialogue, NoticeState { prev_state: main_state, chat_id: user_id, ..Default::default()}).await?
+                        crate::notice::notice::enter(bot, msg, dialogue, NoticeState { prev_state: main_state, chat_id: user_id, ..Default::default()}, db).await?
                     } else {
-                        send_message_with_header(
-                            &bot, chat_id, BOT_NAME,
-                            &loc(format!("{}, you can't send notice's according to your roles: \n{:?}", user.name, user.role)),
-                        ).await?;
+                        return Err(Error::PermissionDenied(loc(format!("{}, you can't send notice's according to your roles: \n{:?}", user.name, user.role))).into());
                     }
                 }
                 MainMenu::Subscribe => {
                     if user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender, UserRole::Member]) {
-                        crate::subscribe::subscribe::enter(bot, msg, dialogue, SubscribeState { prev_state: main_state, chat_id: user_id, ..Default::default() }).await?
+                        crate::subscribe::subscribe::enter(bot, msg, dialogue, SubscribeState { prev_state: main_state, chat_id: user_id, ..Default::default() }, db).await?
+                    } else {
+                        return Err(Error::PermissionDenied(loc(format!("{}, you can't subscribe for notice's according to your roles: \n{:?}", user.name, user.role))).into());
+                    }
+                }
+                MainMenu::Scheduled => {
+                    if user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender]) {
+                        crate::notice::scheduled::enter(bot, msg, dialogue, ScheduledState { prev_state: main_state, chat_id: user_id }, db, &schedule).await?
                     } else {
-                        send_message_with_header(
-                            &bot, chat_id, BOT_NAME,
-                            &loc(format!("{}, you can't subscribe for notice's according to your roles: \n{:?}", user.name, user.role)),
-                        ).await?;
+                        return Err(Error::PermissionDenied(loc(format!("{}, you can't send notice's according to your roles: \n{:?}", user.name, user.role))).into());
+                    }
+                }
+                MainMenu::Recurring => {
+                    if user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender]) {
+                        crate::notice::recurring::enter(bot, msg, dialogue, RecurringState { prev_state: main_state, chat_id: user_id }, db).await?
+                    } else {
+                        return Err(Error::PermissionDenied(loc(format!("{}, you can't send notice's according to your roles: \n{:?}", user.name, user.role))).into());
                     }
                 }
                 MainMenu::Help => crate::help::enter(bot, msg, dialogue, HelpState { prev_state: main_state, user }).await?,
-                MainMenu::Done => crate::states::exit(bot, msg, dialogue, main_state).await?,
+                MainMenu::Done => crate::states::exit(bot, msg, dialogue, main_state, &db).await?,
                 MainMenu::Unknown => {
                     log::debug!("{}.command | user: {} ({}), Unknown command {}", dbgid, user_name, user_id, cmd_raw);
                     // Report about a possible restart and loss of context
@@ -268,7 +331,7 @@ pub async fn command(bot: Bot, msg: Message, dialogue: MyDialogue, state: State)
                     }
                     sleep(Duration::from_secs(2)).await;
                     dialogue.update(main_state.prev_state).await?;
-                    crate::states::reload(bot.clone(), &msg, dialogue, main_state).await?
+                    crate::states::reload(bot.clone(), &msg, dialogue, main_state, &db).await?
                 }
             };
         }
@@ -276,36 +339,51 @@ pub async fn command(bot: Bot, msg: Message, dialogue: MyDialogue, state: State)
             // let user_id = links_state.user_id;
             log::debug!("{}.command | State: {:?}", dbgid, links_state);
             dialogue.update(links_state.prev_state).await?;
-            crate::states::reload(bot.clone(), &msg, dialogue, links_state.prev_state).await?
+            crate::states::reload(bot.clone(), &msg, dialogue, links_state.prev_state, &db).await?
         }
         State::Notice(notice_state) => {
             // let user_id = notice_state.user_id;
             log::debug!("{}.command | State: {:?}", dbgid, notice_state);
             dialogue.update(notice_state.prev_state).await?;
-            crate::states::reload(bot.clone(), &msg, dialogue, notice_state.prev_state).await?
+            crate::states::reload(bot.clone(), &msg, dialogue, notice_state.prev_state, &db).await?
         }
         State::Subscribe(subscribe_state) => {
             // let user_id = subscribe_state.user_id;
             log::debug!("{}.command | State: {:?}", dbgid, subscribe_state);
             dialogue.update(subscribe_state.prev_state).await?;
-            crate::states::reload(bot.clone(), &msg, dialogue, subscribe_state.prev_state).await?
+            crate::states::reload(bot.clone(), &msg, dialogue, subscribe_state.prev_state, &db).await?
         }
         State::RequestAccess(ra_state) => {
             // let user_id = subscribe_state.user_id;
             log::debug!("{}.command | State: {:?}", dbgid, ra_state);
             dialogue.update(ra_state.prev_state).await?;
-            crate::states::reload(bot.clone(), &msg, dialogue, ra_state.prev_state).await?
+            crate::states::reload(bot.clone(), &msg, dialogue, ra_state.prev_state, &db).await?
         }
         State::GrantAccess(ga_state) => {
             // let user_id = subscribe_state.user_id;
             log::debug!("{}.command | State: {:?}", dbgid, ga_state);
             // dialogue.update(ga_state.prev_state).await?;
-            // crate::states::reload(bot.clone(), &msg, dialogue, ga_state.prev_state).await?
+            // crate::states::reload(bot.clone(), &msg, dialogue, ga_state.prev_state, &db).await?
         }
         State::Help(help_state) => {
             log::debug!("{}.command | State: {:?}", dbgid, help_state);
             dialogue.update(help_state.prev_state).await?;
-            crate::states::reload(bot.clone(), &msg, dialogue, help_state.prev_state).await?
+            crate::states::reload(bot.clone(), &msg, dialogue, help_state.prev_state, &db).await?
+        }
+        State::Scheduled(scheduled_state) => {
+            log::debug!("{}.command | State: {:?}", dbgid, scheduled_state);
+            dialogue.update(scheduled_state.prev_state).await?;
+            crate::states::reload(bot.clone(), &msg, dialogue, scheduled_state.prev_state, &db).await?
+        }
+        State::Recurring(recurring_state) => {
+            log::debug!("{}.command | State: {:?}", dbgid, recurring_state);
+            dialogue.update(recurring_state.prev_state).await?;
+            crate::states::reload(bot.clone(), &msg, dialogue, recurring_state.prev_state, &db).await?
+        }
+        State::History(history_state) => {
+            log::debug!("{}.command | State: {:?}", dbgid, history_state);
+            dialogue.update(history_state.prev_state).await?;
+            crate::states::reload(bot.clone(), &msg, dialogue, history_state.prev_state, &db).await?
         }
     }
     Ok(())
@@ -320,27 +398,27 @@ pub async fn chat_message_handler(bot: Bot, msg: Message) -> HandlerResult {
     };
     log::debug!("states.chat_message_handler | user: {} ({}), message {:?}", user_name, user_id, msg.text());
     if let Some(input) = msg.text() {
-        match input.get(..5).unwrap_or_default() {
-            "/chat" => {
-                let chat_id = msg.chat.id;
-                let text = format!("Chat id={}", chat_id);
-                bot.send_message(chat_id, text).await?;
-            }
-            _ => (),
+        if input.get(..5).unwrap_or_default() == "/chat" {
+            let chat_id = msg.chat.id;
+            let text = format!("Chat id={}", chat_id);
+            bot.send_message(chat_id, text).await?;
         }
     }
     Ok(())
 }
-async fn grant_access(dbgid: &str, bot: &Bot, q: &CallbackQuery, dialogue: &MyDialogue, state: &State, input: &str) -> HandlerResult {
-    match GrantAccessMenu::parse(&input, 0) {
+///
+/// Handles GrantAccess menu callbacks,
+/// returns false if input isn't a role granting command, so it is to be handled by the current state
+async fn grant_access(dbgid: &str, bot: &Bot, q: &CallbackQuery, dialogue: &MyDialogue, state: &State, input: &str, db: &Db) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match GrantAccessMenu::parse(input, 0) {
         GrantAccessMenu::Role((role, chat_id)) => {
-            let to_user = db::user(&chat_id).await?;
+            let to_user = db.user(&chat_id).await?;
             let text = format!("Selected role '{:?}' for user '{}'", role, to_user.name);
-            edit_text_message_or_send(&bot, q.regular_message().unwrap(), &text).await?;
+            edit_text_message_or_send(bot, q.regular_message().unwrap(), &text).await?;
             log::debug!("{}.callback | Granting role '{:?}' to user {}", dbgid, role, to_user.name);
             let state = GrantAccessState { prev_state: Box::new(state.to_owned()), user: to_user, role: Some(role) };
-            crate::user::grant_access::enter(bot.clone(), q.regular_message().unwrap().to_owned(), dialogue.clone(), state).await?;
-            Ok(())
+            crate::user::grant_access::enter(bot.clone(), q.regular_message().unwrap().to_owned(), dialogue.clone(), state, db.clone()).await?;
+            Ok(true)
         }
         GrantAccessMenu::Done => {
             let granted_user = match &state {
@@ -350,35 +428,46 @@ async fn grant_access(dbgid: &str, bot: &Bot, q: &CallbackQuery, dialogue: &MyDi
                 },
                 _ => "-".to_owned(),
             };
-            let info = format!("{}.callback | Grant role canceled for user {}", dbgid, granted_user);
-            log::info!("{}", info);
+            log::info!("{}.callback | Grant role canceled for user {}", dbgid, granted_user);
             let text = format!("Canceled role granting for user '{}'", granted_user);
-            edit_text_message_or_send(&bot, q.regular_message().unwrap(), &text).await?;
-            Err(StrError(info).into())
+            edit_text_message_or_send(bot, q.regular_message().unwrap(), &text).await?;
+            Ok(false)
         }
         GrantAccessMenu::Unknown(cmd) => {
-            let info = format!("{}.callback | Grant role Unknown cmd: {}", dbgid, cmd);
-            log::debug!("{}", info);
-            Err(StrError(info).into())
+            log::debug!("{}.callback | Grant role Unknown cmd: {}", dbgid, cmd);
+            Ok(false)
         }
     }
 }
 ///
 /// Handles command callbacks
-pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: State) -> HandlerResult {
+pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: State, db: Db, schedule: ScheduleConfig) -> HandlerResult {
     let dbgid = "states";
     let chat_id = ChatId::from(q.from.id);
-    let user = db::user(&chat_id).await?;
+    let input = q.data.to_owned().unwrap_or_default();
+    // Acknowledgements and answers come from the notice copies, the recipient may be in any state or even not registered
+    if crate::notice::ack::callback(&bot, &q, &input, &db).await? {
+        return Ok(());
+    }
+    if crate::notice::rsvp::callback(&bot, &q, &input, &db).await? {
+        return Ok(());
+    }
+    let user = db.user(&chat_id).await?;
     let user_name = q.from.full_name();
     // Determine the language of the user
-    let input = q.data.to_owned().unwrap_or_default();
     log::debug!("{}.callback | State: {:?}, User {} ({}) Input: {}", dbgid, state, user_name, chat_id, input);
-    if let Ok(()) = grant_access(&dbgid, &bot, &q, &dialogue, &state, &input).await {
+    if grant_access(dbgid, &bot, &q, &dialogue, &state, &input, &db).await? {
         return Ok(());
     }
     match state {
         State::Start(state) => {
             log::debug!("{}.callback | State::Start > state: {:#?}", dbgid, state);
+            if state.restarted {
+                let text = format!("{}\n{}", loc("Sorry, the bot has been restarted"), loc("Please type '/Start' to begin"));
+                bot.send_message(chat_id, text).await?;
+                dialogue.update(StartState { restarted: false }).await?;
+                return Ok(());
+            }
             log::debug!("{}.callback | State::Start > Not implemented, return", dbgid);
         }
         State::RequestAccess(state) => {
@@ -386,8 +475,8 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
             log::debug!("{}.callback | State::RequestAccess > Not implemented, return", dbgid);
         }
         State::GrantAccess(state) => {
+            // Role granting commands are already handled above
             log::debug!("{}.callback | State::GrantAccess > state: {:#?}", dbgid, state);
-            grant_access(dbgid, &bot, &q, &dialogue, &State::GrantAccess(state), &input).await?;
             return Ok(());
         }
         State::Main(state) => {
@@ -398,25 +487,33 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
             match cmd {
                 MainMenu::RequestAccess => {
                     let state = RequestAccessState { prev_state: state, user };
-                    crate::user::request_access::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::user::request_access::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 MainMenu::Links(level) => {
                     let state = LinksState {prev_state: state, level, child: IndexMap::new(), chat_id };
-                    crate::links::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::links::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 MainMenu::Notice => {
                     let state = NoticeState { prev_state: state, chat_id, ..Default::default() };
-                    crate::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 MainMenu::Subscribe => {
                     let state = SubscribeState { prev_state: state, chat_id, ..Default::default() };
-                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                MainMenu::Scheduled => {
+                    let state = ScheduledState { prev_state: state, chat_id };
+                    crate::notice::scheduled::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db, &schedule).await?
+                }
+                MainMenu::Recurring => {
+                    let state = RecurringState { prev_state: state, chat_id };
+                    crate::notice::recurring::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 MainMenu::Help => {
                     let state = HelpState { prev_state: state, user };
                     crate::help::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
                 }
-                MainMenu::Done => crate::states::exit(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?,
+                MainMenu::Done => crate::states::exit(bot, q.regular_message().unwrap().to_owned(), dialogue, state, &db).await?,
                 MainMenu::Unknown => {
                     log::debug!("{}.callback | State::Main > user: {} ({}), Unknown command {}", dbgid, user_name, chat_id, input);
                 }
@@ -437,9 +534,9 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
                         child: state.child,
                         chat_id: state.chat_id,
                     };
-                    crate::links::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::links::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
-                LinksMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state).await?,
+                LinksMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state, &db).await?,
             }
         }
         State::Notice(state) => {
@@ -450,19 +547,55 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
             log::debug!("{}.callback | State::Notice > Cmd: {:?}", dbgid, cmd);
             match cmd {
                 NoticeMenu::Group(group) => {
-                    log::debug!("{}.callback | State::Notice > Notice Will send to the: '{}' group", dbgid, group);
+                    log::debug!("{}.callback | State::Notice > Group '{}' selected / unselected", dbgid, group);
+                    let mut groups = state.groups;
+                    match groups.iter().position(|selected| *selected == group) {
+                        Some(index) => { groups.remove(index); }
+                        None => groups.push(group),
+                    }
                     let state = NoticeState {
                         prev_state: state.prev_state,
-                        group,
+                        groups,
                         chat_id: state.chat_id,
+                        ..Default::default()
                     };
-                    crate::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                NoticeMenu::Continue => {
+                    log::debug!("{}.callback | State::Notice > Notice will be sent to the: {:?}", dbgid, state.groups);
+                    let state = NoticeState { groups_selected: true, ..state };
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                NoticeMenu::Send | NoticeMenu::Schedule | NoticeMenu::Repeat | NoticeMenu::Edit | NoticeMenu::Cancel | NoticeMenu::RequireAck | NoticeMenu::Escalation | NoticeMenu::Priority(_) | NoticeMenu::Rsvp | NoticeMenu::RsvpDeadline | NoticeMenu::Ttl => {
+                    crate::notice::notice::confirm(bot, q, dialogue, state, cmd, db, &schedule).await?
+                }
+                NoticeMenu::Reports => crate::notice::notice::reports(&bot, q.regular_message().unwrap(), &state, &db, &schedule).await?,
+                NoticeMenu::Report(id) => crate::notice::notice::report(&bot, q.regular_message().unwrap(), &state, id, &db, &schedule).await?,
+                NoticeMenu::Correct(id) => crate::notice::notice::ask_correction(&bot, q.regular_message().unwrap(), dialogue, state, id, &db).await?,
+                NoticeMenu::Recall(id) => crate::notice::notice::ask_recall(&bot, q.regular_message().unwrap(), &state, id, &db).await?,
+                NoticeMenu::RecallConfirm(id) => crate::notice::notice::recall(&bot, &q, &state, id, &db, &schedule).await?,
+                NoticeMenu::Templates => crate::notice::templates::enter(&bot, q.regular_message().unwrap(), dialogue, state, &db).await?,
+                NoticeMenu::Template(id) => crate::notice::templates::select(&bot, q.regular_message().unwrap(), dialogue, state, id, &db).await?,
+                NoticeMenu::TemplateAdd => crate::notice::templates::ask_edit(&bot, q.regular_message().unwrap(), dialogue, state, 0, &db).await?,
+                NoticeMenu::TemplateEdit(id) => crate::notice::templates::ask_edit(&bot, q.regular_message().unwrap(), dialogue, state, id, &db).await?,
+                NoticeMenu::TemplateDelete(id) => crate::notice::templates::delete(&bot, &q, dialogue, state, id, &db).await?,
+                NoticeMenu::Senders => crate::notice::senders::enter(&bot, q.regular_message().unwrap(), &state, &db).await?,
+                NoticeMenu::SendersGroup(group) => crate::notice::senders::group(&bot, q.regular_message().unwrap(), &state, &group, &db).await?,
+                NoticeMenu::SenderRole(group, role) => crate::notice::senders::toggle_role(&bot, q.regular_message().unwrap(), &state, &group, role, &db).await?,
+                NoticeMenu::SenderUser(group, user_id) => crate::notice::senders::toggle_user(&bot, q.regular_message().unwrap(), &state, &group, user_id, &db).await?,
+                NoticeMenu::History => {
+                    let state = HistoryState { prev_state: state.prev_state, chat_id: state.chat_id, from: HistoryFrom::Notice, group: String::new(), page: 0 };
+                    crate::notice::history::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                NoticeMenu::Groups => {
+                    let state = NoticeState { prev_state: state.prev_state, chat_id: state.chat_id, ..Default::default() };
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 NoticeMenu::Unknown(text) => {
                     log::debug!("{}.callback | State::Notice > Unknown command received: '{}'", dbgid, text);
-                    crate::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
-                NoticeMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state).await?,
+                NoticeMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state, &db).await?,
             }
         }
         State::Subscribe(state) => {
@@ -480,18 +613,50 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
                         chat_id: state.chat_id,
                         user: q.from.clone()
                     };
-                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                SubscribeMenu::History => {
+                    let state = HistoryState { prev_state: state.prev_state, chat_id: state.chat_id, from: HistoryFrom::Subscribe, group: String::new(), page: 0 };
+                    crate::notice::history::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
                 SubscribeMenu::Unknown(text) => {
                     log::debug!("{}.callback | State::Subscribe > Unknown command received: '{}'", dbgid, text);
-                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state).await?
+                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
                 }
-                SubscribeMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state).await?,
+                SubscribeMenu::Done => crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state, &db).await?,
             }            
         }
         State::Help(state) => {
             log::debug!("{}.callback | State::Help > state: {:#?}", dbgid, state);
-            crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state).await?
+            crate::states::reload(bot.clone(), q.regular_message().unwrap(), dialogue, state.prev_state, &db).await?
+        }
+        State::Scheduled(state) => {
+            log::debug!("{}.callback | State::Scheduled > state: {:#?}", dbgid, state);
+            let cmd = ScheduledMenu::parse(&input, 0);
+            log::debug!("{}.callback | State::Scheduled > Cmd: {:?}", dbgid, cmd);
+            crate::notice::scheduled::select(bot, q, dialogue, state, cmd, db, &schedule).await?
+        }
+        State::Recurring(state) => {
+            log::debug!("{}.callback | State::Recurring > state: {:#?}", dbgid, state);
+            let cmd = RecurringMenu::parse(&input, 0);
+            log::debug!("{}.callback | State::Recurring > Cmd: {:?}", dbgid, cmd);
+            crate::notice::recurring::select(bot, q, dialogue, state, cmd, db, &schedule).await?
+        }
+        State::History(state) => {
+            log::debug!("{}.callback | State::History > state: {:#?}", dbgid, state);
+            let cmd = HistoryMenu::parse(&input, 0);
+            log::debug!("{}.callback | State::History > Cmd: {:?}", dbgid, cmd);
+            match (cmd, state.from) {
+                (HistoryMenu::Done, HistoryFrom::Notice) => {
+                    let state = NoticeState { prev_state: state.prev_state, chat_id: state.chat_id, ..Default::default() };
+                    crate::notice::notice::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                (HistoryMenu::Done, HistoryFrom::Subscribe) => {
+                    let state = SubscribeState { prev_state: state.prev_state, chat_id: state.chat_id, user: q.from.clone(), ..Default::default() };
+                    crate::subscribe::subscribe::enter(bot, q.regular_message().unwrap().to_owned(), dialogue, state, db).await?
+                }
+                (cmd, _) => crate::notice::history::select(bot, q, dialogue, state, cmd, db, &schedule).await?,
+            }
         }
         // State::GeneralMessage(state) => {
         //     log::debug!("{}.callback | State::GeneralMessage > receiver: {}", state.receiver);
@@ -501,7 +666,7 @@ pub async fn callback(bot: Bot, q: CallbackQuery, dialogue: MyDialogue, state: S
 }
 ///
 /// Update or insert user
-async fn update_last_seen_full(user: &User) -> Result<(), String> {
+async fn update_last_seen_full(db: &Db, user: &User) -> Result<(), Error> {
     log::debug!("states.update_last_seen_full | user: {} ({})", user.full_name(), user.id);
     let user_id = user.id.0;
     // Collect info about the new user and store in database
@@ -511,6 +676,6 @@ async fn update_last_seen_full(user: &User) -> Result<(), String> {
     let contact = if let Some(username) = &user.username {
         format!("{}", username)
     } else {String::from("-")};
-    db::user_insert(user_id, name, Some(contact), None, Some(Utc::now()), &[UserRole::Guest]).await?;
+    db.user_insert(user_id, name, Some(contact), None, Some(Utc::now()), &[UserRole::Guest]).await?;
     Ok(())
 }
@@ -1,7 +1,8 @@
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, User, UserId}};
 use crate::{
-    db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MainState, MyDialogue},
+    db::storage::Db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MainState, MyDialogue},
 };
 
 use super::subscription::Subscriptions;
@@ -10,6 +11,7 @@ use super::subscription::Subscriptions;
 #[derive(Debug, Clone, PartialEq)]
 pub enum SubscribeMenu {
    Group(String),   // Selected group to subscribe on
+   History,         // Browse the notices of the subscribed groups
    Unknown(String), // Unknown command received
    Done,            // Exit menu
 }
@@ -22,6 +24,7 @@ impl SubscribeMenu {
             ("/", "done" | "Done") => Self::Done,
             ("/", "back" | "Back") => Self::Done,
             ("/", "exit" | "Exit") => Self::Done,
+            ("/", "SubscribeHistory") => Self::History,
             ("/", input) => Self::Group(input.to_owned()),
             (_, _) => Self::Unknown(s.to_owned()),
         }
@@ -29,7 +32,7 @@ impl SubscribeMenu {
 }
 ///
 /// 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscribeState {
     pub prev_state: MainState,  // Where to go on Back btn
     pub group: String,          // Group id to be noticed
@@ -45,51 +48,35 @@ impl Default for SubscribeState {
 }
 ///
 ///  
-pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: SubscribeState) -> HandlerResult {
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: SubscribeState, db: Db) -> HandlerResult {
     let user_id = state.chat_id;
     let user_name = state.user.username.clone().unwrap_or(state.user.full_name());
     log::debug!("subscribe.enter | state: {:#?}", state);
-    let mut subscriptions =  match db::subscriptions().await {
+    if !state.group.is_empty() {
+        subscribe(&db, &state.group, user_id, &user_name).await?;
+        log::debug!("subscribe.enter | Subscription '{}' ({}) for group '{}' - updated", user_name, user_id, state.group);
+    }
+    let subscriptions =  match db.subscriptions().await {
         Ok(groups) => groups,
         Err(err) => {
             log::warn!("subscribe.enter | Groups is empty, error: {:#?}", err);
             IndexMap::new()
         }
     };
-    if !state.group.is_empty() {
-        // let group_title = groups.get(&state.group).map_or(state.group.clone(), |group| group.title.clone());
-        subscribe(&mut subscriptions, &state.group, user_id, &user_name).await?;
-        log::debug!("subscribe.enter | Subscription '{}' ({}) for group '{}' - updated", user_name, user_id, state.group);
-    }
     let text = format!("Select group to subscribe / unsubscribe");
     dialogue.update(state.clone()).await?;
     view(&bot, &msg, &state, &subscriptions, text).await?;
     Ok(())
 }
 ///
-/// 
-pub async fn subscribe(subscriptions: &mut Subscriptions, group: &str, user_id: ChatId, user_name: &str) -> HandlerResult {
-    if let Some(group) = subscriptions.get_mut(group) {
-        let user_id_str = &user_id.to_string();
-        match group.members.get(user_id_str) {
-            Some(_) => {
-                log::debug!("subscribe.subscribe | Removing subscription '{}' ({}) from group '{}'", user_name, user_id, group.title);
-                if let None = group.members.shift_remove(user_id_str) {
-                    log::debug!("subscribe.subscribe | Error removing subscription '{}' ({}) from group '{}' - key not found", user_name, user_id, group.title);
-                }
-            }
-            None => {
-                log::debug!("subscribe.subscribe | Adding subscription '{}' ({}) to the group '{}' ", user_name, user_id, group.title);
-                let user = crate::db::user(&user_id.into()).await?;
-                if let Some(origin) = group.members.insert(user_id_str.to_owned(), user) {
-                    log::warn!("subscribe.subscribe | Error adding subscription '{}' ({}) to the group '{}' - olready exists", user_name, user_id, group.title);
-                    group.members.insert(user_id_str.to_owned(), origin);
-                }
-            }
-        }
-        db::update_subscriptions(subscriptions).await?;
-    } else {
-        log::warn!("subscribe.subscribe | Group '{}' not found in the subscriptions: {:#?}", group, subscriptions);
+/// Subscribes the user to the group, or unsubscribes if the user is already subscribed,
+/// the membership is switched by the storage in one step
+pub async fn subscribe(db: &Db, group: &str, user_id: ChatId, user_name: &str) -> HandlerResult {
+    let user = db.user(&user_id).await?;
+    match db.toggle_member(group, user).await? {
+        Some(true) => log::debug!("subscribe.subscribe | Subscription '{}' ({}) added to the group '{}'", user_name, user_id, group),
+        Some(false) => log::debug!("subscribe.subscribe | Subscription '{}' ({}) removed from the group '{}'", user_name, user_id, group),
+        None => log::warn!("subscribe.subscribe | Group '{}' not found or archived in the subscriptions", group),
     }
     Ok(())
 }
@@ -97,7 +84,7 @@ pub async fn subscribe(subscriptions: &mut Subscriptions, group: &str, user_id:
 /// 
 pub async fn view(bot: &Bot, msg: &Message, state: &SubscribeState, groups: &Subscriptions, text: impl Into<String>) -> HandlerResult {
     let user_id = state.chat_id;
-    let markup = markup(&groups, user_id).await?;
+    let markup = markup(groups, user_id).await?;
     crate::message::edit_markup_message_or_send(bot, msg, &markup, &text.into()).await
     // bot.edit_message_text(msg.chat.id, msg.id, text)
     //     // .edit_message_media(user_id, message_id, media)
@@ -112,6 +99,7 @@ async fn markup(groups: &Subscriptions, user_id: ChatId) -> Result<InlineKeyboar
     let _ = user_id;
     let mut buttons: Vec<InlineKeyboardButton> = groups
         .iter()
+        .filter(|(_, group)| group.is_active())
         .map(|(group_id, group)| {
             InlineKeyboardButton::callback(
                 if group.members.contains_key(&user_id.to_string()) {
@@ -122,6 +110,7 @@ async fn markup(groups: &Subscriptions, user_id: ChatId) -> Result<InlineKeyboar
                 format!("/{}", group_id),
         )})
         .collect();
+    buttons.push(InlineKeyboardButton::callback(loc("History"), "/SubscribeHistory"));
     let button_back = InlineKeyboardButton::callback(
         loc("⏪Back"), // "⏪Back"
         format!("/back")
@@ -1,6 +1,6 @@
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use crate::user::user::User;
+use crate::user::{user::User, user_role::UserRole};
 ///
 /// 
 pub type Subscriptions = IndexMap<String, Subscription>;
@@ -12,4 +12,132 @@ pub struct Subscription {
    pub id: Option<String>,
    pub title: String,
    pub members: IndexMap<String, User>,
+   /// Timestamp (RFC 3339) the bot was removed from the group chat,
+   /// archived group is hidden from the menus and isn't noticed
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub archived: Option<String>,
+   /// Senders allowed to notice the group, edited by the Moders
+   #[serde(default, skip_serializing_if = "Senders::is_empty")]
+   pub senders: Senders,
+}
+///
+/// Users and roles allowed to notice the group, every Sender is allowed if both are empty
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Senders {
+    /// Ids of the allowed users
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<UserRole>,
+}
+//
+//
+impl Senders {
+    ///
+    /// Returns true if the group isn't restricted
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.roles.is_empty()
+    }
+    ///
+    /// Allows the user if it isn't allowed yet, disallows otherwise
+    pub fn toggle_user(&mut self, user_id: &str) {
+        match self.users.iter().position(|id| id == user_id) {
+            Some(index) => { self.users.remove(index); }
+            None => self.users.push(user_id.to_owned()),
+        }
+    }
+    ///
+    /// Allows the role if it isn't allowed yet, disallows otherwise
+    pub fn toggle_role(&mut self, role: UserRole) {
+        match self.roles.iter().position(|allowed| *allowed == role) {
+            Some(index) => { self.roles.remove(index); }
+            None => self.roles.push(role),
+        }
+    }
+}
+//
+//
+impl Subscription {
+    ///
+    /// Returns true if the group isn't archived
+    pub fn is_active(&self) -> bool {
+        self.archived.is_none()
+    }
+    ///
+    /// Returns true if the user can notice the group: the group is active and the user is allowed by the `senders`,
+    /// Admin's can notice every active group
+    pub fn can_notice(&self, user: &User) -> bool {
+        self.is_active() && (
+            self.senders.is_empty()
+            || user.has_role(&[UserRole::Admin])
+            || self.senders.users.contains(&user.id.to_string())
+            || user.has_role(&self.senders.roles)
+        )
+    }
+}
+///
+/// Returns the active groups of the `selected` the user isn't allowed to notice, all of them if the user has no role to notice
+pub fn denied_groups(subscriptions: &Subscriptions, user: &User, selected: &[String]) -> Vec<String> {
+    let can_send = user.has_role(&[UserRole::Admin, UserRole::Moder, UserRole::Sender]);
+    selected.iter()
+        .filter(|group_id| subscriptions.get(*group_id).is_some_and(|group| group.is_active() && !(can_send && group.can_notice(user))))
+        .cloned()
+        .collect()
+}
+///
+/// Returns titles of the groups joined by comma, id is used for the group not found
+pub fn group_titles(subscriptions: &Subscriptions, groups: &[String]) -> String {
+    groups.iter()
+        .map(|group| subscriptions.get(group).map_or(group.clone(), |subscription| subscription.title.clone()))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+//
+//
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use teloxide::types::ChatId;
+    use crate::user::{user::User, user_role::UserRole};
+    use super::{Senders, Subscription};
+    ///
+    ///
+    fn user(id: i64, role: UserRole) -> User {
+        User { id: ChatId(id), name: format!("user {}", id), contact: None, address: None, subscriptions: vec![], last_seen: String::new(), role: vec![role] }
+    }
+    #[test]
+    fn can_notice() {
+        let (hr, facility, moder, admin) = (user(1, UserRole::Sender), user(2, UserRole::Sender), user(3, UserRole::Moder), user(4, UserRole::Admin));
+        let mut group = Subscription { id: None, title: "All staff".to_owned(), members: IndexMap::new(), archived: None, senders: Senders::default() };
+        assert!([&hr, &facility, &moder, &admin].iter().all(|user| group.can_notice(user)), "group isn't restricted");
+        group.senders.toggle_user("1");
+        assert!(group.can_notice(&hr));
+        assert!(!group.can_notice(&facility));
+        assert!(!group.can_notice(&moder));
+        assert!(group.can_notice(&admin));
+        group.senders.toggle_role(UserRole::Moder);
+        assert!(group.can_notice(&moder));
+        group.senders.toggle_user("1");
+        assert!(!group.can_notice(&hr));
+        assert_eq!(group.senders, Senders { users: vec![], roles: vec![UserRole::Moder] });
+        group.senders.toggle_role(UserRole::Moder);
+        assert!(group.senders.is_empty());
+        group.archived = Some("2024-05-20T08:00:00+00:00".to_owned());
+        assert!(!group.can_notice(&admin), "archived group isn't noticed");
+    }
+    ///
+    /// Groups restricted after the notice was scheduled and every group of the user lost the role are denied
+    #[test]
+    fn denied_groups() {
+        let group = |senders: Senders| Subscription { id: None, title: "Group".to_owned(), members: IndexMap::new(), archived: None, senders };
+        let mut groups = IndexMap::new();
+        groups.insert("ALL".to_owned(), group(Senders::default()));
+        groups.insert("MODERS".to_owned(), group(Senders { users: vec![], roles: vec![UserRole::Moder] }));
+        groups.insert("ARCHIVED".to_owned(), Subscription { archived: Some("2024-05-20T08:00:00+00:00".to_owned()), ..group(Senders { users: vec!["3".to_owned()], roles: vec![] }) });
+        let selected = ["ALL".to_owned(), "MODERS".to_owned(), "ARCHIVED".to_owned(), "UNKNOWN".to_owned()];
+        assert_eq!(super::denied_groups(&groups, &user(1, UserRole::Sender), &selected), vec!["MODERS"]);
+        assert!(super::denied_groups(&groups, &user(2, UserRole::Moder), &selected).is_empty());
+        assert_eq!(super::denied_groups(&groups, &user(3, UserRole::Member), &selected), vec!["ALL", "MODERS"]);
+    }
 }
@@ -1,6 +1,7 @@
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use teloxide::{payloads::SendMessageSetters, prelude::Requester, types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, Message, ParseMode}, Bot};
-use crate::{db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MyDialogue, StartState, State}};
+use crate::{db::storage::Db, kernel::error::HandlerResult, loc::{loc, LocaleTag}, states::{MyDialogue, StartState, State}};
 use super::{user::User, user_role::{UserRole, UserRoles}};
 ///
 /// RequestAccess menu
@@ -62,7 +63,7 @@ impl GrantAccessMenu {
 }
 ///
 /// State holding values rquired for grant access process
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrantAccessState {
     /// Where to go on Back btn
     pub prev_state: Box<State>,
@@ -102,11 +103,11 @@ impl From<State> for GrantAccessState {
 }
 ///
 ///  
-pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: GrantAccessState) -> HandlerResult {
+pub async fn enter(bot: Bot, _msg: Message, dialogue: MyDialogue, state: GrantAccessState, db: Db) -> HandlerResult {
     let user_id = state.user.id;
     let user_name = state.user.name.clone();
     log::debug!("request_access.enter | state: {:#?}", state);
-    let roles =  match db::user_roles(user_id).await {
+    let roles =  match db.user_roles(user_id).await {
         Ok(roles) => roles,
         Err(err) => {
             log::warn!("request_access.enter | Error: {:#?}", err);
@@ -119,7 +120,7 @@ pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: GrantAcc
             log::debug!("request_access.enter | Moder granting a role: {:?}...", role);
             let mut to_user = state.user;
             to_user.add_role(role.to_owned());
-            db::user_update(to_user).await?;
+            db.user_update(to_user).await?;
             let title = roles.get(&role.to_string()).map_or(role.to_string(), |role| role.title.clone());
             let text = format!("{}, role '{}' granted for you!", user_name, title);
             dialogue.update(*state.prev_state).await?;
@@ -132,7 +133,7 @@ pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: GrantAcc
         None => {
             log::debug!("request_access.enter | User '{}' requested access...", user_name);
             let text = format!("Select a Role to be granted for user '{}'", user_name);
-            let users = db::users(None::<&str>).await?;
+            let users = db.users().await?;
             // Moderator avaliable in the DB
             let moders: Vec<User> = users.into_iter().filter_map(|(_, user)| {
                 if user.role.contains(&UserRole::Moder) {
@@ -152,7 +153,8 @@ pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: GrantAcc
                         .parse_mode(ParseMode::Html)
                         .await?;
             
-                    return Err(format!("request_access.enter | No moderators found to grant access for User '{}'", user_name).into())
+                    log::warn!("request_access.enter | No moderators found to grant access for User '{}'", user_name);
+                    return Ok(())
                 }
             }
         
@@ -164,7 +166,7 @@ pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: GrantAcc
 /// Menu buttons to select a role to be granted
 pub async fn view(bot: &Bot, state: &GrantAccessState, roles: &UserRoles, text: impl Into<String>, moder: &User) -> HandlerResult {
     let _ = state.user.id;
-    let markup = markup(&roles, &state.user).await?;
+    let markup = markup(roles, &state.user).await?;
     bot.send_message(moder.id, text)
         .reply_markup(markup)
         .parse_mode(ParseMode::Html)
@@ -176,10 +178,10 @@ pub async fn view(bot: &Bot, state: &GrantAccessState, roles: &UserRoles, text:
 async fn markup(roles: &UserRoles, to_user: &User) -> Result<InlineKeyboardMarkup, String> {
     let mut buttons: Vec<InlineKeyboardButton> = roles
         .iter()
-        .map(|(role_id, role)| {
+        .map(|(_role_id, role)| {
             InlineKeyboardButton::callback(
                 role.title.clone(),
-                format!("/GrantRole/{}:{}", role.role.to_string(), to_user.id),
+                format!("/GrantRole/{}:{}", role.role, to_user.id),
         )})
         .collect();
     let button_back = InlineKeyboardButton::callback(
@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 use teloxide::prelude::*;
-use crate::{kernel::error::HandlerResult, message::edit_text_message_or_send, states::{MainState, MyDialogue, StartState, State}, user::{grant_access::{self, GrantAccessState}, user::User}};
+use crate::{db::storage::Db, kernel::error::HandlerResult, message::edit_text_message_or_send, states::{MainState, MyDialogue, StartState, State}, user::{grant_access::{self, GrantAccessState}, user::User}};
 // ///
 // /// RequestAccess menu
 // #[derive(Debug, Clone, PartialEq)]
@@ -35,7 +36,7 @@ use crate::{kernel::error::HandlerResult, message::edit_text_message_or_send, st
 // }
 ///
 /// State holding values rquired for request access process
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestAccessState {
     pub prev_state: MainState,      // Where to go on Back btn
     pub user: User,                 // User doing request access
@@ -62,7 +63,7 @@ impl Default for RequestAccessState {
 }
 ///
 /// New user (state.user_id) requested access
-pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: RequestAccessState) -> HandlerResult {
+pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: RequestAccessState, db: Db) -> HandlerResult {
     // let user_id = state.user.id;
     let user_name = state.user.name.clone();
     log::debug!("request_access.enter | state: {:#?}", state);
@@ -71,6 +72,6 @@ pub async fn enter(bot: Bot, msg: Message, dialogue: MyDialogue, state: RequestA
     let text = format!("{}, Access requested", user_name);
     edit_text_message_or_send(&bot, &msg, &text).await?;
     let state = GrantAccessState { prev_state: Box::new(State::Start(StartState::default())), user: state.user, role: None };
-    grant_access::enter(bot, msg, dialogue, state).await?;
+    grant_access::enter(bot, msg, dialogue, state, db).await?;
     Ok(())
 }
@@ -22,6 +22,7 @@ pub struct User {
 impl User {
     ///
     /// Returns User new instance
+    #[allow(unused)]
     pub fn new(
         id: ChatId,
         name: String,
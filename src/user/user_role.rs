@@ -30,14 +30,14 @@ pub enum UserRole {
 }
 //
 //
-impl ToString for UserRole {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            UserRole::Admin => "admin".to_owned(),
-            UserRole::Moder => "moder".to_owned(),
-            UserRole::Sender => "sender".to_owned(),
-            UserRole::Member => "member".to_owned(),
-            UserRole::Guest => "guest".to_owned(),
+            UserRole::Admin => write!(f, "admin"),
+            UserRole::Moder => write!(f, "moder"),
+            UserRole::Sender => write!(f, "sender"),
+            UserRole::Member => write!(f, "member"),
+            UserRole::Guest => write!(f, "guest"),
         }
     }
 }